@@ -1,8 +1,16 @@
 // benches/modem_benchmarks.rs
-use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use hsf_softmodem::dsp::carrier::{CarrierDetector, CarrierDetectorConfig};
 use hsf_softmodem::dsp::fsk::*;
+use hsf_softmodem::dsp::fsk_detector::FSKDetector;
 use hsf_softmodem::dsp::qam_modem::*;
 use hsf_softmodem::dsp::qam::*;
+use hsf_softmodem::dsp::equalizer::{EqualizerConfig, FSEqualizer};
+use hsf_softmodem::dsp::filters::{BiquadBank, BiquadFilter};
+use hsf_softmodem::dsp::goertzel::GoertzelBank;
+use hsf_softmodem::dsp::oscillator::{ComplexNCO, SineInterpolation, NCO};
+use hsf_softmodem::dsp::simd::SimdLevel;
+use num_complex::Complex32;
 
 fn bench_fsk_modulation(c: &mut Criterion) {
     let mut group = c.benchmark_group("fsk_modulation");
@@ -46,24 +54,131 @@ fn bench_qam_modulation(c: &mut Criterion) {
     group.finish();
 }
 
+/// Whole receivers on a second of line audio, at the SIMD level they
+/// detect
 fn bench_demodulation(c: &mut Criterion) {
     let mut group = c.benchmark_group("demodulation");
+    let data: Vec<u8> = (0..40u8).map(|i| i.wrapping_mul(37)).collect();
     
-    // Generate test audio
+    // Bell 103, every detector
     let mode = FSKMode::Bell103Originate;
     let mut modulator = FSKModulator::new(mode, 300.0, 8000.0);
-    let audio = modulator.modulate_bytes(b"TEST DATA");
+    let mut audio = modulator.modulate_bytes(&data);
+    audio.resize(8000, 0.0);
+    group.throughput(Throughput::Elements(audio.len() as u64));
+    for detector in FSKDetector::ALL {
+        let mut demodulator = FSKDemodulator::with_detector(mode, 300.0, 8000.0, detector);
+        group.bench_function(format!("Bell 103 receiver/{}", detector), |b| {
+            b.iter(|| demodulator.demodulate_bytes(black_box(&audio)));
+        });
+    }
     
-    let mut demodulator = FSKDemodulator::new(mode, 300.0, 8000.0);
+    // V.22bis, equalizer and all
+    let carrier = QAMMode::V22bis.carrier_freq_originate();
+    let mut modulator = QAMModulator::new(QAMMode::V22bis, carrier, 8000.0);
+    let mut audio = modulator.modulate_bytes(&data);
+    audio.resize(8000, 0.0);
     group.throughput(Throughput::Elements(audio.len() as u64));
-    group.bench_function("FSK Demod", |b| {
+    group.bench_function("V.22bis receiver", |b| {
+        b.iter_batched_ref(
+            || QAMDemodulator::new(QAMMode::V22bis, carrier, 8000.0),
+            |demodulator| demodulator.demodulate_bytes(black_box(&audio)),
+            BatchSize::SmallInput,
+        );
+    });
+    
+    // Carrier detect on the same line
+    let mut detector = CarrierDetector::new(600.0, 1800.0, 8000.0, CarrierDetectorConfig::default());
+    group.bench_function("V.22bis carrier detector", |b| {
+        b.iter(|| detector.process_block(black_box(&audio)));
+    });
+    
+    group.finish();
+}
+
+/// The vectorized hot loops at every level this CPU runs, scalar first
+fn bench_simd(c: &mut Criterion) {
+    let mut group = c.benchmark_group("simd");
+    
+    let audio: Vec<f32> = (0..8000).map(|n| (n as f32 * 0.37).sin() * 0.5).collect();
+    let channels = 16;
+    let frames: Vec<f32> = audio[..1000].iter().flat_map(|&x| std::iter::repeat_n(x, channels)).collect();
+    let mut output = vec![0.0; frames.len()];
+    let dtmf = [697.0, 770.0, 852.0, 941.0, 1209.0, 1336.0, 1477.0, 1633.0];
+    let symbols: Vec<Complex32> = (0..1000).map(|n| Complex32::new((n % 3) as f32 - 1.0, (n % 5) as f32 - 2.0)).collect();
+    
+    for level in SimdLevel::supported() {
+        group.throughput(Throughput::Elements(frames.len() as u64));
+        let filters: Vec<BiquadFilter> = (0..channels).map(|i| BiquadFilter::bandpass(1000.0 + 50.0 * i as f32, 400.0, 8000.0)).collect();
+        let mut bank = BiquadBank::new(&filters);
+        bank.set_simd_level(level).unwrap();
+        group.bench_function(format!("16-channel biquad/{}", level), |b| {
+            b.iter(|| bank.process_frames(black_box(&frames), &mut output));
+        });
+        
+        group.throughput(Throughput::Elements(audio.len() as u64));
+        let mut goertzel = GoertzelBank::new(&dtmf, 8000.0, 205);
+        goertzel.set_simd_level(level).unwrap();
+        group.bench_function(format!("DTMF Goertzel bank/{}", level), |b| {
+            b.iter(|| {
+                goertzel.reset();
+                goertzel.process_block(black_box(&audio));
+            });
+        });
+        
+        group.throughput(Throughput::Elements(symbols.len() as u64));
+        let mut equalizer = FSEqualizer::new(EqualizerConfig::default(), QAMMode::V22bis.slicer());
+        equalizer.set_simd_level(level).unwrap();
+        group.bench_function(format!("NLMS equalizer/{}", level), |b| {
+            b.iter(|| {
+                for pair in symbols.chunks_exact(2) {
+                    black_box(equalizer.equalize(pair, None));
+                }
+            });
+        });
+    }
+    
+    group.finish();
+}
+
+/// Table NCO, both lookups, against `sin()` per sample
+fn bench_oscillator(c: &mut Criterion) {
+    let mut group = c.benchmark_group("oscillator");
+    let mut block = vec![0.0; 8000];
+    group.throughput(Throughput::Elements(block.len() as u64));
+    
+    for interpolation in [SineInterpolation::Nearest, SineInterpolation::Linear] {
+        let mut nco = NCO::new(1209.0, 8000.0, 0.7);
+        nco.set_interpolation(interpolation);
+        group.bench_function(format!("NCO block/{:?}", interpolation), |b| {
+            b.iter(|| nco.generate(black_box(&mut block)));
+        });
+    }
+    
+    let mut phasors = vec![Complex32::new(0.0, 0.0); 8000];
+    let mut nco = ComplexNCO::new(1200.0, 8000.0);
+    group.bench_function("ComplexNCO phasor", |b| {
+        b.iter(|| phasors.iter_mut().for_each(|p| *p = nco.next_phasor()));
+        black_box(&phasors);
+    });
+    
+    let omega = 2.0 * std::f32::consts::PI * 1209.0 / 8000.0;
+    group.bench_function("sin() per sample", |b| {
         b.iter(|| {
-            demodulator.demodulate(black_box(&audio))
+            let mut phase = 0.0f32;
+            for sample in block.iter_mut() {
+                *sample = 0.7 * phase.sin();
+                phase += omega;
+                if phase >= 2.0 * std::f32::consts::PI {
+                    phase -= 2.0 * std::f32::consts::PI;
+                }
+            }
+            black_box(&block);
         });
     });
     
     group.finish();
 }
 
-criterion_group!(benches, bench_fsk_modulation, bench_qam_modulation, bench_demodulation);
+criterion_group!(benches, bench_fsk_modulation, bench_qam_modulation, bench_demodulation, bench_simd, bench_oscillator);
 criterion_main!(benches);
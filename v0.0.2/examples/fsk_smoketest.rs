@@ -1,9 +1,6 @@
-// src/main.rs - DSP test
-mod audio;
-mod dsp;
-
-use dsp::fsk::*;
-use dsp::SAMPLE_RATE;
+// examples/fsk_smoketest.rs - DSP test
+use hsf_softmodem::dsp::fsk::*;
+use hsf_softmodem::dsp::SAMPLE_RATE;
 
 fn main() {
     env_logger::init();
@@ -10,6 +10,12 @@
 //! It uses the existing `windows` crate in your project.
 //! No new dependencies.
 
+use std::env;
+use std::ffi::OsStr;
+use std::os::windows::ffi::OsStrExt;
+use std::thread;
+use std::time::{Duration, Instant};
+
 use windows::core::{PCWSTR, Result as WinResult};
 
 use windows::Win32::Foundation::{CloseHandle, HANDLE};
@@ -47,7 +53,7 @@ fn open_pipe(pipe_base_name: &str) -> WinResult<HANDLE> {
     let handle = unsafe {
         CreateFileW(
             PCWSTR(wide.as_ptr()),
-            (FILE_GENERIC_READ.0 | FILE_GENERIC_WRITE.0) as u32,
+            FILE_GENERIC_READ.0 | FILE_GENERIC_WRITE.0,
             FILE_SHARE_READ | FILE_SHARE_WRITE,
             None,
             OPEN_EXISTING,
@@ -160,6 +166,6 @@ fn main() -> windows::core::Result<()> {
         print_response_bytes(&bytes);
     }
 
-    unsafe { CloseHandle(handle); }
+    unsafe { CloseHandle(handle)?; }
     Ok(())
 }
@@ -0,0 +1,56 @@
+// examples/qam_tap.rs
+//
+// Run V.22bis over a line preset with the receiver tap enabled and dump the
+// constellation/eye plots and raw CSV.
+//
+//   cargo run --example qam_tap -- [short_loop|long_loop|...] [out_dir]
+
+use hsf_softmodem::dsp::qam::QAMMode;
+use hsf_softmodem::dsp::qam_modem::{QAMDemodulator, QAMModulator};
+use hsf_softmodem::sim::channel::{ChannelSimulator, Impairments};
+use hsf_softmodem::sim::line::LinePreset;
+use hsf_softmodem::sim::plot::write_tap_files;
+use std::path::PathBuf;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let preset_name = args.first().map(String::as_str).unwrap_or("short_loop");
+    let out_dir = PathBuf::from(args.get(1).map(String::as_str).unwrap_or("tap_out"));
+
+    let preset = match preset_name {
+        "flat" => LinePreset::Flat,
+        "short_loop" => LinePreset::ShortLoop,
+        "long_loop" => LinePreset::LongLoop,
+        "loaded_loop" => LinePreset::LoadedLoop,
+        "satellite_hop" => LinePreset::SatelliteHop,
+        _ => {
+            eprintln!("unknown preset '{}'", preset_name);
+            std::process::exit(2);
+        }
+    };
+
+    let mut modulator = QAMModulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+    let mut demodulator = QAMDemodulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+    let mut line = ChannelSimulator::new(Impairments::preset(preset), 8000.0);
+
+    let data: Vec<u8> = (0..600u32).map(|i| (i * 37 + 11) as u8).collect();
+    let audio = line.process(&modulator.modulate_bytes(&data));
+
+    demodulator.enable_tap(1200);
+    demodulator.demodulate(&audio);
+    let tap = demodulator.take_tap().expect("tap was enabled");
+
+    // Leave out the first 100 symbols while loops settle
+    match write_tap_files(&tap, &out_dir, "v22bis", 100) {
+        Ok(()) => println!(
+            "{} symbols, EVM {:.1}% -> {}",
+            tap.len(),
+            tap.evm(100).unwrap_or(f32::NAN) * 100.0,
+            out_dir.display()
+        ),
+        Err(e) => {
+            eprintln!("failed to write plots: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -128,13 +128,14 @@ impl WasapiAudioEngine {
                         }
                         Ok(AudioCommand::Capture) => {
                             // Read one nominal buffer worth of samples from the ring.
+                            // Like a device, capture always delivers a full
+                            // buffer: the line is silent past what was played.
                             let frames = (cfg.sample_rate as u64
                                 * cfg.buffer_duration_ms as u64
                                 / 1000) as usize;
                             let mut buf =
                                 vec![0.0f32; frames.saturating_mul(cfg.channels as usize).max(1)];
-                            let read = playback_buffer.read(&mut buf);
-                            buf.truncate(read);
+                            playback_buffer.read(&mut buf);
                             let _ = event_tx.send(AudioEvent::CapturedSamples(buf));
                         }
                         Err(_) => {
@@ -34,9 +34,9 @@ impl<T: Copy + Default> RingBuffer<T> {
         
         unsafe {
             let buffer_ptr = self.buffer.as_ptr() as *mut T;
-            for i in 0..to_write {
+            for (i, &sample) in data[..to_write].iter().enumerate() {
                 let pos = (write_pos + i) % self.capacity;
-                *buffer_ptr.add(pos) = data[i];
+                *buffer_ptr.add(pos) = sample;
             }
         }
         
@@ -59,9 +59,9 @@ impl<T: Copy + Default> RingBuffer<T> {
         
         unsafe {
             let buffer_ptr = self.buffer.as_ptr();
-            for i in 0..to_read {
+            for (i, sample) in data[..to_read].iter_mut().enumerate() {
                 let pos = (read_pos + i) % self.capacity;
-                data[i] = *buffer_ptr.add(pos);
+                *sample = *buffer_ptr.add(pos);
             }
         }
         
@@ -0,0 +1,43 @@
+// src/bin/modemsim.rs
+//
+// Scenario-driven two-modem simulator.
+//
+//   cargo run --bin modemsim -- scenarios/v22bis_noisy_loop.toml
+//
+// See src/sim/scenario.rs for the scenario format.
+
+use hsf_softmodem::sim::link::run_scenario;
+use hsf_softmodem::sim::scenario::Scenario;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        eprintln!("usage: modemsim <scenario.toml> [<scenario.toml> ...]");
+        return ExitCode::from(2);
+    }
+
+    let mut failed = false;
+
+    for (i, path) in args.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        let report = Scenario::load(path).and_then(|scenario| run_scenario(&scenario));
+        match report {
+            Ok(report) => print!("{}", report),
+            Err(e) => {
+                eprintln!("modemsim: {}", e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
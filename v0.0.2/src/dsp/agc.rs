@@ -0,0 +1,160 @@
+// src/dsp/agc.rs
+use super::*;
+
+/// AGC settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AGCConfig {
+    /// Level the AGC drives its output to
+    pub target_dbm0: f32,
+    /// Time constant for rising input levels
+    pub attack_ms: f32,
+    /// Time constant for falling input levels
+    pub decay_ms: f32,
+    /// Largest gain applied (also the smallest, as attenuation)
+    pub max_gain_db: f32,
+    /// Hold the gain while the receiver is in data mode, so level changes
+    /// don't disturb the equalizer and slicer once trained
+    pub freeze_during_data: bool,
+}
+
+impl Default for AGCConfig {
+    fn default() -> Self {
+        Self {
+            target_dbm0: FULL_SCALE_DBM0,
+            attack_ms: 5.0,
+            decay_ms: 100.0,
+            max_gain_db: 50.0,
+            freeze_during_data: false,
+        }
+    }
+}
+
+impl AGCConfig {
+    /// Defaults with a specific output level
+    pub fn with_target(target_dbm0: f32) -> Self {
+        Self {
+            target_dbm0,
+            ..Self::default()
+        }
+    }
+}
+
+/// How far (in power) the short-term level must rise above the tracked
+/// level before the fast attack kicks in (3 dB)
+const ATTACK_THRESHOLD: f32 = 2.0;
+
+/// Feed-forward automatic gain control.
+///
+/// A short-term mean square (attack time constant) follows the input; the
+/// tracked level follows that at the attack rate on jumps of more than 3 dB
+/// and at the decay rate otherwise, so modulation peaks don't bias it. The
+/// tracked level keeps running while the gain is frozen, so the reported
+/// receive level stays accurate.
+#[derive(Debug, Clone)]
+pub struct AGC {
+    config: AGCConfig,
+    sample_rate: f32,
+    target_power: f32,
+    attack: f32,
+    decay: f32,
+    min_gain: f32,
+    max_gain: f32,
+    short_term: f32,
+    power: f32,
+    gain: f32,
+    data_mode: bool,
+}
+
+impl AGC {
+    pub fn new(config: AGCConfig, sample_rate: f32) -> Self {
+        let target_power = dbm0_to_power(config.target_dbm0);
+        let mut agc = Self {
+            config,
+            sample_rate,
+            target_power,
+            attack: 0.0,
+            decay: 0.0,
+            min_gain: 1.0,
+            max_gain: 1.0,
+            // Start as if the input were already at the target level
+            short_term: target_power,
+            power: target_power,
+            gain: 1.0,
+            data_mode: false,
+        };
+        agc.set_config(config);
+        agc
+    }
+
+    pub fn config(&self) -> &AGCConfig {
+        &self.config
+    }
+
+    /// Change settings without losing the tracked level
+    pub fn set_config(&mut self, config: AGCConfig) {
+        let coefficient = |ms: f32| 1.0 - (-1000.0 / (ms.max(0.01) * self.sample_rate)).exp();
+        let max_gain = 10f32.powf(config.max_gain_db.abs() / 20.0);
+
+        self.attack = coefficient(config.attack_ms);
+        self.decay = coefficient(config.decay_ms);
+        self.min_gain = 1.0 / max_gain;
+        self.max_gain = max_gain;
+        self.target_power = dbm0_to_power(config.target_dbm0);
+        self.config = config;
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        self.short_term += self.attack * (input * input - self.short_term);
+        let coeff = if self.short_term > self.power * ATTACK_THRESHOLD {
+            self.attack
+        } else {
+            self.decay
+        };
+        self.power += coeff * (self.short_term - self.power);
+
+        if !self.is_frozen() {
+            let power = self.power.max(f32::MIN_POSITIVE);
+            self.gain = (self.target_power / power)
+                .sqrt()
+                .clamp(self.min_gain, self.max_gain);
+        }
+
+        input * self.gain
+    }
+
+    /// Process block of samples
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.process(*i);
+        }
+    }
+
+    /// Tell the AGC whether the receiver is in data mode; freezes the gain
+    /// if the configuration asks for it
+    pub fn set_data_mode(&mut self, data: bool) {
+        self.data_mode = data;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.data_mode && self.config.freeze_during_data
+    }
+
+    /// Current gain in dB
+    pub fn gain_db(&self) -> f32 {
+        20.0 * self.gain.log10()
+    }
+
+    /// Input level in dBm0
+    pub fn level_dbm0(&self) -> f32 {
+        power_to_dbm0(self.power)
+    }
+
+    pub fn reset(&mut self) {
+        self.short_term = self.target_power;
+        self.power = self.target_power;
+        self.gain = 1.0;
+        self.data_mode = false;
+    }
+}
@@ -0,0 +1,131 @@
+// src/dsp/bits.rs
+//
+// Bits packed into bytes over caller-owned storage, for the streaming
+// `*_into` calls that don't allocate. Bits go LSB first, the order a
+// character's data bits go to line.
+
+/// Bits appended into a caller's byte buffer
+#[derive(Debug)]
+pub struct BitWriter<'a> {
+    bytes: &'a mut [u8],
+    len: usize,
+}
+
+impl<'a> BitWriter<'a> {
+    /// Empty writer over `bytes`; room for `8 * bytes.len()` bits
+    pub fn new(bytes: &'a mut [u8]) -> Self {
+        Self { bytes, len: 0 }
+    }
+
+    /// Append a bit; false (and nothing written) when full
+    #[inline]
+    pub fn push(&mut self, bit: bool) -> bool {
+        if self.is_full() {
+            return false;
+        }
+        let (byte, shift) = (self.len / 8, self.len % 8);
+        if shift == 0 {
+            self.bytes[byte] = 0;
+        }
+        if bit {
+            self.bytes[byte] |= 1 << shift;
+        }
+        self.len += 1;
+        true
+    }
+
+    /// Bits written
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        8 * self.bytes.len()
+    }
+
+    /// Bits that still fit
+    pub fn remaining(&self) -> usize {
+        self.capacity() - self.len
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| (self.bytes[index / 8] >> (index % 8)) & 1 == 1)
+    }
+
+    /// Bytes holding the bits written; unused high bits of the last one are 0
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len.div_ceil(8)]
+    }
+
+    /// Start over at the first bit
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Read back what was written
+    pub fn reader(&self) -> BitReader<'_> {
+        BitReader::with_len(self.bytes, self.len)
+    }
+}
+
+/// Bits read from a byte buffer, as an iterator
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    len: usize,
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    /// Every bit of `bytes`
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_len(bytes, 8 * bytes.len())
+    }
+
+    /// The first `len` bits of `bytes`
+    pub fn with_len(bytes: &'a [u8], len: usize) -> Self {
+        Self {
+            bytes,
+            len: len.min(8 * bytes.len()),
+            pos: 0,
+        }
+    }
+
+    /// Bits read so far
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Bits left to read
+    pub fn remaining(&self) -> usize {
+        self.len - self.pos
+    }
+}
+
+impl Iterator for BitReader<'_> {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<bool> {
+        if self.pos == self.len {
+            return None;
+        }
+        let bit = (self.bytes[self.pos / 8] >> (self.pos % 8)) & 1 == 1;
+        self.pos += 1;
+        Some(bit)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining(), Some(self.remaining()))
+    }
+}
+
+impl ExactSizeIterator for BitReader<'_> {}
@@ -1,46 +1,212 @@
 // src/dsp/carrier.rs
-// Warning Fix: Removed unused 'use super::*;'
-use super::goertzel::GoertzelDetector;
+use super::*;
+use super::fir::Window;
+use super::goertzel::GoertzelBank;
 
-/// Detects presence of carrier signal
+/// Band energy is measured over blocks of this length; it is also the
+/// resolution of the S9/S10 timing and, as the DFT length, sets the bin
+/// spacing (100 Hz)
+const BLOCK_MS: f32 = 10.0;
+
+/// Taper on each block before the DFT. Its sidelobes (-58 dB) keep a loud
+/// tone outside the band from leaking into the band's bins.
+const BLOCK_WINDOW: Window = Window::Blackman;
+
+/// Carrier detector settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CarrierDetectorConfig {
+    /// Band level above which carrier counts as present
+    pub on_dbm0: f32,
+    /// Band level below which carrier counts as gone; the gap to `on_dbm0`
+    /// keeps a marginal line from chattering
+    pub off_dbm0: f32,
+    /// How long the level must stay above `on_dbm0` before carrier is
+    /// reported (Hayes S9)
+    pub response_ms: f32,
+    /// How long the level must stay below `off_dbm0` before carrier is
+    /// reported lost (Hayes S10)
+    pub loss_delay_ms: f32,
+}
+
+impl Default for CarrierDetectorConfig {
+    /// V.22 thresholds (on at -43, off at -48 dBm0) and the Hayes S9/S10
+    /// defaults of 0.6 s and 1.4 s
+    fn default() -> Self {
+        Self {
+            on_dbm0: -43.0,
+            off_dbm0: -48.0,
+            response_ms: 600.0,
+            loss_delay_ms: 1400.0,
+        }
+    }
+}
+
+impl CarrierDetectorConfig {
+    /// Defaults with timing from S9 and S10, both in tenths of a second
+    pub fn from_s_registers(s9: u8, s10: u8) -> Self {
+        Self {
+            response_ms: s9 as f32 * 100.0,
+            loss_delay_ms: s10 as f32 * 100.0,
+            ..Self::default()
+        }
+    }
+}
+
+/// Change in carrier state reported by `CarrierDetector::process_block`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CarrierEvent {
+    Detected,
+    Lost,
+}
+
+/// Detects presence of carrier signal.
+///
+/// Measures the energy in the receive band over 10 ms blocks and applies
+/// separate on/off thresholds, each of which must hold for its own
+/// qualifying time before the reported state changes. The band energy is
+/// the sum of the DFT bins inside it, from a Goertzel bank run once per
+/// windowed block.
 pub struct CarrierDetector {
-// ... (Rest of the file is unchanged)
-    detector: GoertzelDetector,
-    energy_threshold: f32,
+    config: CarrierDetectorConfig,
+    sample_rate: f32,
+    bins: GoertzelBank,
+    window: Vec<f32>,
+    // Band power per unit of summed bin energy
+    scale: f32,
+    on_power: f32,
+    off_power: f32,
+    response_samples: u64,
+    loss_samples: u64,
+
+    // Windowed samples of the block in progress
+    block: Vec<f32>,
+    power: f32,
+    // Samples the level has disagreed with the reported state
+    pending: u64,
     present: bool,
 }
 
 impl CarrierDetector {
-    pub fn new(
-        carrier_freq: f32,
-        sample_rate: f32,
-        block_size: usize,
-        threshold_db: f32,
-    ) -> Self {
-        Self {
-            detector: GoertzelDetector::new(carrier_freq, sample_rate, block_size),
-            // Convert dB threshold to linear power
-            energy_threshold: 10f32.powf(threshold_db / 10.0), 
+    /// Detector for energy between `low` and `high` Hz
+    pub fn new(low: f32, high: f32, sample_rate: f32, config: CarrierDetectorConfig) -> Self {
+        let block_len = ((BLOCK_MS * sample_rate / 1000.0) as usize).max(1);
+        let window: Vec<f32> = (0..block_len).map(|n| BLOCK_WINDOW.coefficient(n, block_len)).collect();
+
+        // Every bin from `low` to `high`, at least one
+        let spacing = sample_rate / block_len as f32;
+        let first = (low / spacing).ceil().max(1.0) as usize;
+        let last = ((high / spacing).floor() as usize).max(first);
+        let freqs: Vec<f32> = (first..=last).map(|k| k as f32 * spacing).collect();
+
+        // Parseval: the positive-frequency bins of a windowed sine hold a
+        // quarter of block_len * sum(w^2) * A^2, and its power is A^2 / 2
+        let window_energy: f32 = window.iter().map(|w| w * w).sum();
+
+        let mut detector = Self {
+            config,
+            sample_rate,
+            bins: GoertzelBank::new(&freqs, sample_rate, block_len),
+            scale: 2.0 / (block_len as f32 * window_energy),
+            window,
+            on_power: 0.0,
+            off_power: 0.0,
+            response_samples: 0,
+            loss_samples: 0,
+            block: Vec::with_capacity(block_len),
+            power: 0.0,
+            pending: 0,
             present: false,
+        };
+        detector.set_config(config);
+        detector
+    }
+
+    pub fn config(&self) -> &CarrierDetectorConfig {
+        &self.config
+    }
+
+    /// Change thresholds and timing without losing the current state
+    pub fn set_config(&mut self, config: CarrierDetectorConfig) {
+        let samples = |ms: f32| (ms.max(0.0) * self.sample_rate / 1000.0).round() as u64;
+
+        self.on_power = dbm0_to_power(config.on_dbm0);
+        self.off_power = dbm0_to_power(config.off_dbm0.min(config.on_dbm0));
+        self.response_samples = samples(config.response_ms);
+        self.loss_samples = samples(config.loss_delay_ms);
+        self.config = config;
+    }
+
+    /// Process single sample; returns true if the carrier state changed
+    #[inline]
+    pub fn process_sample(&mut self, sample: f32) -> bool {
+        self.block.push(sample * self.window[self.block.len()]);
+        if self.block.len() < self.window.len() {
+            return false;
         }
+        self.bins.reset();
+        self.bins.process_block(&self.block);
+        self.block.clear();
+        let energy: f32 = (0..self.bins.len()).map(|k| self.bins.magnitude_squared(k)).sum();
+        self.power = energy * self.scale;
+
+        let disagrees = if self.present {
+            self.power < self.off_power
+        } else {
+            self.power > self.on_power
+        };
+        if !disagrees {
+            self.pending = 0;
+            return false;
+        }
+
+        self.pending += self.window.len() as u64;
+        let needed = if self.present { self.loss_samples } else { self.response_samples };
+        if self.pending < needed.max(1) {
+            return false;
+        }
+
+        self.present = !self.present;
+        self.pending = 0;
+        true
     }
-    
-    pub fn process_sample(&mut self, sample: f32) {
-        self.detector.process_sample(sample);
-        
-        if self.detector.is_complete() {
-            let energy = self.detector.magnitude_squared();
-            self.present = energy > self.energy_threshold;
-            self.detector.reset();
+
+    /// Process block of samples; returns every state change, in order (a
+    /// drop and the carrier's return can both fall in one block)
+    pub fn process_block(&mut self, samples: &[f32]) -> Vec<CarrierEvent> {
+        let mut events = Vec::new();
+        self.process_block_with(samples, |event| events.push(event));
+        events
+    }
+
+    /// `process_block` without allocating: `on_event` gets each state
+    /// change as it happens
+    pub fn process_block_with<F: FnMut(CarrierEvent)>(&mut self, samples: &[f32], mut on_event: F) {
+        for &sample in samples {
+            if self.process_sample(sample) {
+                on_event(self.state());
+            }
         }
     }
-    
+
+    /// Current state as the event that led to it
+    pub fn state(&self) -> CarrierEvent {
+        if self.present { CarrierEvent::Detected } else { CarrierEvent::Lost }
+    }
+
     pub fn is_present(&self) -> bool {
         self.present
     }
-    
+
+    /// Receive-band level over the last block
+    pub fn level_dbm0(&self) -> f32 {
+        power_to_dbm0(self.power)
+    }
+
     pub fn reset(&mut self) {
-        self.detector.reset();
+        self.bins.reset();
+        self.block.clear();
+        self.power = 0.0;
+        self.pending = 0;
         self.present = false;
     }
-}
\ No newline at end of file
+}
@@ -1,91 +1,247 @@
 // src/dsp/costas.rs
 use super::*;
+use super::hilbert::AnalyticSignal;
+use super::oscillator::ComplexNCO;
 use num_complex::Complex32;
 
-/// Costas loop for carrier recovery [web:86][web:89]
+/// Smoothing for the lock detector statistics (about 64 symbols)
+const LOCK_ALPHA: f32 = 1.0 / 64.0;
+
+/// Phase-error variance (rad²) below which the loop counts as locked,
+/// about 6° rms; it has to rise above `UNLOCK_VARIANCE` to unlock again
+const LOCK_VARIANCE: f32 = 0.01;
+const UNLOCK_VARIANCE: f32 = 0.02;
+
+/// Frequency-acquisition gain, in rad/symbol per rad/symbol of estimated
+/// offset
+const FLL_GAIN: f32 = 0.02;
+
+/// Smoothing for the frequency discriminator (about 128 symbols). The
+/// fourth power leaves heavy self-noise on the 16-QAM side points; a
+/// shorter average walks the NCO around by ten hertz or more.
+const FLL_ALPHA: f32 = 1.0 / 128.0;
+
+/// Offsets (rad/symbol) the frequency discriminator leaves to the PLL.
+/// Its estimate is noisy for 16-QAM (a few Hz either way at 600 baud);
+/// inside this band it would only push the PLL off lock.
+const FLL_DEADBAND: f32 = 0.1;
+
+/// Largest frequency correction (rad/symbol) the PLL integrator holds.
+/// DPSK and 16-QAM look the same turned a quarter turn every symbol, so
+/// without a limit the loop can lock a quarter of the symbol rate off
+/// with every decision right and every quadrant change wrong.
+const MAX_INTEGRATOR: f32 = PI / 8.0;
+
+/// How much wider the PLL runs until it locks. 16-QAM needs the pull-in
+/// range (about 15 Hz at 600 baud); once locked the narrow loop lets less
+/// decision noise through.
+const ACQUISITION_WIDENING: f32 = 6.0;
+
+/// PI loop filter gains for a noise bandwidth (fraction of the symbol rate)
+#[derive(Debug, Clone, Copy)]
+struct LoopGains {
+    proportional: f32,
+    integral: f32,
+}
+
+impl LoopGains {
+    fn new(loop_bandwidth: f32) -> Self {
+        let damping = 0.707;  // Critical damping
+        let theta = loop_bandwidth / (damping + 0.25 / damping);
+        let denom = 1.0 + 2.0 * damping * theta + theta * theta;
+
+        Self {
+            proportional: (4.0 * damping * theta) / denom,
+            integral: (4.0 * theta * theta) / denom,
+        }
+    }
+}
+
+/// Decision-directed carrier recovery [web:86][web:89]
+///
+/// Two parts:
+///   - the line signal is made analytic and the mixer NCO shifts it down to
+///     baseband, leaving no double-frequency product; a fourth-power
+///     frequency discriminator on the raw symbol strobes steers it onto
+///     large carrier offsets while the loop is unlocked
+///   - a second-order PLL at the symbol rate rotates the equalizer output
+///     onto the slicer decisions. Its phase detector compares against the
+///     decision, so it works for DPSK and 16-QAM alike, and sitting after
+///     the equalizer keeps the filter delays out of the loop. It runs at
+///     a wider bandwidth until locked.
+///
+/// Lock is judged from the variance of the phase error, so noise or
+/// silence never counts as locked.
 pub struct CostasLoop {
-    phase: f32,
-    frequency: f32,
-    
-    // Loop filter (PI controller)
-    proportional_gain: f32,
-    integral_gain: f32,
+    analytic: AnalyticSignal,
+    nco: ComplexNCO,
+
+    // PLL: loop filter (PI controller) and derotator, in rad per symbol
+    tracking: LoopGains,
+    acquisition: LoopGains,
     integrator: f32,
-    
-    // NCO
+    phase: f32,
+
+    // Frequency acquisition
+    prev_fourth: Complex32,
+    rotation: Complex32,
+
+    // Lock detector: smoothed phase error and its square
+    error_mean: f32,
+    error_power: f32,
+    last_error: f32,
+    locked: bool,
+    acquired: bool,
+
     carrier_freq: f32,
     sample_rate: f32,
+    symbol_rate: f32,
 }
 
 impl CostasLoop {
-    /// Create Costas loop [web:86]
+    /// Create carrier loop [web:86]; `loop_bandwidth` is the PLL noise
+    /// bandwidth as a fraction of the symbol rate
     pub fn new(
-        carrier_freq: f32, 
-        sample_rate: f32, 
+        carrier_freq: f32,
+        sample_rate: f32,
+        symbol_rate: f32,
         loop_bandwidth: f32
     ) -> Self {
-        // Design loop filter coefficients
-        let damping = 0.707;  // Critical damping
-        let theta = loop_bandwidth / sample_rate;
-        let denom = 1.0 + 2.0 * damping * theta + theta * theta;
-        
-        let proportional_gain = (4.0 * damping * theta) / denom;
-        let integral_gain = (4.0 * theta * theta) / denom;
-        
         Self {
-            phase: 0.0,
-            frequency: carrier_freq,
-            proportional_gain,
-            integral_gain,
+            analytic: AnalyticSignal::default(),
+            nco: ComplexNCO::new(carrier_freq, sample_rate),
+            tracking: LoopGains::new(loop_bandwidth),
+            acquisition: LoopGains::new(loop_bandwidth * ACQUISITION_WIDENING),
             integrator: 0.0,
+            phase: 0.0,
+            prev_fourth: Complex32::new(0.0, 0.0),
+            rotation: Complex32::new(0.0, 0.0),
+            error_mean: 0.0,
+            error_power: UNLOCK_VARIANCE * 4.0,
+            last_error: 0.0,
+            locked: false,
+            acquired: false,
             carrier_freq,
             sample_rate,
+            symbol_rate,
         }
     }
-    
-    /// Demodulate sample and track carrier [web:86][web:89]
+
+    /// Mix one line sample down to baseband
+    #[inline]
     pub fn process(&mut self, input: f32) -> Complex32 {
-        let omega = freq_to_omega(self.frequency, self.sample_rate);
-        
-        // Generate quadrature carriers
-        let i_carrier = self.phase.cos();
-        let q_carrier = self.phase.sin();
-        
-        // Demodulate (mix down to baseband)
-        let i_signal = input * i_carrier;
-        let q_signal = input * q_carrier;
-        
-        // Phase error detector (for QPSK/QAM)
-        // Simple decision-directed: sign(I)*Q - sign(Q)*I
-        let phase_error = i_signal.signum() * q_signal - q_signal.signum() * i_signal;
-        
+        let analytic = self.analytic.process(input);
+        self.nco.mix_down_complex(analytic)
+    }
+
+    /// Samples of latency `process` adds (the Hilbert transformer)
+    pub fn delay(&self) -> usize {
+        self.analytic.delay()
+    }
+
+    /// Frequency acquisition step on a timing strobe (before equalization);
+    /// does nothing once the PLL has first locked. A later loss of lock is
+    /// left to the PLL: the discriminator would walk it off the carrier.
+    pub fn acquire(&mut self, strobe: Complex32) {
+        if self.acquired {
+            return;
+        }
+
+        // Raising to the fourth power strips the quadrant of DPSK and
+        // 16-QAM points, leaving 4x the carrier rotation per symbol. The
+        // products are averaged unnormalized so the large points, whose
+        // fourth powers line up best, dominate.
+        let fourth = strobe * strobe * strobe * strobe;
+        self.rotation += (fourth * self.prev_fourth.conj() - self.rotation) * FLL_ALPHA;
+        self.prev_fourth = fourth;
+
+        // Offsets inside the deadband are the PLL's. Steering by the
+        // estimate alone, not its difference from the PLL integrator, keeps
+        // a wandering integrator from dragging the NCO along with it.
+        let offset = self.rotation.arg() / 4.0;
+        if offset.abs() > FLL_DEADBAND {
+            // The fourth power only sees offsets up to an eighth of the
+            // symbol rate; past that it aliases
+            let range = self.symbol_rate / 8.0;
+            let step = FLL_GAIN * offset * self.symbol_rate / (2.0 * PI);
+            let frequency = (self.nco.frequency() + step)
+                .clamp(self.carrier_freq - range, self.carrier_freq + range);
+            self.nco.set_frequency(frequency);
+        }
+    }
+
+    /// Rotation to apply to the next equalized symbol
+    pub fn derotator(&self) -> Complex32 {
+        Complex32::from_polar(1.0, -self.phase)
+    }
+
+    /// Phase-tracking step on a derotated symbol and its slicer decision
+    pub fn update(&mut self, symbol: Complex32, decision: Complex32) {
+        if decision.norm_sqr() <= f32::EPSILON {
+            return;
+        }
+
+        // Angle from decision to symbol
+        let phase_error = (symbol * decision.conj()).arg();
+        self.last_error = phase_error;
+
         // Loop filter (PI controller)
-        self.integrator += phase_error * self.integral_gain;
-        let freq_correction = phase_error * self.proportional_gain + self.integrator;
-        
-        // Update frequency and phase
-        self.frequency = self.carrier_freq + freq_correction * self.sample_rate / (2.0 * PI);
-        self.phase += omega + freq_correction;
-        
-        // Wrap phase
-        while self.phase >= 2.0 * PI {
+        let gains = if self.locked { self.tracking } else { self.acquisition };
+        self.integrator = (self.integrator + phase_error * gains.integral)
+            .clamp(-MAX_INTEGRATOR, MAX_INTEGRATOR);
+        self.phase += phase_error * gains.proportional + self.integrator;
+        if self.phase > PI {
             self.phase -= 2.0 * PI;
-        }
-        while self.phase < 0.0 {
+        } else if self.phase < -PI {
             self.phase += 2.0 * PI;
         }
-        
-        Complex32::new(i_signal, q_signal)
+
+        // Lock detector with hysteresis
+        self.error_mean += LOCK_ALPHA * (phase_error - self.error_mean);
+        self.error_power += LOCK_ALPHA * (phase_error * phase_error - self.error_power);
+        let variance = self.phase_error_variance();
+        if variance < LOCK_VARIANCE {
+            self.locked = true;
+            self.acquired = true;
+        } else if variance > UNLOCK_VARIANCE {
+            self.locked = false;
+        }
+    }
+
+    /// Tracked carrier frequency
+    pub fn frequency(&self) -> f32 {
+        self.nco.frequency() + self.integrator * self.symbol_rate / (2.0 * PI)
+    }
+
+    /// Tracked offset from the nominal carrier
+    pub fn frequency_offset(&self) -> f32 {
+        self.frequency() - self.carrier_freq
+    }
+
+    /// Latest phase detector output in radians
+    pub fn phase_error(&self) -> f32 {
+        self.last_error
+    }
+
+    pub fn phase_error_variance(&self) -> f32 {
+        (self.error_power - self.error_mean * self.error_mean).max(0.0)
     }
-    
+
     pub fn reset(&mut self) {
-        self.phase = 0.0;
-        self.frequency = self.carrier_freq;
+        self.analytic.reset();
+        self.nco = ComplexNCO::new(self.carrier_freq, self.sample_rate);
         self.integrator = 0.0;
+        self.phase = 0.0;
+        self.prev_fourth = Complex32::new(0.0, 0.0);
+        self.rotation = Complex32::new(0.0, 0.0);
+        self.error_mean = 0.0;
+        self.error_power = UNLOCK_VARIANCE * 4.0;
+        self.last_error = 0.0;
+        self.locked = false;
+        self.acquired = false;
     }
-    
+
     pub fn is_locked(&self) -> bool {
-        // Simple lock detector: frequency error within tolerance
-        (self.frequency - self.carrier_freq).abs() < 10.0
+        self.locked
     }
 }
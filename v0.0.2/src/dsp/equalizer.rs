@@ -1,21 +1,94 @@
 // src/dsp/equalizer.rs
 // Warning Fix: Removed unused 'use super::*;'
 use num_complex::Complex32;
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use super::simd::SimdLevel;
+
+/// Hard decision for a constellation, supplied by the modulation
+pub trait Slicer {
+    /// Nearest ideal constellation point
+    fn slice(&self, symbol: Complex32) -> Complex32;
+
+    /// Smallest distance between two constellation points
+    fn min_distance(&self) -> f32;
+
+    /// Decision MSE below which the eye counts as open enough to adapt on
+    /// decisions: a tenth of the squared half-distance to the nearest
+    /// decision boundary (about 10 dB of margin)
+    fn convergence_mse(&self) -> f32 {
+        let half = self.min_distance() / 2.0;
+        0.1 * half * half
+    }
+}
+
+/// Smoothing for the MSE estimate (about 64 symbols)
+const MSE_ALPHA: f32 = 1.0 / 64.0;
+
+/// Symbols to wait after a reset before the MSE estimate is trusted
+const MIN_TRAINING_SYMBOLS: usize = 128;
+
+/// Smoothed squared error and the training -> decision-directed switch
+#[derive(Debug, Clone)]
+struct Convergence {
+    training: bool,
+    auto_switch: bool,
+    mse: f32,
+    symbols: usize,
+}
+
+impl Convergence {
+    fn new() -> Self {
+        Self {
+            training: true,
+            auto_switch: true,
+            mse: 1.0,
+            symbols: 0,
+        }
+    }
+
+    /// Record one symbol's error; leaves training once the MSE has settled
+    /// below `threshold`
+    fn track(&mut self, error: Complex32, threshold: f32) {
+        self.mse += MSE_ALPHA * (error.norm_sqr() - self.mse);
+        self.symbols += 1;
+
+        if self.training
+            && self.auto_switch
+            && self.symbols >= MIN_TRAINING_SYMBOLS
+            && self.mse < threshold
+        {
+            self.training = false;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.training = true;
+        self.mse = 1.0;
+        self.symbols = 0;
+    }
+}
 
 /// LMS adaptive equalizer [web:90][web:91]
+///
+/// Starts in training mode, adapting only on known symbols. While training
+/// it also tracks the MSE against its own decisions, and once that drops
+/// below the slicer's convergence level it switches itself to
+/// decision-directed adaptation.
 pub struct LMSEqualizer {
-// ... (Rest of the file is unchanged)
     taps: Vec<Complex32>,
     num_taps: usize,
     step_size: f32,  // μ (mu) parameter
     buffer: Vec<Complex32>,
     buffer_idx: usize,
-    training: bool,
+    
+    // Decisions and convergence tracking
+    slicer: Box<dyn Slicer>,
+    convergence: Convergence,
 }
 
 impl LMSEqualizer {
-    /// Create new LMS equalizer [web:91]
-    pub fn new(num_taps: usize, step_size: f32) -> Self {
+    /// Create new LMS equalizer [web:91] deciding with `slicer`
+    pub fn new<S: Slicer + 'static>(num_taps: usize, step_size: f32, slicer: S) -> Self {
         let mut taps = vec![Complex32::new(0.0, 0.0); num_taps];
         taps[num_taps / 2] = Complex32::new(1.0, 0.0);  // Center tap = 1
         
@@ -25,7 +98,8 @@ impl LMSEqualizer {
             step_size,
             buffer: vec![Complex32::new(0.0, 0.0); num_taps],
             buffer_idx: 0,
-            training: true,
+            slicer: Box::new(slicer),
+            convergence: Convergence::new(),
         }
     }
     
@@ -48,15 +122,20 @@ impl LMSEqualizer {
         }
         
         // 3. Compute error and update taps
+        let threshold = self.slicer.convergence_mse();
         if let Some(desired) = training_symbol {
             // Training mode: known symbol
             let error = desired - output;
+            self.convergence.track(error, threshold);
             self.update_taps(error);
-        } else if !self.training {
-            // Decision-directed mode: use sliced output
-            let decided = self.slice_symbol(output);
-            let error = decided - output;
-            self.update_taps(error);
+        } else {
+            // Decision-directed: use sliced output (adapt only once trained)
+            let error = self.slicer.slice(output) - output;
+            let training = self.convergence.training;
+            self.convergence.track(error, threshold);
+            if !training {
+                self.update_taps(error);
+            }
         }
         
         output
@@ -73,14 +152,36 @@ impl LMSEqualizer {
         }
     }
     
-    /// Simple hard decision (override for specific constellation)
-    fn slice_symbol(&self, symbol: Complex32) -> Complex32 {
-        // Round to nearest integer I and Q
-        Complex32::new(symbol.re.round(), symbol.im.round())
+    /// Decision for an equalized symbol
+    pub fn slice(&self, symbol: Complex32) -> Complex32 {
+        self.slicer.slice(symbol)
     }
     
     pub fn set_training_mode(&mut self, training: bool) {
-        self.training = training;
+        self.convergence.training = training;
+    }
+    
+    pub fn is_training(&self) -> bool {
+        self.convergence.training
+    }
+    
+    /// Enable/disable the automatic switch to decision-directed mode
+    pub fn set_auto_switch(&mut self, enabled: bool) {
+        self.convergence.auto_switch = enabled;
+    }
+    
+    /// Smoothed squared error (against training symbols or decisions)
+    pub fn mse(&self) -> f32 {
+        self.convergence.mse
+    }
+    
+    pub fn taps(&self) -> &[Complex32] {
+        &self.taps
+    }
+    
+    /// Sum of squared tap magnitudes
+    pub fn tap_energy(&self) -> f32 {
+        self.taps.iter().map(|t| t.norm_sqr()).sum()
     }
 
     pub fn reset(&mut self) {
@@ -88,6 +189,268 @@ impl LMSEqualizer {
         self.buffer_idx = 0;
         self.taps = vec![Complex32::new(0.0, 0.0); self.num_taps];
         self.taps[self.num_taps / 2] = Complex32::new(1.0, 0.0);
-        self.training = true;
+        self.convergence.reset();
+    }
+}
+
+/// Tap spacing of an `FSEqualizer`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TapSpacing {
+    /// One tap per symbol (T)
+    Symbol,
+    /// Two taps per symbol (T/2): insensitive to the sampling phase
+    HalfSymbol,
+}
+
+impl TapSpacing {
+    pub fn taps_per_symbol(&self) -> usize {
+        match self {
+            TapSpacing::Symbol => 1,
+            TapSpacing::HalfSymbol => 2,
+        }
+    }
+}
+
+/// Tap update algorithm
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Adaptation {
+    /// Plain LMS with a fixed step size
+    Lms { step_size: f32 },
+    /// LMS with the step normalized by the input energy in the delay line
+    Nlms { step_size: f32 },
+    /// Recursive least squares; converges in a few times the tap count
+    Rls { forgetting: f32 },
+}
+
+/// Equalizer layout and adaptation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqualizerConfig {
+    pub spacing: TapSpacing,
+    /// Time span covered by the taps, in symbols
+    pub span_symbols: usize,
+    pub adaptation: Adaptation,
+}
+
+impl Default for EqualizerConfig {
+    fn default() -> Self {
+        Self {
+            spacing: TapSpacing::HalfSymbol,
+            span_symbols: 8,
+            adaptation: Adaptation::Nlms { step_size: 0.25 },
+        }
+    }
+}
+
+/// Keeps NLMS from blowing up on silence
+const NLMS_EPSILON: f32 = 1e-3;
+
+/// Initial diagonal of the RLS inverse correlation matrix
+const RLS_INITIAL_P: f32 = 100.0;
+
+/// Adaptive equalizer with T or T/2 tap spacing [web:90][web:91]
+///
+/// Fed with every input sample at the tap spacing (for T/2, the midpoint
+/// strobe followed by the symbol strobe) and produces one output per
+/// symbol. Same training / decision-directed behaviour as `LMSEqualizer`.
+pub struct FSEqualizer {
+    config: EqualizerConfig,
+    taps: Vec<Complex32>,
+    // Delay line, oldest first
+    buffer: Vec<Complex32>,
+    // RLS inverse correlation matrix (row major), empty for LMS/NLMS
+    p: Vec<Complex32>,
+    // RLS scratch for P u, so an update doesn't allocate
+    pu: Vec<Complex32>,
+    simd: SimdLevel,
+
+    slicer: Box<dyn Slicer>,
+    convergence: Convergence,
+}
+
+impl FSEqualizer {
+    pub fn new<S: Slicer + 'static>(config: EqualizerConfig, slicer: S) -> Self {
+        let len = config.span_symbols.max(1) * config.spacing.taps_per_symbol();
+        let mut equalizer = Self {
+            config,
+            taps: vec![Complex32::new(0.0, 0.0); len],
+            buffer: vec![Complex32::new(0.0, 0.0); len],
+            p: Vec::new(),
+            pu: Vec::new(),
+            simd: SimdLevel::detect(),
+            slicer: Box::new(slicer),
+            convergence: Convergence::new(),
+        };
+        equalizer.reset();
+        equalizer
+    }
+
+    pub fn config(&self) -> &EqualizerConfig {
+        &self.config
+    }
+
+    /// Force an instruction set for the tap loops (e.g. scalar, to compare
+    /// against)
+    pub fn set_simd_level(&mut self, level: SimdLevel) -> Result<(), String> {
+        if !level.is_supported() {
+            return Err(format!("{} is not supported on this CPU", level));
+        }
+        self.simd = level;
+        Ok(())
+    }
+
+    pub fn simd_level(&self) -> SimdLevel {
+        self.simd
+    }
+
+    /// Symbols between an input and the output it mostly shows up in
+    pub fn delay(&self) -> usize {
+        self.config.span_symbols.max(1) / 2
+    }
+
+    /// Process one symbol period: `inputs` holds `taps_per_symbol()` samples,
+    /// oldest first, the last one on the symbol strobe
+    pub fn equalize(&mut self, inputs: &[Complex32], training_symbol: Option<Complex32>) -> Complex32 {
+        self.equalize_rotated(inputs, Complex32::new(1.0, 0.0), training_symbol)
+    }
+
+    /// As `equalize`, with the output turned by `rotation` (unit magnitude)
+    /// before slicing, for a carrier loop that derotates after the
+    /// equalizer. The error is turned back for the tap update.
+    pub fn equalize_rotated(
+        &mut self,
+        inputs: &[Complex32],
+        rotation: Complex32,
+        training_symbol: Option<Complex32>,
+    ) -> Complex32 {
+        for &input in inputs {
+            self.buffer.rotate_left(1);
+            if let Some(newest) = self.buffer.last_mut() {
+                *newest = input;
+            }
+        }
+
+        // y = W^T X
+        let output = self.simd.complex_dot(&self.taps, &self.buffer) * rotation;
+
+        let threshold = self.slicer.convergence_mse();
+        let (error, adapt) = match training_symbol {
+            Some(desired) => (desired - output, true),
+            None => (self.slicer.slice(output) - output, !self.convergence.training),
+        };
+        self.convergence.track(error, threshold);
+        if adapt {
+            self.update_taps(error * rotation.conj());
+        }
+
+        output
+    }
+
+    fn update_taps(&mut self, error: Complex32) {
+        match self.config.adaptation {
+            Adaptation::Lms { step_size } => {
+                self.simd.complex_axpy_conj(&mut self.taps, &self.buffer, error, step_size);
+            }
+            Adaptation::Nlms { step_size } => {
+                let energy = self.simd.complex_energy(&self.buffer);
+                let mu = step_size / (NLMS_EPSILON + energy);
+                self.simd.complex_axpy_conj(&mut self.taps, &self.buffer, error, mu);
+            }
+            Adaptation::Rls { forgetting } => self.rls_update(error, forgetting),
+        }
+    }
+
+    /// Exponentially weighted RLS. With u = X the filter is y = h^H u for
+    /// h = conj(W), so the textbook update h += k e* becomes W += conj(k) e.
+    fn rls_update(&mut self, error: Complex32, forgetting: f32) {
+        let n = self.taps.len();
+        let u = &self.buffer;
+
+        // pu = P u, and P Hermitian gives u^H P = (P u)^H
+        for (i, v) in self.pu.iter_mut().enumerate() {
+            *v = self.simd.complex_dot(&self.p[i * n..(i + 1) * n], u);
+        }
+        let pu = &self.pu;
+        let denom = forgetting + u.iter().zip(pu).map(|(x, v)| x.conj() * v).sum::<Complex32>().re;
+        // Gain k = pu / denom
+        let gain = |i: usize| pu[i] / denom;
+
+        for (i, w) in self.taps.iter_mut().enumerate() {
+            *w += gain(i).conj() * error;
+        }
+
+        // P = (P - k (Pu)^H) / lambda, kept Hermitian against rounding drift
+        let inv = 1.0 / forgetting;
+        for i in 0..n {
+            for j in i..n {
+                let a = (self.p[i * n + j] - gain(i) * pu[j].conj()) * inv;
+                let b = (self.p[j * n + i] - gain(j) * pu[i].conj()) * inv;
+                let avg = (a + b.conj()) * 0.5;
+                self.p[i * n + j] = avg;
+                self.p[j * n + i] = avg.conj();
+            }
+        }
+
+        // Directions the input never excites (the band edges of a T/2 line)
+        // make P grow by 1/lambda every symbol; cap it at its initial size
+        let trace: f32 = (0..n).map(|i| self.p[i * n + i].re).sum();
+        let limit = RLS_INITIAL_P * n as f32;
+        if trace > limit {
+            let scale = limit / trace;
+            self.p.iter_mut().for_each(|p| *p *= scale);
+        }
+    }
+
+    /// Decision for an equalized symbol
+    pub fn slice(&self, symbol: Complex32) -> Complex32 {
+        self.slicer.slice(symbol)
+    }
+
+    pub fn set_training_mode(&mut self, training: bool) {
+        self.convergence.training = training;
+    }
+
+    pub fn is_training(&self) -> bool {
+        self.convergence.training
+    }
+
+    /// Enable/disable the automatic switch to decision-directed mode
+    pub fn set_auto_switch(&mut self, enabled: bool) {
+        self.convergence.auto_switch = enabled;
+    }
+
+    /// Smoothed squared error (against training symbols or decisions)
+    pub fn mse(&self) -> f32 {
+        self.convergence.mse
+    }
+
+    pub fn taps(&self) -> &[Complex32] {
+        &self.taps
+    }
+
+    /// Sum of squared tap magnitudes
+    pub fn tap_energy(&self) -> f32 {
+        self.taps.iter().map(|t| t.norm_sqr()).sum()
     }
-}
\ No newline at end of file
+
+    pub fn reset(&mut self) {
+        let n = self.taps.len();
+        self.buffer.iter_mut().for_each(|x| *x = Complex32::new(0.0, 0.0));
+        self.taps.iter_mut().for_each(|w| *w = Complex32::new(0.0, 0.0));
+
+        // Unit tap on the symbol-strobe sample `delay()` symbols back
+        let centre = n - 1 - self.delay() * self.config.spacing.taps_per_symbol();
+        self.taps[centre] = Complex32::new(1.0, 0.0);
+
+        self.p.clear();
+        self.pu.clear();
+        if let Adaptation::Rls { .. } = self.config.adaptation {
+            self.p.resize(n * n, Complex32::new(0.0, 0.0));
+            self.pu.resize(n, Complex32::new(0.0, 0.0));
+            for i in 0..n {
+                self.p[i * n + i] = Complex32::new(RLS_INITIAL_P, 0.0);
+            }
+        }
+
+        self.convergence.reset();
+    }
+}
@@ -1,5 +1,6 @@
 // src/dsp/filters.rs
 use super::*;
+use super::simd::{BiquadLanes, SimdLevel};
 
 /// Second-order IIR filter (Direct Form II)
 #[derive(Clone)]
@@ -85,9 +86,76 @@ impl BiquadFilter {
         }
     }
     
+    /// Normalized coefficients: ([b0, b1, b2], [a1, a2])
+    pub fn coefficients(&self) -> ([f32; 3], [f32; 2]) {
+        ([self.b0, self.b1, self.b2], [self.a1, self.a2])
+    }
+    
     /// Reset filter state
     pub fn reset(&mut self) {
         self.z1 = 0.0;
         self.z2 = 0.0;
     }
 }
+
+/// Independent biquads stepped together, one lane per filter, vectorized
+/// across lanes (the filters of many channels, or a filter bank on one).
+/// Each lane does exactly the arithmetic of `BiquadFilter::process`.
+#[derive(Clone)]
+pub struct BiquadBank {
+    lanes: BiquadLanes,
+    simd: SimdLevel,
+}
+
+impl BiquadBank {
+    /// One lane per filter, starting from each filter's current state
+    pub fn new(filters: &[BiquadFilter]) -> Self {
+        let mut lanes = BiquadLanes::default();
+        for f in filters {
+            lanes.b0.push(f.b0);
+            lanes.b1.push(f.b1);
+            lanes.b2.push(f.b2);
+            lanes.a1.push(f.a1);
+            lanes.a2.push(f.a2);
+            lanes.z1.push(f.z1);
+            lanes.z2.push(f.z2);
+        }
+        Self {
+            lanes,
+            simd: SimdLevel::detect(),
+        }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.lanes.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.lanes.len() == 0
+    }
+    
+    /// Force an instruction set (e.g. scalar, to compare against)
+    pub fn set_simd_level(&mut self, level: SimdLevel) -> Result<(), String> {
+        if !level.is_supported() {
+            return Err(format!("{} is not supported on this CPU", level));
+        }
+        self.simd = level;
+        Ok(())
+    }
+    
+    pub fn simd_level(&self) -> SimdLevel {
+        self.simd
+    }
+    
+    /// Process whole frames of one sample per lane, lane fastest:
+    /// `input[frame * len() + lane]`. A partial trailing frame is ignored.
+    pub fn process_frames(&mut self, input: &[f32], output: &mut [f32]) {
+        self.simd.biquad_lanes(&mut self.lanes, input, output);
+    }
+    
+    /// Reset every lane's state
+    pub fn reset(&mut self) {
+        self.lanes.z1.iter_mut().for_each(|z| *z = 0.0);
+        self.lanes.z2.iter_mut().for_each(|z| *z = 0.0);
+    }
+}
@@ -0,0 +1,491 @@
+// src/dsp/fir.rs
+//
+// FIR filter design (windowed sinc, raised cosine / root raised cosine and an
+// iteratively reweighted least-squares equiripple lowpass), a streaming
+// FIR filter for sample-by-sample use, and a pulse shaper for symbol
+// clocks that don't divide the sample rate.
+use super::*;
+use num_complex::Complex32;
+use alloc::collections::VecDeque;
+
+/// Window applied to a truncated ideal response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Window {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+    /// Kaiser window with shape parameter beta
+    Kaiser(f32),
+}
+
+impl Window {
+    /// Window value for tap `n` of a `len`-tap filter
+    pub fn coefficient(&self, n: usize, len: usize) -> f32 {
+        if len <= 1 {
+            return 1.0;
+        }
+        let x = n as f32 / (len - 1) as f32;
+        match *self {
+            Window::Rectangular => 1.0,
+            Window::Hann => 0.5 - 0.5 * (2.0 * PI * x).cos(),
+            Window::Hamming => 0.54 - 0.46 * (2.0 * PI * x).cos(),
+            Window::Blackman => {
+                0.42 - 0.5 * (2.0 * PI * x).cos() + 0.08 * (4.0 * PI * x).cos()
+            }
+            Window::Kaiser(beta) => {
+                let r = 2.0 * x - 1.0;
+                bessel_i0(beta * (1.0 - r * r).max(0.0).sqrt()) / bessel_i0(beta)
+            }
+        }
+    }
+
+    /// Kaiser beta for a given stopband attenuation in dB
+    pub fn kaiser_beta(attenuation_db: f32) -> f32 {
+        if attenuation_db > 50.0 {
+            0.1102 * (attenuation_db - 8.7)
+        } else if attenuation_db >= 21.0 {
+            0.5842 * (attenuation_db - 21.0).powf(0.4) + 0.07886 * (attenuation_db - 21.0)
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Zeroth-order modified Bessel function of the first kind (power series)
+fn bessel_i0(x: f32) -> f32 {
+    let half = x / 2.0;
+    let mut term = 1.0f32;
+    let mut sum = 1.0f32;
+    for k in 1..32 {
+        term *= half / k as f32;
+        let t2 = term * term;
+        sum += t2;
+        if t2 < sum * 1e-9 {
+            break;
+        }
+    }
+    sum
+}
+
+#[inline]
+pub(crate) fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+/// Windowed-sinc lowpass with unity DC gain
+pub fn lowpass(num_taps: usize, cutoff: f32, sample_rate: f32, window: Window) -> Vec<f32> {
+    let fc = cutoff / sample_rate;
+    let centre = (num_taps as f32 - 1.0) / 2.0;
+
+    let mut taps: Vec<f32> = (0..num_taps)
+        .map(|n| 2.0 * fc * sinc(2.0 * fc * (n as f32 - centre)) * window.coefficient(n, num_taps))
+        .collect();
+
+    let dc: f32 = taps.iter().sum();
+    if dc.abs() > 1e-12 {
+        taps.iter_mut().for_each(|t| *t /= dc);
+    }
+    taps
+}
+
+/// Windowed-sinc highpass by spectral inversion (`num_taps` must be odd)
+pub fn highpass(num_taps: usize, cutoff: f32, sample_rate: f32, window: Window) -> Vec<f32> {
+    let mut taps = lowpass(num_taps, cutoff, sample_rate, window);
+    taps.iter_mut().for_each(|t| *t = -*t);
+    taps[num_taps / 2] += 1.0;
+    taps
+}
+
+/// Windowed-sinc bandpass with unity gain at the band centre
+pub fn bandpass(
+    num_taps: usize,
+    low: f32,
+    high: f32,
+    sample_rate: f32,
+    window: Window,
+) -> Vec<f32> {
+    let centre = (num_taps as f32 - 1.0) / 2.0;
+    let (f1, f2) = (low / sample_rate, high / sample_rate);
+
+    let mut taps: Vec<f32> = (0..num_taps)
+        .map(|n| {
+            let t = n as f32 - centre;
+            (2.0 * f2 * sinc(2.0 * f2 * t) - 2.0 * f1 * sinc(2.0 * f1 * t))
+                * window.coefficient(n, num_taps)
+        })
+        .collect();
+
+    let gain = magnitude_response(&taps, (low + high) / 2.0, sample_rate);
+    if gain > 1e-12 {
+        taps.iter_mut().for_each(|t| *t /= gain);
+    }
+    taps
+}
+
+/// Windowed Hilbert transformer (type III: `num_taps` is forced odd).
+///
+/// Ideal response 2/(πk) on odd offsets k from the centre, zero on even
+/// ones: a 90° phase shift with unity gain away from DC and fs/2.
+pub fn hilbert(num_taps: usize, window: Window) -> Vec<f32> {
+    let num_taps = num_taps | 1;
+    let centre = (num_taps / 2) as isize;
+
+    (0..num_taps)
+        .map(|n| {
+            let k = n as isize - centre;
+            if k % 2 == 0 {
+                0.0
+            } else {
+                2.0 / (PI * k as f32) * window.coefficient(n, num_taps)
+            }
+        })
+        .collect()
+}
+
+/// Number of taps for a pulse spanning `span_symbols` symbols (always odd)
+fn pulse_length(symbol_rate: f32, sample_rate: f32, span_symbols: usize) -> usize {
+    let sps = sample_rate / symbol_rate;
+    ((span_symbols as f32 * sps).round() as usize) | 1
+}
+
+/// Raised-cosine (Nyquist) pulse, peak 1.0, zero at every other symbol instant
+pub fn raised_cosine(
+    symbol_rate: f32,
+    sample_rate: f32,
+    rolloff: f32,
+    span_symbols: usize,
+) -> Vec<f32> {
+    let len = pulse_length(symbol_rate, sample_rate, span_symbols);
+    let centre = (len / 2) as f32;
+    let sps = sample_rate / symbol_rate;
+    let beta = rolloff.clamp(0.0, 1.0);
+
+    (0..len)
+        .map(|n| {
+            let t = (n as f32 - centre) / sps;
+            let denom = 1.0 - (2.0 * beta * t).powi(2);
+            if denom.abs() < 1e-5 {
+                PI / 4.0 * sinc(1.0 / (2.0 * beta))
+            } else {
+                sinc(t) * (PI * beta * t).cos() / denom
+            }
+        })
+        .collect()
+}
+
+/// Root-raised-cosine pulse normalised to unit energy, so that a matched
+/// pair cascades to a raised cosine with peak 1.0
+pub fn root_raised_cosine(
+    symbol_rate: f32,
+    sample_rate: f32,
+    rolloff: f32,
+    span_symbols: usize,
+) -> Vec<f32> {
+    let len = pulse_length(symbol_rate, sample_rate, span_symbols);
+    let centre = (len / 2) as f32;
+    let sps = sample_rate / symbol_rate;
+    let beta = rolloff.clamp(1e-3, 1.0);
+
+    let mut taps: Vec<f32> = (0..len)
+        .map(|n| {
+            let t = (n as f32 - centre) / sps;
+            if t.abs() < 1e-6 {
+                1.0 - beta + 4.0 * beta / PI
+            } else if (t.abs() - 1.0 / (4.0 * beta)).abs() < 1e-5 {
+                beta / 2f32.sqrt()
+                    * ((1.0 + 2.0 / PI) * (PI / (4.0 * beta)).sin()
+                        + (1.0 - 2.0 / PI) * (PI / (4.0 * beta)).cos())
+            } else {
+                let num = (PI * t * (1.0 - beta)).sin()
+                    + 4.0 * beta * t * (PI * t * (1.0 + beta)).cos();
+                let den = PI * t * (1.0 - (4.0 * beta * t).powi(2));
+                num / den
+            }
+        })
+        .collect();
+
+    let energy = taps.iter().map(|t| t * t).sum::<f32>().sqrt();
+    taps.iter_mut().for_each(|t| *t /= energy);
+    taps
+}
+
+/// Near-equiripple linear-phase lowpass (odd length, unity passband).
+///
+/// Uses Lawson's iteratively reweighted least squares, which converges
+/// towards the Parks-McClellan minimax solution without the Remez exchange.
+/// `stop_weight` trades stopband attenuation against passband ripple.
+pub fn equiripple_lowpass(
+    num_taps: usize,
+    pass_edge: f32,
+    stop_edge: f32,
+    sample_rate: f32,
+    stop_weight: f32,
+) -> Vec<f32> {
+    let num_taps = num_taps | 1;
+    let m = num_taps / 2;
+    let wp = freq_to_omega(pass_edge, sample_rate) as f64;
+    let ws = freq_to_omega(stop_edge, sample_rate) as f64;
+
+    // Dense grid over the pass and stop bands, skipping the transition band
+    let grid_len = 16 * num_taps;
+    let pass_len = ((grid_len as f64) * wp / (wp + core::f64::consts::PI - ws)).ceil() as usize;
+    let mut grid: Vec<(f64, f64, f64)> = Vec::with_capacity(grid_len + 2);
+    for i in 0..=pass_len {
+        grid.push((wp * i as f64 / pass_len as f64, 1.0, 1.0));
+    }
+    let stop_len = grid_len.saturating_sub(pass_len).max(1);
+    for i in 0..=stop_len {
+        let w = ws + (core::f64::consts::PI - ws) * i as f64 / stop_len as f64;
+        grid.push((w, 0.0, stop_weight as f64));
+    }
+
+    // Amplitude A(w) = sum c_k cos(k w); Lawson weights start flat
+    let mut lawson = vec![1.0 / grid.len() as f64; grid.len()];
+    let mut coeffs = vec![0.0f64; m + 1];
+
+    for _ in 0..40 {
+        let mut ata = vec![vec![0.0f64; m + 1]; m + 1];
+        let mut atb = vec![0.0f64; m + 1];
+        for (&(w, desired, weight), &v) in grid.iter().zip(lawson.iter()) {
+            let g = v * weight * weight;
+            let basis: Vec<f64> = (0..=m).map(|k| (k as f64 * w).cos()).collect();
+            for r in 0..=m {
+                atb[r] += g * basis[r] * desired;
+                for c in 0..=m {
+                    ata[r][c] += g * basis[r] * basis[c];
+                }
+            }
+        }
+        match solve(ata, atb) {
+            Some(c) => coeffs = c,
+            None => break,
+        }
+
+        // Reweight towards the points with the largest error
+        let mut total = 0.0;
+        for (&(w, desired, weight), v) in grid.iter().zip(lawson.iter_mut()) {
+            let a: f64 = coeffs.iter().enumerate().map(|(k, c)| c * (k as f64 * w).cos()).sum();
+            *v *= (weight * (a - desired)).abs() + 1e-12;
+            total += *v;
+        }
+        if total <= 0.0 {
+            break;
+        }
+        lawson.iter_mut().for_each(|v| *v /= total);
+    }
+
+    let mut taps = vec![0.0f32; num_taps];
+    taps[m] = coeffs[0] as f32;
+    for k in 1..=m {
+        taps[m - k] = (coeffs[k] / 2.0) as f32;
+        taps[m + k] = (coeffs[k] / 2.0) as f32;
+    }
+    taps
+}
+
+/// Gaussian elimination with partial pivoting
+fn solve(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-300 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1..n {
+            let f = a[row][col] / a[col][col];
+            if f != 0.0 {
+                let (upper, lower) = a.split_at_mut(row);
+                for (x, p) in lower[0][col..].iter_mut().zip(&upper[col][col..]) {
+                    *x -= f * p;
+                }
+                b[row] -= f * b[col];
+            }
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let s: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - s) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Magnitude of the filter's frequency response at `freq`
+pub fn magnitude_response(taps: &[f32], freq: f32, sample_rate: f32) -> f32 {
+    let omega = freq_to_omega(freq, sample_rate);
+    let (re, im) = taps.iter().enumerate().fold((0.0f32, 0.0f32), |(re, im), (n, &h)| {
+        let phi = omega * n as f32;
+        (re + h * phi.cos(), im - h * phi.sin())
+    });
+    (re * re + im * im).sqrt()
+}
+
+/// Streaming FIR filter.
+///
+/// History is kept twice over so the newest `len` samples are always one
+/// contiguous slice and the inner loop is a plain dot product.
+#[derive(Clone)]
+pub struct FirFilter {
+    // Stored reversed so they line up with the oldest-first history window
+    reversed: Vec<f32>,
+    history: Vec<f32>,
+    pos: usize,
+}
+
+impl FirFilter {
+    pub fn new(taps: &[f32]) -> Self {
+        let taps = if taps.is_empty() { &[1.0][..] } else { taps };
+        Self {
+            reversed: taps.iter().rev().copied().collect(),
+            history: vec![0.0; 2 * taps.len()],
+            pos: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.reversed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reversed.is_empty()
+    }
+
+    /// Delay in samples of a linear-phase (symmetric) design
+    pub fn group_delay(&self) -> usize {
+        (self.len() - 1) / 2
+    }
+
+    pub fn taps(&self) -> Vec<f32> {
+        self.reversed.iter().rev().copied().collect()
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let n = self.reversed.len();
+        self.history[self.pos] = input;
+        self.history[self.pos + n] = input;
+        self.pos = (self.pos + 1) % n;
+
+        self.history[self.pos..self.pos + n]
+            .iter()
+            .zip(self.reversed.iter())
+            .map(|(x, h)| x * h)
+            .sum()
+    }
+
+    /// Process block of samples
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.process(*i);
+        }
+    }
+
+    /// Reset filter state
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|s| *s = 0.0);
+        self.pos = 0;
+    }
+}
+
+/// Pulse shaper for a symbol clock that need not divide the sample rate.
+///
+/// Symbol k goes out at instant k * samples_per_symbol, fractional or not,
+/// and each output sample sums the pulses of the symbols still ringing,
+/// read from a copy of the pulse oversampled `phases` times and linearly
+/// interpolated. With an integer samples_per_symbol this is the same as
+/// an impulse per symbol through a FIR filter.
+pub struct PulseShaper {
+    pulse: Vec<f32>,
+    phases: usize,
+    samples_per_symbol: f64,
+    // Instant and value of the symbols whose pulses haven't ended
+    symbols: VecDeque<(f64, Complex32)>,
+    next_instant: f64,
+    clock: f64,
+}
+
+impl PulseShaper {
+    /// `pulse` is sampled at `phases` times the output rate
+    pub fn new(pulse: Vec<f32>, phases: usize, samples_per_symbol: f32) -> Self {
+        Self {
+            pulse: if pulse.is_empty() { vec![1.0] } else { pulse },
+            phases: phases.max(1),
+            samples_per_symbol: samples_per_symbol as f64,
+            symbols: VecDeque::new(),
+            next_instant: 0.0,
+            clock: 0.0,
+        }
+    }
+
+    /// Pulse length in output samples
+    pub fn span(&self) -> f64 {
+        (self.pulse.len() - 1) as f64 / self.phases as f64
+    }
+
+    /// Samples from a symbol's instant to its pulse peak (symmetric pulses)
+    pub fn delay(&self) -> f32 {
+        (self.span() / 2.0) as f32
+    }
+
+    /// Queue the next symbol; returns how many samples to take before the
+    /// one after it is due
+    pub fn push(&mut self, symbol: Complex32) -> usize {
+        self.symbols.push_back((self.next_instant, symbol));
+        self.next_instant += self.samples_per_symbol;
+        (self.next_instant.ceil() - self.clock).max(0.0) as usize
+    }
+
+    /// Samples still to come from queued pulses
+    pub fn tail_len(&self) -> usize {
+        match self.symbols.back() {
+            Some(&(instant, _)) => (instant + self.span() + 1.0 - self.clock).ceil().max(0.0) as usize,
+            None => 0,
+        }
+    }
+
+    /// Next output sample
+    #[inline]
+    pub fn next_sample(&mut self) -> Complex32 {
+        let span = self.span();
+        while let Some(&(instant, _)) = self.symbols.front() {
+            if self.clock - instant > span {
+                self.symbols.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let mut sum = Complex32::new(0.0, 0.0);
+        for &(instant, symbol) in &self.symbols {
+            let offset = self.clock - instant;
+            if offset < 0.0 {
+                break;
+            }
+            let position = offset * self.phases as f64;
+            let index = position as usize;
+            let frac = (position - index as f64) as f32;
+            let value = match self.pulse.get(index + 1) {
+                Some(&next) => self.pulse[index] + frac * (next - self.pulse[index]),
+                None => self.pulse[index],
+            };
+            sum += symbol * value;
+        }
+
+        self.clock += 1.0;
+        sum
+    }
+
+    pub fn reset(&mut self) {
+        self.symbols.clear();
+        self.next_instant = 0.0;
+        self.clock = 0.0;
+    }
+}
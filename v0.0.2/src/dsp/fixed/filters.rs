@@ -0,0 +1,88 @@
+// src/dsp/fixed/filters.rs
+use super::*;
+use crate::dsp::filters;
+
+/// Coefficient and feedback state format: Q30, as a1 reaches ±2
+const COEFF_BITS: u32 = 30;
+
+#[inline]
+fn to_q30(x: f32) -> i32 {
+    (x as f64 * (1u64 << COEFF_BITS) as f64).round() as i32
+}
+
+/// Second-order IIR filter (Direct Form I), Q15 in and out.
+///
+/// Direct Form I keeps the feedback path on the outputs, which are held in
+/// Q30 between samples so the recursion doesn't round to Q15 each time
+/// round. Designed as the float `BiquadFilter` and quantized.
+#[derive(Clone)]
+pub struct BiquadFilter {
+    b: [i32; 3],
+    a: [i32; 2],
+
+    // Input history (Q15) and output history (Q30)
+    x1: i32,
+    x2: i32,
+    y1: i32,
+    y2: i32,
+}
+
+impl BiquadFilter {
+    /// Quantize a float design
+    pub fn from_float(filter: &filters::BiquadFilter) -> Self {
+        let (b, a) = filter.coefficients();
+        Self {
+            b: [to_q30(b[0]), to_q30(b[1]), to_q30(b[2])],
+            a: [to_q30(a[0]), to_q30(a[1])],
+            x1: 0,
+            x2: 0,
+            y1: 0,
+            y2: 0,
+        }
+    }
+
+    pub fn bandpass(center_freq: f32, bandwidth: f32, sample_rate: f32) -> Self {
+        Self::from_float(&filters::BiquadFilter::bandpass(center_freq, bandwidth, sample_rate))
+    }
+
+    pub fn lowpass(cutoff_freq: f32, q: f32, sample_rate: f32) -> Self {
+        Self::from_float(&filters::BiquadFilter::lowpass(cutoff_freq, q, sample_rate))
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process(&mut self, input: Q15) -> Q15 {
+        let x = input as i32;
+
+        // Q45 feed-forward lifted to Q60, Q60 feedback
+        let feed_forward = self.b[0] as i64 * x as i64
+            + self.b[1] as i64 * self.x1 as i64
+            + self.b[2] as i64 * self.x2 as i64;
+        let feedback = self.a[0] as i64 * self.y1 as i64 + self.a[1] as i64 * self.y2 as i64;
+        let acc = (feed_forward << 15) - feedback;
+
+        let y = ((acc + (1 << (COEFF_BITS - 1))) >> COEFF_BITS).clamp(i32::MIN as i64, i32::MAX as i64) as i32;
+
+        self.x2 = self.x1;
+        self.x1 = x;
+        self.y2 = self.y1;
+        self.y1 = y;
+
+        saturate_q15((y + (1 << 14)) >> 15)
+    }
+
+    /// Process block of samples
+    pub fn process_block(&mut self, input: &[Q15], output: &mut [Q15]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.process(*i);
+        }
+    }
+
+    /// Reset filter state
+    pub fn reset(&mut self) {
+        self.x1 = 0;
+        self.x2 = 0;
+        self.y1 = 0;
+        self.y2 = 0;
+    }
+}
@@ -0,0 +1,153 @@
+// src/dsp/fixed/fir.rs
+//
+// Streaming FIR filter and fractional-instant pulse shaper in Q15, taking
+// their taps from the float designs in dsp::fir.
+use super::*;
+use num_complex::Complex;
+use alloc::collections::VecDeque;
+
+/// Sample clock instants and pulse positions carry 32 fractional bits
+const INSTANT_ONE: i64 = 1 << 32;
+
+/// Streaming FIR filter, Q15 taps and samples, 64-bit accumulator
+#[derive(Clone)]
+pub struct FirFilter {
+    taps: Vec<i32>,
+    // Circular delay line, twice the length so the window is contiguous
+    delay: Vec<i32>,
+    pos: usize,
+}
+
+impl FirFilter {
+    pub fn new(taps: &[f32]) -> Self {
+        let taps: Vec<i32> = taps.iter().map(|&t| (t * Q15_ONE as f32).round() as i32).collect();
+        let len = taps.len().max(1);
+        Self {
+            taps,
+            delay: vec![0; 2 * len],
+            pos: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.taps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.taps.is_empty()
+    }
+
+    /// Filter one sample held in i32, so baseband values may exceed full
+    /// scale; Q15 out
+    #[inline]
+    pub fn process(&mut self, input: i32) -> i32 {
+        let len = self.taps.len();
+        if len == 0 {
+            return input;
+        }
+        self.pos = if self.pos == 0 { len - 1 } else { self.pos - 1 };
+        self.delay[self.pos] = input;
+        self.delay[self.pos + len] = input;
+
+        let acc: i64 = self.taps.iter()
+            .zip(&self.delay[self.pos..self.pos + len])
+            .map(|(&t, &x)| t as i64 * x as i64)
+            .sum();
+        ((acc + (1 << 14)) >> 15) as i32
+    }
+
+    pub fn reset(&mut self) {
+        self.delay.iter_mut().for_each(|x| *x = 0);
+        self.pos = 0;
+    }
+}
+
+/// Pulse shaper for a symbol clock that need not divide the sample rate,
+/// as `dsp::fir::PulseShaper` with instants in 32.32 fixed point
+pub struct PulseShaper {
+    pulse: Vec<i32>,
+    phases: i64,
+    samples_per_symbol: i64,
+    span: i64,
+    // Instant and value (Q15) of the symbols whose pulses haven't ended
+    symbols: VecDeque<(i64, Complex<i32>)>,
+    next_instant: i64,
+    clock: i64,
+}
+
+impl PulseShaper {
+    /// `pulse` is sampled at `phases` times the output rate
+    pub fn new(pulse: &[f32], phases: usize, samples_per_symbol: f32) -> Self {
+        let mut pulse: Vec<i32> = pulse.iter().map(|&t| (t * Q15_ONE as f32).round() as i32).collect();
+        if pulse.is_empty() {
+            pulse.push(Q15_ONE);
+        }
+        let phases = phases.max(1) as i64;
+        Self {
+            span: (pulse.len() as i64 - 1) * INSTANT_ONE / phases,
+            pulse,
+            phases,
+            samples_per_symbol: (samples_per_symbol as f64 * INSTANT_ONE as f64).round() as i64,
+            symbols: VecDeque::new(),
+            next_instant: 0,
+            clock: 0,
+        }
+    }
+
+    /// Queue the next symbol; returns how many samples to take before the
+    /// one after it is due
+    pub fn push(&mut self, symbol: Complex<i32>) -> usize {
+        self.symbols.push_back((self.next_instant, symbol));
+        self.next_instant += self.samples_per_symbol;
+        let due = (self.next_instant + INSTANT_ONE - 1) >> 32;
+        (due - (self.clock >> 32)).max(0) as usize
+    }
+
+    /// Samples still to come from queued pulses
+    pub fn tail_len(&self) -> usize {
+        match self.symbols.back() {
+            Some(&(instant, _)) => {
+                let end = instant + self.span + INSTANT_ONE - self.clock;
+                ((end + INSTANT_ONE - 1) >> 32).max(0) as usize
+            }
+            None => 0,
+        }
+    }
+
+    /// Next output sample, Q15 in i32
+    #[inline]
+    pub fn next_sample(&mut self) -> Complex<i32> {
+        while let Some(&(instant, _)) = self.symbols.front() {
+            if self.clock - instant > self.span {
+                self.symbols.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let mut sum = Complex::new(0, 0);
+        for &(instant, symbol) in &self.symbols {
+            let offset = self.clock - instant;
+            if offset < 0 {
+                break;
+            }
+            let position = offset * self.phases;
+            let index = (position >> 32) as usize;
+            let fraction = position & (INSTANT_ONE - 1);
+            let value = match self.pulse.get(index + 1) {
+                Some(&next) => self.pulse[index] + (((next - self.pulse[index]) as i64 * fraction) >> 32) as i32,
+                None => self.pulse[index],
+            };
+            sum += Complex::new(mul_q15(symbol.re, value), mul_q15(symbol.im, value));
+        }
+
+        self.clock += INSTANT_ONE;
+        sum
+    }
+
+    pub fn reset(&mut self) {
+        self.symbols.clear();
+        self.next_instant = 0;
+        self.clock = 0;
+    }
+}
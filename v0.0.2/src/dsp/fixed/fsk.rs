@@ -0,0 +1,363 @@
+// src/dsp/fixed/fsk.rs
+//
+// FSK in Q15: the float chain's bandpass, quadrature correlator and
+// transition-driven bit clock, without the AGC (the correlator compares
+// tones and the bit clock's thresholds track the signal level, so neither
+// cares about the absolute level).
+use super::*;
+use super::filters::BiquadFilter;
+use super::oscillator::{cos_q15, phase_increment, sin_q15, NCO};
+use crate::dsp::framing::{AsyncDeframer, AsyncFramer, CharFormat, FramingStats};
+use crate::dsp::fsk::FSKMode;
+use crate::dsp::TxLevel;
+
+/// FSK modulator, Q15 out. Rates are taken to the nearest hertz so bit
+/// boundaries land exactly where the float modulator puts them.
+pub struct FSKModulator {
+    mode: FSKMode,
+    osc: NCO,
+    sample_rate: u64,
+    baud_rate: u64,
+    sample_counter: u64,
+    bit_counter: u64,
+    framer: AsyncFramer,
+    tx_level: TxLevel,
+}
+
+impl FSKModulator {
+    pub fn new(mode: FSKMode, baud_rate: f32, sample_rate: f32) -> Self {
+        let tx_level = TxLevel::default();
+        Self {
+            mode,
+            osc: NCO::new(mode.space_freq(), sample_rate, tx_level.sine_amplitude()),
+            sample_rate: sample_rate.round() as u64,
+            baud_rate: (baud_rate.round() as u64).max(1),
+            sample_counter: 0,
+            bit_counter: 0,
+            framer: AsyncFramer::new(CharFormat::default()),
+            tx_level,
+        }
+    }
+
+    /// Line level of the tones (default 0 dBm0)
+    pub fn set_tx_level(&mut self, level: TxLevel) {
+        self.osc.set_amplitude(level.sine_amplitude());
+        self.tx_level = level;
+    }
+
+    pub fn tx_level(&self) -> TxLevel {
+        self.tx_level
+    }
+
+    /// Modulate bits to Q15 samples
+    pub fn modulate(&mut self, bits: &[bool]) -> Vec<Q15> {
+        let mut samples = Vec::new();
+
+        for &bit in bits {
+            let freq = if bit { self.mode.mark_freq() } else { self.mode.space_freq() };
+            self.osc.set_frequency(freq);
+
+            // Bit k ends on the sample nearest k * sample_rate / baud_rate
+            self.bit_counter += 1;
+            let bit_end = (2 * self.bit_counter * self.sample_rate + self.baud_rate) / (2 * self.baud_rate);
+            while self.sample_counter < bit_end {
+                samples.push(self.osc.next_sample());
+                self.sample_counter += 1;
+            }
+        }
+
+        samples
+    }
+
+    /// Modulate bytes as start/stop framed characters
+    pub fn modulate_bytes(&mut self, data: &[u8]) -> Vec<Q15> {
+        let bits = self.framer.frame(data);
+        self.modulate(&bits)
+    }
+
+    /// Mark idle
+    pub fn idle(&mut self, bits: usize) -> Vec<Q15> {
+        self.modulate(&vec![true; bits])
+    }
+
+    /// Send a break
+    pub fn modulate_break(&mut self) -> Vec<Q15> {
+        let bits = self.framer.break_bits();
+        self.modulate(&bits)
+    }
+
+    /// Character format used by `modulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.framer.set_format(format);
+    }
+}
+
+/// One tone's I/Q branch: mix with a table LO, integrate over the last bit
+struct QuadratureBranch {
+    phase: u32,
+    phase_increment: u32,
+    products: Vec<(i32, i32)>,
+    sum: (i32, i32),
+}
+
+impl QuadratureBranch {
+    fn new(freq: f32, len: usize, sample_rate: f32) -> Self {
+        Self {
+            phase: 0,
+            phase_increment: phase_increment(freq, sample_rate),
+            products: vec![(0, 0); len],
+            sum: (0, 0),
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, sample: i32, pos: usize) -> i32 {
+        let product = (mul_q15(sample, cos_q15(self.phase)), -mul_q15(sample, sin_q15(self.phase)));
+        self.phase = self.phase.wrapping_add(self.phase_increment);
+
+        let old = core::mem::replace(&mut self.products[pos], product);
+        self.sum.0 += product.0 - old.0;
+        self.sum.1 += product.1 - old.1;
+
+        let (i, q) = (self.sum.0 as i64, self.sum.1 as i64);
+        ((i * i + q * q) as u64).isqrt() as i32
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0;
+        self.products.iter_mut().for_each(|p| *p = (0, 0));
+        self.sum = (0, 0);
+    }
+}
+
+/// Non-coherent quadrature correlator pair, as the float
+/// `QuadratureCorrelator`: envelope difference of the mark and space
+/// correlations over one bit, Q15 (positive for mark)
+pub struct QuadratureCorrelator {
+    mark: QuadratureBranch,
+    space: QuadratureBranch,
+    pos: usize,
+}
+
+impl QuadratureCorrelator {
+    pub fn new(mode: FSKMode, samples_per_bit: usize, sample_rate: f32) -> Self {
+        let len = samples_per_bit.max(1);
+        Self {
+            mark: QuadratureBranch::new(mode.mark_freq(), len, sample_rate),
+            space: QuadratureBranch::new(mode.space_freq(), len, sample_rate),
+            pos: 0,
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: Q15) -> i32 {
+        let mark = self.mark.process(sample as i32, self.pos);
+        let space = self.space.process(sample as i32, self.pos);
+        self.pos = (self.pos + 1) % self.mark.products.len();
+        mark - space
+    }
+
+    pub fn reset(&mut self) {
+        self.mark.reset();
+        self.space.reset();
+        self.pos = 0;
+    }
+}
+
+/// Bit clock phase: one bit is 2^32
+const BIT: i64 = 1 << 32;
+const HALF_BIT: i64 = BIT / 2;
+
+/// Transitions taken at the acquisition gain before settling
+const ACQUISITION_TRANSITIONS: u32 = 8;
+
+/// Phase correction per unit error, Q16: 0.5 acquiring, 0.1 tracking,
+/// and the integrator's 0.1^2 / 4
+const ACQUISITION_GAIN: i64 = 1 << 15;
+const TRACKING_GAIN: i64 = 6554;
+const INTEGRATOR_GAIN: i64 = 164;
+
+/// Largest clock offset followed, 5% in Q32
+const MAX_CLOCK_OFFSET: i64 = BIT / 20;
+
+/// Bits without a transition after which the clock counts as unlocked,
+/// and after which the next transition re-acquires the phase
+const LOCK_TIMEOUT_BITS: u32 = 64;
+const RESYNC_IDLE_BITS: u32 = 12;
+
+/// Transition hysteresis, 0.3 of the average magnitude in Q8
+const TRANSITION_HYSTERESIS: i64 = 77;
+
+/// Soft decision magnitude smoothing, 1/128
+const LEVEL_SHIFT: u32 = 7;
+
+/// Transition-driven bit clock, the float `BitSync` in integer arithmetic
+pub struct BitSync {
+    step: i64,
+    phase: i64,
+    integrator: i64,
+
+    mark: bool,
+    level: i64,
+    prev_soft: i32,
+    crossing_phase: Option<i64>,
+
+    transitions: u32,
+    bits_since_transition: u32,
+}
+
+impl BitSync {
+    pub fn new(samples_per_bit: f32) -> Self {
+        Self {
+            step: (BIT as f64 / samples_per_bit as f64).round() as i64,
+            phase: 0,
+            integrator: 0,
+            mark: true,
+            level: 0,
+            prev_soft: 0,
+            crossing_phase: None,
+            transitions: 0,
+            bits_since_transition: LOCK_TIMEOUT_BITS,
+        }
+    }
+
+    /// Feed one soft decision (positive = mark); returns the bit on strobes
+    pub fn process(&mut self, soft: i32) -> Option<bool> {
+        let step = self.step + ((self.step * self.integrator) >> 32);
+        self.phase += step;
+
+        // Level in Q8 for the hysteresis
+        let magnitude = (soft.unsigned_abs() as i64) << 8;
+        self.level += (magnitude - self.level) >> LEVEL_SHIFT;
+
+        // Latest zero crossing, interpolated between the two samples
+        if soft != 0 && self.prev_soft != 0 && (soft < 0) != (self.prev_soft < 0) {
+            let prev = self.prev_soft as i64;
+            let fraction = (prev << 32) / (prev - soft as i64);
+            self.crossing_phase = Some(self.phase - (((BIT - fraction) * step) >> 32));
+        }
+        if soft != 0 {
+            self.prev_soft = soft;
+        }
+
+        // Confirmed transition: steer the clock from its crossing
+        let threshold = (TRANSITION_HYSTERESIS * self.level) >> 16;
+        let confirmed = if self.mark { (soft as i64) < -threshold } else { soft as i64 > threshold };
+        if confirmed {
+            self.mark = !self.mark;
+            if let Some(crossing) = self.crossing_phase.take() {
+                self.steer(crossing - HALF_BIT);
+            }
+        }
+
+        if self.phase < BIT {
+            return None;
+        }
+        self.phase -= BIT;
+        if let Some(crossing) = self.crossing_phase.as_mut() {
+            *crossing -= BIT;
+        }
+        self.bits_since_transition = self.bits_since_transition.saturating_add(1);
+        Some(self.mark)
+    }
+
+    fn steer(&mut self, raw_error: i64) {
+        // Wrapped to half a bit either way
+        let error = raw_error - (((raw_error + HALF_BIT) >> 32) << 32);
+        if self.transitions == 0 || self.bits_since_transition >= RESYNC_IDLE_BITS {
+            self.phase -= raw_error;
+            self.transitions = 0;
+        } else if self.transitions < ACQUISITION_TRANSITIONS {
+            self.phase -= (ACQUISITION_GAIN * error) >> 16;
+        } else {
+            self.phase -= (TRACKING_GAIN * error) >> 16;
+            self.integrator = (self.integrator - ((INTEGRATOR_GAIN * error) >> 16))
+                .clamp(-MAX_CLOCK_OFFSET, MAX_CLOCK_OFFSET);
+        }
+        self.transitions = self.transitions.saturating_add(1);
+        self.bits_since_transition = 0;
+    }
+
+    /// Estimated bit-rate offset of the far end, Q32 (positive = fast)
+    pub fn clock_offset(&self) -> i64 {
+        self.integrator
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.transitions >= ACQUISITION_TRANSITIONS && self.bits_since_transition < LOCK_TIMEOUT_BITS
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0;
+        self.integrator = 0;
+        self.mark = true;
+        self.level = 0;
+        self.prev_soft = 0;
+        self.crossing_phase = None;
+        self.transitions = 0;
+        self.bits_since_transition = LOCK_TIMEOUT_BITS;
+    }
+}
+
+/// FSK demodulator, Q15 in: bandpass, quadrature correlator, bit clock
+pub struct FSKDemodulator {
+    bandpass: BiquadFilter,
+    detector: QuadratureCorrelator,
+    bit_sync: BitSync,
+    deframer: AsyncDeframer,
+}
+
+impl FSKDemodulator {
+    pub fn new(mode: FSKMode, baud_rate: f32, sample_rate: f32) -> Self {
+        let (space_freq, mark_freq) = mode.frequencies();
+        let bandwidth = (mark_freq - space_freq).abs() * 2.0;
+        let samples_per_bit = sample_rate / baud_rate;
+
+        Self {
+            bandpass: BiquadFilter::bandpass(mode.center_freq(), bandwidth, sample_rate),
+            detector: QuadratureCorrelator::new(mode, samples_per_bit.round() as usize, sample_rate),
+            bit_sync: BitSync::new(samples_per_bit),
+            deframer: AsyncDeframer::new(CharFormat::default()),
+        }
+    }
+
+    /// Process Q15 samples and extract bits
+    pub fn demodulate(&mut self, samples: &[Q15]) -> Vec<bool> {
+        let mut bits = Vec::new();
+        for &sample in samples {
+            let filtered = self.bandpass.process(sample);
+            let soft = self.detector.process(filtered);
+            if let Some(bit) = self.bit_sync.process(soft) {
+                bits.push(bit);
+            }
+        }
+        bits
+    }
+
+    /// Demodulate start/stop framed characters to bytes
+    pub fn demodulate_bytes(&mut self, samples: &[Q15]) -> Vec<u8> {
+        let bits = self.demodulate(samples);
+        self.deframer.deframe_bytes(&bits)
+    }
+
+    /// Character format expected by `demodulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.deframer.set_format(format);
+    }
+
+    /// Character, parity, framing error and break counts
+    pub fn framing_stats(&self) -> &FramingStats {
+        self.deframer.stats()
+    }
+
+    pub fn is_bit_locked(&self) -> bool {
+        self.bit_sync.is_locked()
+    }
+
+    pub fn reset(&mut self) {
+        self.bandpass.reset();
+        self.detector.reset();
+        self.bit_sync.reset();
+        self.deframer.reset();
+    }
+}
@@ -0,0 +1,68 @@
+// src/dsp/fixed/goertzel.rs
+use super::*;
+
+/// Coefficient format (2cos spans ±2)
+const COEFF_BITS: u32 = 30;
+
+/// Single-frequency DFT using the Goertzel algorithm, Q15 in.
+///
+/// The state grows with the block (to about N/2 at full scale), so it is
+/// kept as Q15 in i32; the energy comes out in Q30, the float detector's
+/// value times 2^30.
+pub struct GoertzelDetector {
+    coefficient: i32,
+    s1: i32,
+    s2: i32,
+    n: usize,
+    block_size: usize,
+}
+
+impl GoertzelDetector {
+    /// Same bin as the float detector for `target_freq`
+    pub fn new(target_freq: f32, sample_rate: f32, block_size: usize) -> Self {
+        let k = (0.5 + (block_size as f32 * target_freq / sample_rate)) as usize;
+        let omega = 2.0 * core::f64::consts::PI * k as f64 / block_size as f64;
+        let coefficient = (2.0 * omega.cos() * (1u64 << COEFF_BITS) as f64).round() as i32;
+
+        Self {
+            coefficient,
+            s1: 0,
+            s2: 0,
+            n: 0,
+            block_size,
+        }
+    }
+
+    /// Process single sample
+    #[inline]
+    pub fn process_sample(&mut self, sample: Q15) {
+        let feedback = ((self.coefficient as i64 * self.s1 as i64 + (1 << (COEFF_BITS - 1))) >> COEFF_BITS) as i32;
+        let s0 = sample as i32 + feedback - self.s2;
+        self.s2 = self.s1;
+        self.s1 = s0;
+        self.n += 1;
+    }
+
+    /// Energy at the target frequency, Q30
+    pub fn magnitude_squared(&self) -> i64 {
+        let (s1, s2) = (self.s1 as i64, self.s2 as i64);
+        s1 * s1 + s2 * s2 - (((self.coefficient as i64 * s1) >> COEFF_BITS) * s2)
+    }
+
+    /// Magnitude at the target frequency, Q15
+    pub fn magnitude(&self) -> i32 {
+        (self.magnitude_squared().max(0) as u64).isqrt() as i32
+    }
+
+    /// Reset detector
+    pub fn reset(&mut self) {
+        self.s1 = 0;
+        self.s2 = 0;
+        self.n = 0;
+    }
+
+    /// Check if block is complete
+    pub fn is_complete(&self) -> bool {
+        self.n >= self.block_size
+    }
+}
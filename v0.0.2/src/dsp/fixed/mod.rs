@@ -0,0 +1,65 @@
+// src/dsp/fixed/mod.rs
+//
+// Fixed-point versions of the NCO, biquad, Goertzel, FSK and QAM chains for
+// processors without an FPU (`fixed-point` feature). Samples are Q15;
+// accumulators, loop states and coefficients that need the range are
+// wider. Filters and loop gains are still designed in floating point, once,
+// at construction; the per-sample paths are integer only.
+use super::*;
+
+pub mod oscillator;
+pub mod filters;
+pub mod fir;
+pub mod goertzel;
+pub mod fsk;
+pub mod qam_modem;
+
+/// Q15: 1.0 is 2^15, samples span [-1.0, 1.0)
+pub type Q15 = i16;
+
+/// Q31: 1.0 is 2^31
+pub type Q31 = i32;
+
+/// 1.0 in Q15, held wider so products and sums can overshoot it
+pub const Q15_ONE: i32 = 1 << 15;
+
+#[inline]
+pub fn to_q15(x: f32) -> Q15 {
+    (x * 32768.0).round().clamp(i16::MIN as f32, i16::MAX as f32) as Q15
+}
+
+#[inline]
+pub fn q15_to_f32(x: Q15) -> f32 {
+    x as f32 / 32768.0
+}
+
+#[inline]
+pub fn to_q31(x: f32) -> Q31 {
+    (x as f64 * 2147483648.0).round().clamp(i32::MIN as f64, i32::MAX as f64) as Q31
+}
+
+#[inline]
+pub fn q31_to_f32(x: Q31) -> f32 {
+    (x as f64 / 2147483648.0) as f32
+}
+
+/// Product of two Q15 values held in i32, rounded
+#[inline]
+pub fn mul_q15(a: i32, b: i32) -> i32 {
+    ((a as i64 * b as i64 + (1 << 14)) >> 15) as i32
+}
+
+/// Clip a wide Q15 value to a sample
+#[inline]
+pub fn saturate_q15(x: i32) -> Q15 {
+    x.clamp(i16::MIN as i32, i16::MAX as i32) as Q15
+}
+
+/// Float audio to Q15 samples, clipping at full scale
+pub fn samples_to_q15(samples: &[f32]) -> Vec<Q15> {
+    samples.iter().map(|&x| to_q15(x)).collect()
+}
+
+pub fn samples_from_q15(samples: &[Q15]) -> Vec<f32> {
+    samples.iter().map(|&x| q15_to_f32(x)).collect()
+}
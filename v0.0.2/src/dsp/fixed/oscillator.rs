@@ -0,0 +1,138 @@
+// src/dsp/fixed/oscillator.rs
+use super::*;
+use num_complex::Complex;
+
+/// Quarter-wave sine table resolution (256 steps per quarter turn)
+const QUARTER_BITS: u32 = 8;
+const QUARTER_LEN: usize = 1 << QUARTER_BITS;
+
+/// Phase bits below the table index, interpolated linearly
+const FRACTION_BITS: u32 = 30 - QUARTER_BITS;
+
+/// sin over the first quarter turn in Q15, plus the endpoint
+static QUARTER_SINE: [i32; QUARTER_LEN + 1] = quarter_sine();
+
+/// Taylor series to x^17; under 1e-11 out on [0, pi/2]
+const fn quarter_sine() -> [i32; QUARTER_LEN + 1] {
+    let mut table = [0; QUARTER_LEN + 1];
+    let mut i = 0;
+    while i <= QUARTER_LEN {
+        let x = i as f64 * core::f64::consts::FRAC_PI_2 / QUARTER_LEN as f64;
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 1;
+        while n < 9 {
+            term = -term * x * x / ((2 * n) * (2 * n + 1)) as f64;
+            sum += term;
+            n += 1;
+        }
+        table[i] = (sum * 32768.0 + 0.5) as i32;
+        i += 1;
+    }
+    table
+}
+
+/// sin of a phase word (2^32 = one turn), Q15
+#[inline]
+pub fn sin_q15(phase: u32) -> i32 {
+    let quadrant = phase >> 30;
+    let offset = phase & ((1 << 30) - 1);
+    let offset = if quadrant & 1 == 1 { (1 << 30) - offset } else { offset };
+
+    let index = (offset >> FRACTION_BITS) as usize;
+    let fraction = (offset & ((1 << FRACTION_BITS) - 1)) as i64;
+    let low = QUARTER_SINE[index];
+    let high = QUARTER_SINE[(index + 1).min(QUARTER_LEN)];
+    let value = low + (((high - low) as i64 * fraction) >> FRACTION_BITS) as i32;
+
+    if quadrant >= 2 { -value } else { value }
+}
+
+/// cos of a phase word, Q15
+#[inline]
+pub fn cos_q15(phase: u32) -> i32 {
+    sin_q15(phase.wrapping_add(1 << 30))
+}
+
+/// Phase word step per sample for `frequency`; negative frequencies wrap
+pub fn phase_increment(frequency: f32, sample_rate: f32) -> u32 {
+    ((frequency as f64 / sample_rate as f64) * 4294967296.0).round() as i64 as u32
+}
+
+/// Phase-accumulating sine generator, Q15 out
+pub struct NCO {
+    phase: u32,
+    phase_increment: u32,
+    amplitude: i32,
+    sample_rate: f32,
+}
+
+impl NCO {
+    pub fn new(frequency: f32, sample_rate: f32, amplitude: f32) -> Self {
+        Self {
+            phase: 0,
+            phase_increment: phase_increment(frequency, sample_rate),
+            amplitude: (amplitude * Q15_ONE as f32).round() as i32,
+            sample_rate,
+        }
+    }
+
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.phase_increment = phase_increment(frequency, self.sample_rate);
+    }
+
+    pub fn frequency(&self) -> f32 {
+        (self.phase_increment as i32 as f64 / 4294967296.0 * self.sample_rate as f64) as f32
+    }
+
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = (amplitude * Q15_ONE as f32).round() as i32;
+    }
+
+    /// Generate next sample
+    #[inline]
+    pub fn next_sample(&mut self) -> Q15 {
+        let sample = mul_q15(self.amplitude, sin_q15(self.phase));
+        self.phase = self.phase.wrapping_add(self.phase_increment);
+        saturate_q15(sample)
+    }
+
+    /// Generate block of samples
+    pub fn generate(&mut self, samples: &mut [Q15]) {
+        for sample in samples.iter_mut() {
+            *sample = self.next_sample();
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0;
+    }
+}
+
+/// Quadrature NCO producing (cos, sin) pairs in Q15
+#[derive(Debug, Clone)]
+pub struct ComplexNCO {
+    phase: u32,
+    phase_increment: u32,
+}
+
+impl ComplexNCO {
+    pub fn new(frequency: f32, sample_rate: f32) -> Self {
+        Self {
+            phase: 0,
+            phase_increment: phase_increment(frequency, sample_rate),
+        }
+    }
+
+    /// Current phasor, then advance
+    #[inline]
+    pub fn next_phasor(&mut self) -> Complex<i32> {
+        let phasor = Complex::new(cos_q15(self.phase), sin_q15(self.phase));
+        self.phase = self.phase.wrapping_add(self.phase_increment);
+        phasor
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0;
+    }
+}
@@ -0,0 +1,373 @@
+// src/dsp/fixed/qam_modem.rs
+//
+// V.22/V.22bis/Bell 212A in Q15. The modulator covers every mode; the
+// demodulator detects DPSK differentially (no carrier loop, equalizer or
+// AGC), which the DPSK modes allow and 16-QAM doesn't.
+use super::*;
+use super::fir::{FirFilter, PulseShaper};
+use super::oscillator::ComplexNCO;
+use crate::dsp::framing::{AsyncDeframer, AsyncFramer, CharFormat, FramingStats};
+use crate::dsp::qam::{map_to_qam16, qam16_differential_encode, QAMMode};
+use crate::dsp::qam_modem::{rrc_taps, unit_power, DEFAULT_ROLLOFF, FLUSH_IDLE_SYMBOLS, MARK_LEAD_IN_MS, TIMING_LOOP_BANDWIDTH, TX_PULSE_PHASES};
+use crate::dsp::scrambler::Scrambler;
+use crate::dsp::TxLevel;
+use num_complex::Complex;
+
+/// DPSK points for quadrant 0..3 (0°, 90°, 180°, 270°)
+const DPSK_POINTS: [(i32, i32); 4] = [(Q15_ONE, 0), (0, Q15_ONE), (-Q15_ONE, 0), (0, -Q15_ONE)];
+
+/// QAM/DPSK modulator, Q15 out
+pub struct QAMModulator {
+    mode: QAMMode,
+    carrier: ComplexNCO,
+    scrambler: Scrambler,
+    dpsk_quadrant: u8,
+    qam16_point: u8,
+    qam16_points: [Complex<i32>; 16],
+    shaper: PulseShaper,
+    framer: AsyncFramer,
+    tx_level: TxLevel,
+    gain: i32,
+}
+
+impl QAMModulator {
+    pub fn new(mode: QAMMode, carrier_freq: f32, sample_rate: f32) -> Self {
+        Self::with_rolloff(mode, carrier_freq, sample_rate, DEFAULT_ROLLOFF)
+    }
+
+    /// Modulator with a specific RRC roll-off (0.0-1.0)
+    pub fn with_rolloff(mode: QAMMode, carrier_freq: f32, sample_rate: f32, rolloff: f32) -> Self {
+        let symbol_rate = mode.symbol_rate();
+        let samples_per_symbol = sample_rate / symbol_rate;
+
+        // Same pulse table as the float modulator
+        let phases = TX_PULSE_PHASES as f32;
+        let gain = (samples_per_symbol * phases).sqrt();
+        let pulse = rrc_taps(symbol_rate, sample_rate * phases, rolloff, gain);
+
+        let mut qam16_points = [Complex::new(0, 0); 16];
+        for (bits, point) in qam16_points.iter_mut().enumerate() {
+            let p = map_to_qam16(bits as u8);
+            *point = Complex::new(to_q15(p.re) as i32, to_q15(p.im) as i32);
+        }
+
+        let mut framer = AsyncFramer::v14(CharFormat::default());
+        let lead_in_symbols = (MARK_LEAD_IN_MS * symbol_rate / 1000.0) as usize;
+        framer.set_lead_in(lead_in_symbols * mode.bits_per_symbol());
+
+        let tx_level = TxLevel::default();
+
+        Self {
+            mode,
+            carrier: ComplexNCO::new(carrier_freq, sample_rate),
+            scrambler: Scrambler::new(),
+            dpsk_quadrant: 0,
+            qam16_point: 0,
+            qam16_points,
+            shaper: PulseShaper::new(&pulse, TX_PULSE_PHASES, samples_per_symbol),
+            framer,
+            tx_level,
+            gain: q15_gain(tx_level, mode),
+        }
+    }
+
+    /// Line level of the signal (default 0 dBm0)
+    pub fn set_tx_level(&mut self, level: TxLevel) {
+        self.gain = q15_gain(level, self.mode);
+        self.tx_level = level;
+    }
+
+    pub fn tx_level(&self) -> TxLevel {
+        self.tx_level
+    }
+
+    /// Modulate bits to Q15 samples
+    pub fn modulate(&mut self, bits: &[bool]) -> Vec<Q15> {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        let mut samples = Vec::new();
+
+        for symbol_bits in bits.chunks_exact(bits_per_symbol) {
+            let mut scrambled = 0u8;
+            for (i, &bit) in symbol_bits.iter().enumerate() {
+                if self.scrambler.scramble_bit(bit) {
+                    scrambled |= 1 << i;
+                }
+            }
+
+            let baseband_symbol = match self.mode {
+                QAMMode::V22 | QAMMode::Bell212A => {
+                    self.dpsk_quadrant = (self.dpsk_quadrant + (scrambled & 0x03)) & 0x03;
+                    let (re, im) = DPSK_POINTS[self.dpsk_quadrant as usize];
+                    Complex::new(re, im)
+                }
+                QAMMode::V22bis => {
+                    self.qam16_point = qam16_differential_encode(scrambled, self.qam16_point);
+                    self.qam16_points[self.qam16_point as usize]
+                }
+            };
+
+            for _ in 0..self.shaper.push(baseband_symbol) {
+                samples.push(self.shape_and_mix());
+            }
+        }
+
+        samples
+    }
+
+    /// Next pulse-shaped baseband sample, mixed up to the carrier
+    #[inline]
+    fn shape_and_mix(&mut self) -> Q15 {
+        let lo = self.carrier.next_phasor();
+        let baseband = self.shaper.next_sample();
+
+        // Re{(i + jq) e^(jwt)}
+        let passband = mul_q15(baseband.re, lo.re) - mul_q15(baseband.im, lo.im);
+        saturate_q15(mul_q15(self.gain, passband))
+    }
+
+    /// Finish the data sent so far with `FLUSH_IDLE_SYMBOLS` of mark idle,
+    /// so the far end's receiver gives up every character, and the pulse
+    /// tails
+    pub fn flush(&mut self) -> Vec<Q15> {
+        let mut samples = self.idle(FLUSH_IDLE_SYMBOLS * self.mode.bits_per_symbol());
+        samples.extend((0..self.shaper.tail_len()).map(|_| self.shape_and_mix()));
+        samples
+    }
+
+    /// Modulate bytes as async characters through V.14 conversion
+    pub fn modulate_bytes(&mut self, data: &[u8]) -> Vec<Q15> {
+        let bits = self.framer.frame(data);
+        self.modulate_padded(bits)
+    }
+
+    /// Mark idle, rounded up to whole symbols
+    pub fn idle(&mut self, bits: usize) -> Vec<Q15> {
+        self.modulate_padded(vec![true; bits])
+    }
+
+    /// Send a V.14 break
+    pub fn modulate_break(&mut self) -> Vec<Q15> {
+        let bits = self.framer.break_bits();
+        self.modulate_padded(bits)
+    }
+
+    fn modulate_padded(&mut self, mut bits: Vec<bool>) -> Vec<Q15> {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        bits.resize(bits.len().div_ceil(bits_per_symbol) * bits_per_symbol, true);
+        self.modulate(&bits)
+    }
+
+    /// Character format used by `modulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.framer.set_format(format);
+    }
+
+    pub fn reset(&mut self) {
+        self.framer.reset();
+        self.scrambler.reset();
+        self.carrier.reset();
+        self.dpsk_quadrant = 0;
+        self.qam16_point = 0;
+        self.shaper.reset();
+    }
+}
+
+/// Transmit gain for `level` in Q15 (above 1.0 is fine, it's held in i32)
+fn q15_gain(level: TxLevel, mode: QAMMode) -> i32 {
+    (level.gain(unit_power(mode)) * Q15_ONE as f32).round() as i32
+}
+
+/// Timing loop countdown: one sample is 2^32
+const SAMPLE: i64 = 1 << 32;
+
+/// Symbol power smoothing for the timing error normalization (1/64)
+const POWER_SHIFT: u32 = 6;
+
+/// Gardner timing recovery as the float `GardnerTED`, with a linear
+/// interpolator and the error normalized by the symbol power in place of
+/// an AGC
+struct GardnerTED {
+    half_symbol: i64,
+    proportional_gain: i64,
+    integral_gain: i64,
+    integrator: i64,
+
+    prev_sample: Complex<i32>,
+    newest: Complex<i32>,
+    countdown: i64,
+    at_midpoint: bool,
+
+    midpoint: Complex<i32>,
+    prev_symbol: Complex<i32>,
+    symbol_power: i64,
+}
+
+impl GardnerTED {
+    fn new(samples_per_symbol: f32, loop_bandwidth: f32, first_strobe: f32) -> Self {
+        // Loop filter design as the float loop, gains in countdown units
+        // per unit (Q15) error
+        let zeta = 0.707;
+        let detector_gain = 2.0;
+        let theta = loop_bandwidth / (zeta + 0.25 / zeta);
+        let denom = 1.0 + 2.0 * zeta * theta + theta * theta;
+        let kp = 4.0 * zeta * theta / denom / detector_gain * samples_per_symbol;
+        let ki = 4.0 * theta * theta / denom / detector_gain * samples_per_symbol;
+
+        Self {
+            half_symbol: (samples_per_symbol as f64 / 2.0 * SAMPLE as f64).round() as i64,
+            proportional_gain: (kp as f64 * SAMPLE as f64).round() as i64,
+            integral_gain: (ki as f64 * SAMPLE as f64).round() as i64,
+            integrator: 0,
+            prev_sample: Complex::new(0, 0),
+            newest: Complex::new(0, 0),
+            countdown: ((first_strobe.max(0.0) + 1.0) as f64 * SAMPLE as f64).round() as i64,
+            at_midpoint: false,
+            midpoint: Complex::new(0, 0),
+            prev_symbol: Complex::new(0, 0),
+            symbol_power: (Q15_ONE as i64) * (Q15_ONE as i64),
+        }
+    }
+
+    /// Feed one sample; returns the interpolated symbol on symbol strobes
+    fn process(&mut self, sample: Complex<i32>) -> Option<Complex<i32>> {
+        self.prev_sample = core::mem::replace(&mut self.newest, sample);
+
+        self.countdown -= SAMPLE;
+        if self.countdown > 0 {
+            return None;
+        }
+
+        // Strobe falls `mu` samples before the newest one
+        let mu = (-self.countdown).min(SAMPLE);
+        let lerp = |newest: i32, prev: i32| newest + (((prev - newest) as i64 * mu) >> 32) as i32;
+        let value = Complex::new(lerp(self.newest.re, self.prev_sample.re), lerp(self.newest.im, self.prev_sample.im));
+
+        if self.at_midpoint {
+            self.midpoint = value;
+            self.at_midpoint = false;
+            self.countdown += self.half_symbol;
+            return None;
+        }
+
+        // Gardner error Re{ conj(midpoint) * (previous - current) }, Q30,
+        // normalized to unit symbol power and brought to Q15
+        let diff = self.prev_symbol - value;
+        let raw = self.midpoint.re as i64 * diff.re as i64 + self.midpoint.im as i64 * diff.im as i64;
+        let error = (raw << 15) / self.symbol_power.max(1);
+
+        self.integrator += (error * self.integral_gain) >> 15;
+        let limit = self.half_symbol / 2;
+        let adjustment = (((error * self.proportional_gain) >> 15) + self.integrator).clamp(-limit, limit);
+
+        let power = value.re as i64 * value.re as i64 + value.im as i64 * value.im as i64;
+        self.symbol_power += (power - self.symbol_power) >> POWER_SHIFT;
+
+        self.prev_symbol = value;
+        self.at_midpoint = true;
+        self.countdown += self.half_symbol + adjustment;
+
+        Some(value)
+    }
+}
+
+/// DPSK demodulator for V.22 and Bell 212A, Q15 in.
+///
+/// Mixes down with a table carrier, matched-filters, recovers symbol
+/// timing and decides each dibit from the phase change between symbols,
+/// which a small carrier offset doesn't disturb.
+pub struct QAMDemodulator {
+    mode: QAMMode,
+    carrier: ComplexNCO,
+    rx_filter_i: FirFilter,
+    rx_filter_q: FirFilter,
+    timing: GardnerTED,
+    descrambler: Scrambler,
+    prev_symbol: Complex<i32>,
+    deframer: AsyncDeframer,
+}
+
+impl QAMDemodulator {
+    pub fn new(mode: QAMMode, carrier_freq: f32, sample_rate: f32) -> Result<Self, String> {
+        if mode == QAMMode::V22bis {
+            return Err("fixed-point demodulator handles DPSK modes only; V.22bis needs the float receiver".to_string());
+        }
+
+        let symbol_rate = mode.symbol_rate();
+        let samples_per_symbol = sample_rate / symbol_rate;
+
+        // Matched filters; the real mix loses half the amplitude to the
+        // image, so they get twice the float receiver's gain
+        let taps = rrc_taps(symbol_rate, sample_rate, DEFAULT_ROLLOFF, 2.0 / samples_per_symbol.sqrt());
+
+        // First strobe where the back-to-back TX+RX pulse peaks
+        let first_strobe = (taps.len() - 1) as f32 % samples_per_symbol;
+
+        Ok(Self {
+            mode,
+            carrier: ComplexNCO::new(carrier_freq, sample_rate),
+            rx_filter_i: FirFilter::new(&taps),
+            rx_filter_q: FirFilter::new(&taps),
+            timing: GardnerTED::new(samples_per_symbol, TIMING_LOOP_BANDWIDTH, first_strobe),
+            descrambler: Scrambler::new(),
+            prev_symbol: Complex::new(0, 0),
+            deframer: AsyncDeframer::v14(CharFormat::default()),
+        })
+    }
+
+    /// Demodulate Q15 samples to bits
+    pub fn demodulate(&mut self, samples: &[Q15]) -> Vec<bool> {
+        let mut bits = Vec::new();
+
+        for &sample in samples {
+            // x * e^(-jwt)
+            let lo = self.carrier.next_phasor();
+            let mixed = Complex::new(mul_q15(sample as i32, lo.re), -mul_q15(sample as i32, lo.im));
+
+            let baseband = Complex::new(self.rx_filter_i.process(mixed.re), self.rx_filter_q.process(mixed.im));
+
+            if let Some(symbol) = self.timing.process(baseband) {
+                let dibit = dpsk_dibit(symbol, self.prev_symbol);
+                self.prev_symbol = symbol;
+                for i in 0..self.mode.bits_per_symbol() {
+                    bits.push(self.descrambler.descramble_bit(dibit & (1 << i) != 0));
+                }
+            }
+        }
+
+        bits
+    }
+
+    /// Demodulate to bytes through V.14 conversion
+    pub fn demodulate_bytes(&mut self, samples: &[Q15]) -> Vec<u8> {
+        let bits = self.demodulate(samples);
+        self.deframer.deframe_bytes(&bits)
+    }
+
+    /// Character format expected by `demodulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.deframer.set_format(format);
+    }
+
+    /// Character, parity error, stop-bit insertion and break counts
+    pub fn framing_stats(&self) -> &FramingStats {
+        self.deframer.stats()
+    }
+}
+
+/// Dibit for the phase change from `prev` to `current`, by the quadrant of
+/// current * conj(prev) (0° is 00, 90° is 01, 180° is 10, 270° is 11)
+fn dpsk_dibit(current: Complex<i32>, prev: Complex<i32>) -> u8 {
+    let c = Complex::new(current.re as i64, current.im as i64);
+    let p = Complex::new(prev.re as i64, prev.im as i64);
+    let re = c.re * p.re + c.im * p.im;
+    let im = c.im * p.re - c.re * p.im;
+    if re > im.abs() {
+        0b00
+    } else if im >= re.abs() {
+        0b01
+    } else if -re > im.abs() {
+        0b10
+    } else {
+        0b11
+    }
+}
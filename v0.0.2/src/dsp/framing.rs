@@ -0,0 +1,500 @@
+// src/dsp/framing.rs
+//
+// Asynchronous character framing. FSK modems carry start/stop framed
+// characters directly; V.22/V.22bis run a synchronous bit stream and carry
+// the same characters through V.14 async-to-sync conversion, which lets the
+// transmitter drop an occasional stop bit (overspeed) and the receiver put
+// it back, and defines how a break is sent.
+use core::fmt;
+use core::str::FromStr;
+use alloc::{format, string::String, vec::Vec};
+
+/// Parity bit appended after the data bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    None,
+    Even,
+    Odd,
+    Mark,
+    Space,
+}
+
+impl Parity {
+    /// Parity bit for `data` (`None` has no bit)
+    fn bit(&self, data: u8) -> Option<bool> {
+        let odd_ones = data.count_ones() % 2 == 1;
+        match self {
+            Parity::None => None,
+            Parity::Even => Some(odd_ones),
+            Parity::Odd => Some(!odd_ones),
+            Parity::Mark => Some(true),
+            Parity::Space => Some(false),
+        }
+    }
+
+    fn letter(&self) -> char {
+        match self {
+            Parity::None => 'N',
+            Parity::Even => 'E',
+            Parity::Odd => 'O',
+            Parity::Mark => 'M',
+            Parity::Space => 'S',
+        }
+    }
+}
+
+/// Character format, e.g. 8N1 or 7E1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CharFormat {
+    /// 5 to 8
+    pub data_bits: u8,
+    pub parity: Parity,
+    /// 1 or 2
+    pub stop_bits: u8,
+}
+
+impl CharFormat {
+    pub const ASYNC_8N1: CharFormat = CharFormat { data_bits: 8, parity: Parity::None, stop_bits: 1 };
+    pub const ASYNC_8N2: CharFormat = CharFormat { data_bits: 8, parity: Parity::None, stop_bits: 2 };
+    pub const ASYNC_7E1: CharFormat = CharFormat { data_bits: 7, parity: Parity::Even, stop_bits: 1 };
+    pub const ASYNC_7O1: CharFormat = CharFormat { data_bits: 7, parity: Parity::Odd, stop_bits: 1 };
+
+    pub fn new(data_bits: u8, parity: Parity, stop_bits: u8) -> Result<Self, String> {
+        if !(5..=8).contains(&data_bits) {
+            return Err(format!("{} data bits not supported (5-8)", data_bits));
+        }
+        if !(1..=2).contains(&stop_bits) {
+            return Err(format!("{} stop bits not supported (1-2)", stop_bits));
+        }
+        Ok(Self { data_bits, parity, stop_bits })
+    }
+
+    /// Bits per character including start, parity and stop bits
+    pub fn frame_bits(&self) -> usize {
+        1 + self.data_bits as usize + self.parity_bits() + self.stop_bits as usize
+    }
+
+    fn parity_bits(&self) -> usize {
+        if self.parity == Parity::None { 0 } else { 1 }
+    }
+
+    fn data_mask(&self) -> u8 {
+        (0xFFu16 >> (8 - self.data_bits)) as u8
+    }
+}
+
+impl Default for CharFormat {
+    fn default() -> Self {
+        Self::ASYNC_8N1
+    }
+}
+
+impl fmt::Display for CharFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.data_bits, self.parity.letter(), self.stop_bits)
+    }
+}
+
+impl FromStr for CharFormat {
+    type Err = String;
+
+    /// Parse the usual shorthand: "8N1", "7E1", "7O2", ...
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.trim().chars().collect();
+        if chars.len() != 3 {
+            return Err(format!("bad character format {:?}", s));
+        }
+        let digit = |c: char| c.to_digit(10).map(|d| d as u8).ok_or_else(|| format!("bad character format {:?}", s));
+        let parity = match chars[1].to_ascii_uppercase() {
+            'N' => Parity::None,
+            'E' => Parity::Even,
+            'O' => Parity::Odd,
+            'M' => Parity::Mark,
+            'S' => Parity::Space,
+            other => return Err(format!("bad parity {:?} in {:?}", other, s)),
+        };
+        Self::new(digit(chars[0])?, parity, digit(chars[2])?)
+    }
+}
+
+/// V.14 basic signalling rate range: at most one stop bit deleted in every
+/// 8 characters
+const V14_DELETION_INTERVAL: usize = 8;
+
+/// Character framer: bytes in, line bits out (start bit, data LSB first,
+/// parity, stop bits)
+#[derive(Debug, Clone)]
+pub struct AsyncFramer {
+    format: CharFormat,
+    v14: bool,
+    delete_stop_bits: bool,
+    lead_in_bits: usize,
+    started: bool,
+    characters: usize,
+}
+
+impl AsyncFramer {
+    /// Start/stop framing as sent on an FSK line
+    pub fn new(format: CharFormat) -> Self {
+        Self {
+            format,
+            v14: false,
+            delete_stop_bits: false,
+            // Mark idle ahead of the first character, so the far end's
+            // receiver can arm before the first start bit
+            lead_in_bits: 2 * format.frame_bits(),
+            started: false,
+            characters: 0,
+        }
+    }
+
+    /// V.14 async-to-sync conversion for a synchronous modem
+    pub fn v14(format: CharFormat) -> Self {
+        Self {
+            v14: true,
+            ..Self::new(format)
+        }
+    }
+
+    pub fn format(&self) -> CharFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: CharFormat) {
+        self.format = format;
+    }
+
+    /// Mark bits sent before the first character after `new`/`reset`
+    pub fn set_lead_in(&mut self, bits: usize) {
+        self.lead_in_bits = bits;
+    }
+
+    /// Drop one stop bit every 8 characters, as a V.14 transmitter does
+    /// when its DTE runs slightly faster than the line. Only for V.14.
+    pub fn set_stop_bit_deletion(&mut self, delete: bool) {
+        self.delete_stop_bits = delete && self.v14;
+    }
+
+    /// Line bits for one character, preceded by the lead-in if it's the
+    /// first; no allocation
+    pub fn frame_char(&mut self, byte: u8) -> CharBits {
+        let lead_in = if self.started { 0 } else { self.lead_in_bits };
+        self.started = true;
+
+        // Start bit (0), then data LSB first
+        let data = byte & self.format.data_mask();
+        let mut word = (data as u16) << 1;
+        let mut len = 1 + self.format.data_bits;
+        if let Some(parity) = self.format.parity.bit(data) {
+            word |= (parity as u16) << len;
+            len += 1;
+        }
+
+        self.characters += 1;
+        let mut stop_bits = self.format.stop_bits;
+        if self.delete_stop_bits && self.characters.is_multiple_of(V14_DELETION_INTERVAL) {
+            stop_bits -= 1;
+        }
+        word |= ((1u16 << stop_bits) - 1) << len;
+        len += stop_bits;
+
+        CharBits { lead_in, word, len }
+    }
+
+    /// Append the line bits for one character
+    pub fn frame_byte(&mut self, byte: u8, out: &mut Vec<bool>) {
+        out.extend(self.frame_char(byte));
+    }
+
+    /// Line bits for a run of characters
+    pub fn frame(&mut self, data: &[u8]) -> Vec<bool> {
+        let mut bits = Vec::with_capacity(self.lead_in_bits + data.len() * self.format.frame_bits());
+        for &byte in data {
+            self.frame_byte(byte, &mut bits);
+        }
+        bits
+    }
+
+    /// Line bits for a break: 2M+3 bits of start polarity then 2M of stop
+    /// polarity, M being the character length (V.14 §4)
+    pub fn break_bits(&mut self) -> Vec<bool> {
+        let mut bits = Vec::new();
+        self.lead_in(&mut bits);
+        let m = self.format.frame_bits();
+        bits.extend(core::iter::repeat_n(false, 2 * m + 3));
+        bits.extend(core::iter::repeat_n(true, 2 * m));
+        bits
+    }
+
+    fn lead_in(&mut self, out: &mut Vec<bool>) {
+        if !self.started {
+            out.extend(core::iter::repeat_n(true, self.lead_in_bits));
+            self.started = true;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.started = false;
+        self.characters = 0;
+    }
+}
+
+/// One character's line bits from `AsyncFramer::frame_char`, as an
+/// iterator: `lead_in` mark bits, then `len` bits of `word`, LSB first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CharBits {
+    lead_in: usize,
+    word: u16,
+    len: u8,
+}
+
+impl Iterator for CharBits {
+    type Item = bool;
+
+    #[inline]
+    fn next(&mut self) -> Option<bool> {
+        if self.lead_in > 0 {
+            self.lead_in -= 1;
+            return Some(true);
+        }
+        if self.len == 0 {
+            return None;
+        }
+        let bit = self.word & 1 == 1;
+        self.word >>= 1;
+        self.len -= 1;
+        Some(bit)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.lead_in + self.len as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for CharBits {}
+
+/// One received character, or a break
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsyncChar {
+    Data(u8),
+    /// Received with the wrong parity bit
+    ParityError(u8),
+    /// Stop bit missing (start/stop framing only; V.14 takes it as deleted)
+    FramingError(u8),
+    Break,
+}
+
+impl AsyncChar {
+    /// The byte as a UART would pass it on, errors included; none for a
+    /// break
+    pub fn byte(self) -> Option<u8> {
+        match self {
+            AsyncChar::Data(byte) | AsyncChar::ParityError(byte) | AsyncChar::FramingError(byte) => Some(byte),
+            AsyncChar::Break => None,
+        }
+    }
+}
+
+/// Character counts kept by the deframer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FramingStats {
+    pub characters: usize,
+    pub parity_errors: usize,
+    pub framing_errors: usize,
+    /// Stop bits put back by a V.14 receiver
+    pub stop_bits_inserted: usize,
+    pub breaks: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RxState {
+    /// Waiting for a character time of mark before trusting start bits
+    Arming(usize),
+    /// Line at mark, waiting for a start bit
+    Idle,
+    /// Inside a character: bits received after the start bit
+    Receiving(usize),
+    /// After a framing error or break, waiting for the line to return to mark
+    Resync,
+}
+
+/// Character deframer: line bits in, characters out
+#[derive(Debug, Clone)]
+pub struct AsyncDeframer {
+    format: CharFormat,
+    v14: bool,
+    state: RxState,
+    shift: u16,
+    zero_run: usize,
+    // V.14: an all-zero character without its stop bit may be the start of
+    // a break, so it's held back until the line shows which
+    held_nul: bool,
+    stats: FramingStats,
+}
+
+impl AsyncDeframer {
+    pub fn new(format: CharFormat) -> Self {
+        Self {
+            format,
+            v14: false,
+            state: RxState::Arming(0),
+            shift: 0,
+            zero_run: 0,
+            held_nul: false,
+            stats: FramingStats::default(),
+        }
+    }
+
+    /// V.14 receiver: missing stop bits are reinserted rather than errors
+    pub fn v14(format: CharFormat) -> Self {
+        Self {
+            v14: true,
+            ..Self::new(format)
+        }
+    }
+
+    pub fn format(&self) -> CharFormat {
+        self.format
+    }
+
+    pub fn set_format(&mut self, format: CharFormat) {
+        self.format = format;
+        self.reset();
+    }
+
+    pub fn stats(&self) -> &FramingStats {
+        &self.stats
+    }
+
+    /// Zero bits that make a break
+    fn break_bits(&self) -> usize {
+        if self.v14 {
+            2 * self.format.frame_bits() + 3
+        } else {
+            // A whole character, up to the first stop bit, at space
+            self.format.frame_bits() - self.format.stop_bits as usize + 1
+        }
+    }
+
+    /// Feed one line bit; completed characters are appended to `out` (a
+    /// bit can finish two when it settles a held V.14 NUL)
+    pub fn push_bit(&mut self, bit: bool, out: &mut Vec<AsyncChar>) {
+        self.push_bit_with(bit, |c| out.push(c));
+    }
+
+    /// As `push_bit`, handing completed characters to `emit` (at most two)
+    /// rather than allocating
+    pub fn push_bit_with(&mut self, bit: bool, mut emit: impl FnMut(AsyncChar)) {
+        self.zero_run = if bit { 0 } else { self.zero_run + 1 };
+
+        if self.zero_run == self.break_bits() {
+            self.held_nul = false;
+            self.state = RxState::Resync;
+            self.stats.breaks += 1;
+            emit(AsyncChar::Break);
+            return;
+        }
+
+        // Any mark means the held all-zero character was a real NUL
+        if bit && self.held_nul {
+            self.held_nul = false;
+            self.stats.stop_bits_inserted += 1;
+            self.stats.characters += 1;
+            emit(AsyncChar::Data(0));
+        }
+
+        match self.state {
+            RxState::Arming(ones) => {
+                let ones = if bit { ones + 1 } else { 0 };
+                self.state = if ones >= self.format.frame_bits() {
+                    RxState::Idle
+                } else {
+                    RxState::Arming(ones)
+                };
+            }
+            RxState::Resync => {
+                if bit {
+                    self.state = RxState::Idle;
+                }
+            }
+            RxState::Idle => {
+                if !bit {
+                    self.shift = 0;
+                    self.state = RxState::Receiving(0);
+                }
+            }
+            RxState::Receiving(n) => {
+                let payload = self.format.data_bits as usize + self.format.parity_bits();
+                if n < payload {
+                    if bit {
+                        self.shift |= 1 << n;
+                    }
+                    self.state = RxState::Receiving(n + 1);
+                } else {
+                    // First stop bit
+                    self.complete(bit, &mut emit);
+                }
+            }
+        }
+    }
+
+    fn complete(&mut self, stop: bool, emit: &mut impl FnMut(AsyncChar)) {
+        let data = (self.shift as u8) & self.format.data_mask();
+        let parity_ok = match self.format.parity.bit(data) {
+            Some(expected) => ((self.shift >> self.format.data_bits) & 1 == 1) == expected,
+            None => true,
+        };
+
+        if stop {
+            self.state = RxState::Idle;
+        } else if self.v14 {
+            // Deleted stop bit: this bit is already the next start bit
+            self.state = RxState::Receiving(0);
+            let all_zero = self.shift == 0;
+            self.shift = 0;
+            if all_zero {
+                // Looks like the start of a break until a mark shows up
+                self.held_nul = true;
+                return;
+            }
+            self.stats.stop_bits_inserted += 1;
+        } else {
+            self.state = RxState::Resync;
+            self.stats.framing_errors += 1;
+            emit(AsyncChar::FramingError(data));
+            return;
+        }
+
+        self.stats.characters += 1;
+        if parity_ok {
+            emit(AsyncChar::Data(data));
+        } else {
+            self.stats.parity_errors += 1;
+            emit(AsyncChar::ParityError(data));
+        }
+    }
+
+    /// Feed line bits; returns the characters completed
+    pub fn deframe(&mut self, bits: &[bool]) -> Vec<AsyncChar> {
+        let mut out = Vec::new();
+        for &bit in bits {
+            self.push_bit(bit, &mut out);
+        }
+        out
+    }
+
+    /// Feed line bits; returns the received bytes (errored characters
+    /// included, as a UART would pass them on; breaks dropped)
+    pub fn deframe_bytes(&mut self, bits: &[bool]) -> Vec<u8> {
+        self.deframe(bits).into_iter().filter_map(AsyncChar::byte).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.state = RxState::Arming(0);
+        self.shift = 0;
+        self.zero_run = 0;
+        self.held_nul = false;
+        self.stats = FramingStats::default();
+    }
+}
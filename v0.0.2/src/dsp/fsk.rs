@@ -1,8 +1,12 @@
 // src/dsp/fsk.rs
 use super::*;
 use super::oscillator::NCO;
-use super::goertzel::DualToneDetector;
+use super::fsk_detector::{default_detector, FSKDetector, SoftDetector};
+use super::timing::BitSync;
 use super::filters::BiquadFilter;
+use super::agc::{AGCConfig, AGC};
+use super::framing::{AsyncDeframer, AsyncFramer, CharBits, CharFormat, FramingStats};
+use super::bits::BitWriter;
 
 /// Bell 103 / V.21 frequency specifications [web:46][web:49]
 #[derive(Debug, Clone, Copy)]
@@ -41,122 +45,317 @@ impl FSKMode {
 pub struct FSKModulator {
     mode: FSKMode,
     osc: NCO,
-    samples_per_bit: usize,
-    sample_counter: usize,
+    // Fractional at most rates; bit k ends on the sample nearest
+    // (k + 1) * samples_per_bit
+    samples_per_bit: f64,
+    sample_counter: u64,
+    bit_counter: u64,
+    framer: AsyncFramer,
+    // Rest of the character `modulate_bytes_into` is sending
+    queued: CharBits,
+    tx_level: TxLevel,
 }
 
 impl FSKModulator {
     pub fn new(mode: FSKMode, baud_rate: f32, sample_rate: f32) -> Self {
-        let samples_per_bit = (sample_rate / baud_rate) as usize;
+        let samples_per_bit = sample_rate as f64 / baud_rate as f64;
         let (space_freq, _) = mode.frequencies();
+        let tx_level = TxLevel::default();
         
         Self {
             mode,
-            osc: NCO::new(space_freq, sample_rate, 1.0),
+            osc: NCO::new(space_freq, sample_rate, tx_level.sine_amplitude()),
             samples_per_bit,
             sample_counter: 0,
+            bit_counter: 0,
+            framer: AsyncFramer::new(CharFormat::default()),
+            queued: CharBits::default(),
+            tx_level,
         }
     }
     
+    /// Line level of the tones (default 0 dBm0)
+    pub fn set_tx_level(&mut self, level: TxLevel) {
+        self.osc.set_amplitude(level.sine_amplitude());
+        self.tx_level = level;
+    }
+    
+    pub fn tx_level(&self) -> TxLevel {
+        self.tx_level
+    }
+    
     /// Modulate bits to audio samples
     pub fn modulate(&mut self, bits: &[bool]) -> Vec<f32> {
-        let mut samples = Vec::with_capacity(bits.len() * self.samples_per_bit);
-        
-        for &bit in bits {
+        let mut samples = vec![0.0; self.samples_for_bits(bits.len())];
+        self.modulate_into(&mut bits.iter().copied(), &mut samples);
+        samples
+    }
+    
+    /// Samples the next `bits` bits take, counting the rest of the bit in
+    /// progress
+    pub fn samples_for_bits(&self, bits: usize) -> usize {
+        let end = ((self.bit_counter + bits as u64) as f64 * self.samples_per_bit).round() as u64;
+        (end - self.sample_counter) as usize
+    }
+    
+    /// Modulate into `out` without allocating; returns the samples
+    /// written. Stops when `out` is full, possibly mid-bit (the next call
+    /// finishes that bit), or when `bits` runs out. `bits` can be a
+    /// `BitReader` over packed bytes.
+    pub fn modulate_into<I: Iterator<Item = bool>>(&mut self, bits: &mut I, out: &mut [f32]) -> usize {
+        let mut written = 0;
+        loop {
+            let bit_end = (self.bit_counter as f64 * self.samples_per_bit).round() as u64;
+            while self.sample_counter < bit_end && written < out.len() {
+                out[written] = self.osc.next();
+                self.sample_counter += 1;
+                written += 1;
+            }
+            if self.sample_counter < bit_end || written == out.len() {
+                return written;
+            }
+            
+            let Some(bit) = bits.next() else {
+                return written;
+            };
             let freq = if bit {
                 self.mode.mark_freq()
             } else {
                 self.mode.space_freq()
             };
-            
-            self.osc.set_frequency(freq, SAMPLE_RATE);
-            
-            for _ in 0..self.samples_per_bit {
-                samples.push(self.osc.next());
-            }
+            self.osc.set_frequency(freq);
+            self.bit_counter += 1;
         }
-        
-        samples
     }
     
-    /// Modulate bytes to audio
-    pub fn modulate_bytes(&mut self, data: &[u8]) -> Vec<f32> {
-        let mut bits = Vec::with_capacity(data.len() * 8);
-        for &byte in data {
-            for i in 0..8 {
-                bits.push((byte >> i) & 1 == 1);
+    /// Frame and modulate bytes into `out` without allocating; returns
+    /// (bytes taken, samples written). A byte counts as taken once its
+    /// character is started; the next call sends the rest.
+    pub fn modulate_bytes_into(&mut self, data: &[u8], out: &mut [f32]) -> (usize, usize) {
+        let mut taken = 0;
+        let mut written = 0;
+        loop {
+            let mut queued = core::mem::take(&mut self.queued);
+            written += self.modulate_into(&mut queued, &mut out[written..]);
+            self.queued = queued;
+            if self.queued.len() > 0 || written == out.len() || taken == data.len() {
+                return (taken, written);
             }
+            self.queued = self.framer.frame_char(data[taken]);
+            taken += 1;
         }
+    }
+    
+    /// Modulate bytes to audio as start/stop framed characters; the first
+    /// call starts with a short run of mark
+    pub fn modulate_bytes(&mut self, data: &[u8]) -> Vec<f32> {
+        let bits = self.framer.frame(data);
+        self.modulate(&bits)
+    }
+    
+    /// Mark idle, e.g. after the last character so it clears the far
+    /// end's receiver
+    pub fn idle(&mut self, bits: usize) -> Vec<f32> {
+        self.modulate(&vec![true; bits])
+    }
+    
+    /// Send a break (line held at space for over two characters)
+    pub fn modulate_break(&mut self) -> Vec<f32> {
+        let bits = self.framer.break_bits();
         self.modulate(&bits)
     }
+    
+    /// Character format used by `modulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.framer.set_format(format);
+    }
 }
 
+/// Samples the receive chain runs through each stage at a time
+const BLOCK_LEN: usize = 64;
+
 /// FSK demodulator - converts audio tones to bits
 pub struct FSKDemodulator {
     mode: FSKMode,
+    agc: AGC,
     bandpass: BiquadFilter,
-    detector: DualToneDetector,
-    samples_per_bit: usize,
-    sample_buffer: Vec<f32>,
-    bit_buffer: Vec<bool>,
+    detector: SoftDetector,
+    baud_rate: f32,
+    sample_rate: f32,
+    // Soft decisions of the latest block; those from `soft_pos` on have
+    // not reached the bit clock yet
+    soft: [f32; BLOCK_LEN],
+    soft_pos: usize,
+    soft_len: usize,
+    bit_sync: BitSync,
+    deframer: AsyncDeframer,
 }
 
 impl FSKDemodulator {
+    /// Demodulator with the default detector for `mode`
     pub fn new(mode: FSKMode, baud_rate: f32, sample_rate: f32) -> Self {
-        let samples_per_bit = (sample_rate / baud_rate) as usize;
+        Self::with_detector(mode, baud_rate, sample_rate, default_detector(mode))
+    }
+    
+    pub fn with_detector(mode: FSKMode, baud_rate: f32, sample_rate: f32, detector: FSKDetector) -> Self {
+        let samples_per_bit = sample_rate / baud_rate;
         let (space_freq, mark_freq) = mode.frequencies();
         let center_freq = mode.center_freq();
         let bandwidth = (mark_freq - space_freq).abs() * 2.0;
         
         Self {
             mode,
+            // FSK is constant envelope; level it to a full-scale tone
+            agc: AGC::new(AGCConfig::default(), sample_rate),
             bandpass: BiquadFilter::bandpass(center_freq, bandwidth, sample_rate),
-            detector: DualToneDetector::new(mark_freq, space_freq, sample_rate, samples_per_bit),
-            samples_per_bit,
-            sample_buffer: Vec::new(),
-            bit_buffer: Vec::new(),
+            detector: SoftDetector::new(detector, mode, baud_rate, sample_rate),
+            baud_rate,
+            sample_rate,
+            soft: [0.0; BLOCK_LEN],
+            soft_pos: 0,
+            soft_len: 0,
+            bit_sync: BitSync::new(samples_per_bit),
+            deframer: AsyncDeframer::new(CharFormat::default()),
         }
     }
     
+    /// Switch demodulation algorithm; the bit clock keeps its timing
+    pub fn set_detector(&mut self, detector: FSKDetector) {
+        self.detector = SoftDetector::new(detector, self.mode, self.baud_rate, self.sample_rate);
+    }
+    
+    pub fn detector(&self) -> FSKDetector {
+        self.detector.kind()
+    }
+    
     /// Process audio samples and extract bits
     pub fn demodulate(&mut self, samples: &[f32]) -> Vec<bool> {
-        self.bit_buffer.clear();
+        let mut consumed = 0;
+        let mut bits = Vec::new();
+        while let Some(bit) = self.next_bit(samples, &mut consumed) {
+            bits.push(bit);
+        }
+        bits
+    }
+    
+    /// Run up to a block of line samples through the AGC, band filter and
+    /// detector, a stage at a time; returns the samples taken
+    fn process_block(&mut self, samples: &[f32]) -> usize {
+        let n = samples.len().min(BLOCK_LEN);
+        let mut leveled = [0.0; BLOCK_LEN];
+        let mut filtered = [0.0; BLOCK_LEN];
+        self.agc.process_block(&samples[..n], &mut leveled[..n]);
+        self.bandpass.process_block(&leveled[..n], &mut filtered[..n]);
         
-        for &sample in samples {
-            // Bandpass filter
-            let filtered = self.bandpass.process(sample);
-            
-            // Feed to Goertzel detectors
-            self.detector.process_sample(filtered);
-            
-            // Check if we have a complete bit period
-            if self.detector.is_complete() {
-                let bit = self.detector.detect_bit();
-                self.bit_buffer.push(bit);
-                self.detector.reset();
+        // Soft decision, positive for mark
+        self.detector.process_block(&filtered[..n], &mut self.soft[..n]);
+        self.soft_pos = 0;
+        self.soft_len = n;
+        n
+    }
+    
+    /// Next bit the bit clock picks at a mid-bit instant, taking samples
+    /// from `samples[*consumed..]` as needed; None once they and the
+    /// pending soft decisions have run out
+    fn next_bit(&mut self, samples: &[f32], consumed: &mut usize) -> Option<bool> {
+        loop {
+            while self.soft_pos < self.soft_len {
+                let soft = self.soft[self.soft_pos];
+                self.soft_pos += 1;
+                if let Some(bit) = self.bit_sync.process(soft) {
+                    return Some(bit);
+                }
             }
+            if *consumed == samples.len() {
+                return None;
+            }
+            *consumed += self.process_block(&samples[*consumed..]);
         }
-        
-        self.bit_buffer.clone()
     }
     
-    /// Demodulate to bytes (LSB first)
+    /// Demodulate into packed bits without allocating; returns the samples
+    /// consumed, which is fewer than given only if `bits` fills up. Soft
+    /// decisions already made for consumed samples carry over to the next
+    /// call.
+    pub fn demodulate_into(&mut self, samples: &[f32], bits: &mut BitWriter) -> usize {
+        let mut consumed = 0;
+        while !bits.is_full() {
+            let Some(bit) = self.next_bit(samples, &mut consumed) else {
+                break;
+            };
+            bits.push(bit);
+        }
+        consumed
+    }
+    
+    /// Demodulate start/stop framed characters to bytes. Characters with
+    /// parity or framing errors are passed on as received; breaks are
+    /// counted in `framing_stats`.
     pub fn demodulate_bytes(&mut self, samples: &[f32]) -> Vec<u8> {
         let bits = self.demodulate(samples);
-        let mut bytes = Vec::new();
-        
-        for chunk in bits.chunks(8) {
-            if chunk.len() == 8 {
-                let mut byte = 0u8;
-                for (i, &bit) in chunk.iter().enumerate() {
-                    if bit {
-                        byte |= 1 << i;
-                    }
+        self.deframer.deframe_bytes(&bits)
+    }
+    
+    /// Deframe bits `demodulate` returned, as `demodulate_bytes` would
+    pub fn deframe_bytes(&mut self, bits: &[bool]) -> Vec<u8> {
+        self.deframer.deframe_bytes(bits)
+    }
+    
+    /// `demodulate_bytes` into `out` without allocating; returns (samples
+    /// consumed, bytes written). One bit can complete two characters, so
+    /// this stops while fewer than two bytes of `out` are free.
+    pub fn demodulate_bytes_into(&mut self, samples: &[f32], out: &mut [u8]) -> (usize, usize) {
+        let mut consumed = 0;
+        let mut written = 0;
+        while out.len() - written >= 2 {
+            let Some(bit) = self.next_bit(samples, &mut consumed) else {
+                break;
+            };
+            self.deframer.push_bit_with(bit, |c| {
+                if let Some(byte) = c.byte() {
+                    out[written] = byte;
+                    written += 1;
                 }
-                bytes.push(byte);
-            }
+            });
         }
-        
-        bytes
+        (consumed, written)
+    }
+    
+    /// Character format expected by `demodulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.deframer.set_format(format);
+    }
+    
+    /// Character, parity, framing error and break counts
+    pub fn framing_stats(&self) -> &FramingStats {
+        self.deframer.stats()
+    }
+    
+    /// Far end's bit-rate offset as tracked by the bit clock (fraction)
+    pub fn clock_offset(&self) -> f32 {
+        self.bit_sync.clock_offset()
+    }
+    
+    /// Bit clock is following transitions in the received signal
+    pub fn is_bit_locked(&self) -> bool {
+        self.bit_sync.is_locked()
+    }
+    
+    /// Receive level before the AGC
+    pub fn receive_level_dbm0(&self) -> f32 {
+        self.agc.level_dbm0()
+    }
+    
+    /// Switch between training and data; may freeze the AGC
+    pub fn set_data_mode(&mut self, data: bool) {
+        self.agc.set_data_mode(data);
+    }
+    
+    pub fn agc(&self) -> &AGC {
+        &self.agc
+    }
+    
+    pub fn agc_mut(&mut self) -> &mut AGC {
+        &mut self.agc
     }
 }
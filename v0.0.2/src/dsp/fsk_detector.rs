@@ -0,0 +1,475 @@
+// src/dsp/fsk_detector.rs
+//
+// Interchangeable FSK soft-decision back ends. Each turns band-filtered
+// line samples into a value that is positive for mark and negative for
+// space; `BitSync` finds the transitions in it and slices at mid-bit, so
+// only the sign and the shape around zero crossings matter, not the scale.
+use super::*;
+use super::fsk::FSKMode;
+use super::filters::{BiquadBank, BiquadFilter};
+use super::goertzel::GoertzelBank;
+use super::hilbert::AnalyticSignal;
+use num_complex::Complex32;
+use core::fmt;
+use core::str::FromStr;
+
+/// FSK demodulation algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FSKDetector {
+    /// Mark/space energy over the last bit (staggered Goertzel bins)
+    Goertzel,
+    /// Non-coherent quadrature correlators, Hann-windowed over one bit,
+    /// compared by envelope
+    Quadrature,
+    /// Phase advance of the analytic signal across a fixed delay line
+    Discriminator,
+    /// Second-order PLL tracking the tone; its frequency is the decision
+    PLL,
+}
+
+impl FSKDetector {
+    pub const ALL: [FSKDetector; 4] = [
+        FSKDetector::Goertzel,
+        FSKDetector::Quadrature,
+        FSKDetector::Discriminator,
+        FSKDetector::PLL,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FSKDetector::Goertzel => "goertzel",
+            FSKDetector::Quadrature => "quadrature",
+            FSKDetector::Discriminator => "discriminator",
+            FSKDetector::PLL => "pll",
+        }
+    }
+}
+
+impl fmt::Display for FSKDetector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for FSKDetector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FSKDetector::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown FSK detector {:?} (goertzel, quadrature, discriminator, pll)", s))
+    }
+}
+
+/// Default detector for `mode`, picked from the link-simulator BER
+/// comparison (`test_fsk_detector_ber_comparison` in tests/sim_tests.rs)
+pub fn default_detector(mode: FSKMode) -> FSKDetector {
+    match mode {
+        FSKMode::Bell103Originate
+        | FSKMode::Bell103Answer
+        | FSKMode::V21Originate
+        | FSKMode::V21Answer => FSKDetector::Quadrature,
+    }
+}
+
+/// +1 when mark is the upper tone; the frequency-based detectors measure
+/// "above centre" and need this to report mark as positive
+fn mark_sign(mode: FSKMode) -> f32 {
+    if mode.mark_freq() > mode.space_freq() { 1.0 } else { -1.0 }
+}
+
+/// Post-detection filter for the frequency-based detectors: passes the
+/// bit-rate square wave, removes the sum-frequency ripple
+fn post_filter(baud_rate: f32, sample_rate: f32) -> BiquadFilter {
+    BiquadFilter::lowpass(baud_rate, 0.707, sample_rate)
+}
+
+/// Staggered one-bit windows per tone: a new mark/space comparison every
+/// eighth of a bit
+const TONE_WINDOW_PHASES: usize = 8;
+
+/// Energy comparison of the mark and space bins over one bit.
+///
+/// A Goertzel bank runs `TONE_WINDOW_PHASES` windows per tone, each one bit
+/// long and restarted every bit, staggered so a pair completes every hop
+/// of an eighth of a bit. The soft decision ramps from one completed
+/// pair's difference to the next across the following hop; a held value
+/// would leave the bit clock only hop-sized steps to find crossings in.
+pub struct ToneEnergyDetector {
+    // Lanes: mark windows, then space windows, in phase order
+    bins: GoertzelBank,
+    // Position in the bit at which each phase's windows complete
+    restarts: Vec<usize>,
+    samples_per_bit: usize,
+    pos: usize,
+
+    // Ramp between the last two comparisons, over the current hop
+    from: f32,
+    to: f32,
+    hop_len: usize,
+    hop_pos: usize,
+}
+
+impl ToneEnergyDetector {
+    pub fn new(mode: FSKMode, samples_per_bit: usize, sample_rate: f32) -> Self {
+        // One-bit windows: the difference changes sign half a bit after
+        // each transition, which the bit clock locks to
+        let samples_per_bit = samples_per_bit.max(1);
+        let phases = TONE_WINDOW_PHASES.min(samples_per_bit);
+        let mut freqs = vec![mode.mark_freq(); phases];
+        freqs.extend(core::iter::repeat_n(mode.space_freq(), phases));
+
+        let mut detector = Self {
+            bins: GoertzelBank::at_frequencies(&freqs, sample_rate, samples_per_bit),
+            restarts: (0..phases).map(|p| p * samples_per_bit / phases).collect(),
+            samples_per_bit,
+            pos: 0,
+            from: 0.0,
+            to: 0.0,
+            hop_len: 1,
+            hop_pos: 0,
+        };
+        detector.hop_len = detector.to_boundary();
+        detector
+    }
+
+    /// Samples to the next window boundary, counting the one at `pos`
+    fn to_boundary(&self) -> usize {
+        self.restarts.iter()
+            .map(|&r| (r + self.samples_per_bit - self.pos - 1) % self.samples_per_bit + 1)
+            .min()
+            .unwrap_or(self.samples_per_bit)
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let mut soft = 0.0;
+        self.process_block(&[sample], core::slice::from_mut(&mut soft));
+        soft
+    }
+
+    /// Soft decisions for a block; the bank runs from one window boundary
+    /// to the next
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        let phases = self.restarts.len();
+        let mut start = 0;
+        while start < input.len() {
+            let len = self.to_boundary().min(input.len() - start);
+            let end = start + len;
+
+            self.bins.process_block(&input[start..end]);
+            for o in &mut output[start..end] {
+                self.hop_pos += 1;
+                *o = self.from + (self.to - self.from) * self.hop_pos as f32 / self.hop_len as f32;
+            }
+            self.pos = (self.pos + len) % self.samples_per_bit;
+
+            // Windows that are a whole bit long now: compare, restart
+            if let Some(phase) = self.restarts.iter().position(|&r| r == self.pos) {
+                self.from = self.to;
+                self.to = self.bins.magnitude_squared(phase) - self.bins.magnitude_squared(phases + phase);
+                self.bins.reset_bin(phase);
+                self.bins.reset_bin(phases + phase);
+                self.hop_len = self.to_boundary();
+                self.hop_pos = 0;
+            }
+            start = end;
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.bins.reset();
+        self.pos = 0;
+        self.from = 0.0;
+        self.to = 0.0;
+        self.hop_len = self.to_boundary();
+        self.hop_pos = 0;
+    }
+}
+
+/// One tone's I/Q branch: mix with a local oscillator, integrate over
+/// the last bit
+struct QuadratureBranch {
+    omega: f32,
+    phase: f32,
+    products: Vec<Complex32>,
+    sum: Complex32,
+}
+
+impl QuadratureBranch {
+    fn new(freq: f32, len: usize, sample_rate: f32) -> Self {
+        Self {
+            omega: freq_to_omega(freq, sample_rate),
+            phase: 0.0,
+            products: vec![Complex32::new(0.0, 0.0); len],
+            sum: Complex32::new(0.0, 0.0),
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, sample: f32, pos: usize) -> f32 {
+        let product = Complex32::from_polar(sample, -self.phase);
+        self.phase += self.omega;
+        if self.phase > PI {
+            self.phase -= 2.0 * PI;
+        }
+
+        // Integrate-and-dump as a running sum over one bit
+        self.sum += product - core::mem::replace(&mut self.products[pos], product);
+        self.sum.norm()
+    }
+
+    fn reset(&mut self) {
+        self.phase = 0.0;
+        self.products.iter_mut().for_each(|p| *p = Complex32::new(0.0, 0.0));
+        self.sum = Complex32::new(0.0, 0.0);
+    }
+}
+
+/// Non-coherent quadrature correlator pair: each tone's I/Q correlation
+/// over one bit, compared by envelope. The LO phase against the tone is
+/// arbitrary, which the envelope doesn't see.
+pub struct QuadratureCorrelator {
+    mark: QuadratureBranch,
+    space: QuadratureBranch,
+    pos: usize,
+}
+
+impl QuadratureCorrelator {
+    pub fn new(mode: FSKMode, samples_per_bit: usize, sample_rate: f32) -> Self {
+        let len = samples_per_bit.max(1);
+        Self {
+            mark: QuadratureBranch::new(mode.mark_freq(), len, sample_rate),
+            space: QuadratureBranch::new(mode.space_freq(), len, sample_rate),
+            pos: 0,
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let mark = self.mark.process(sample, self.pos);
+        let space = self.space.process(sample, self.pos);
+        self.pos = (self.pos + 1) % self.mark.products.len();
+        mark - space
+    }
+
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.process(*i);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.mark.reset();
+        self.space.reset();
+        self.pos = 0;
+    }
+}
+
+/// Samples the delay-line discriminator smooths per bank call
+const DISCRIMINATOR_BLOCK: usize = 64;
+
+/// Delay-line frequency discriminator. The phase the analytic signal
+/// advances over `delay` samples is proportional to its frequency; the
+/// delay is chosen so mark and space land a quarter turn apart.
+pub struct DelayLineDiscriminator {
+    analytic: AnalyticSignal,
+    delay_line: Vec<Complex32>,
+    pos: usize,
+    // Post filters on the product's real and imaginary parts, lanes 0 and 1
+    filters: BiquadBank,
+    center_advance: f32,
+    sign: f32,
+}
+
+impl DelayLineDiscriminator {
+    pub fn new(mode: FSKMode, baud_rate: f32, sample_rate: f32) -> Self {
+        let shift = (mode.mark_freq() - mode.space_freq()).abs();
+        let delay = ((sample_rate / (4.0 * shift)).round() as usize).max(1);
+        let filter = post_filter(baud_rate, sample_rate);
+
+        Self {
+            analytic: AnalyticSignal::default(),
+            delay_line: vec![Complex32::new(0.0, 0.0); delay],
+            pos: 0,
+            filters: BiquadBank::new(&[filter.clone(), filter]),
+            center_advance: freq_to_omega(mode.center_freq(), sample_rate) * delay as f32,
+            sign: mark_sign(mode),
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let mut soft = 0.0;
+        self.process_block(&[sample], core::slice::from_mut(&mut soft));
+        soft
+    }
+
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        let rotation = Complex32::from_polar(1.0, -self.center_advance);
+        for (input, output) in input.chunks(DISCRIMINATOR_BLOCK).zip(output.chunks_mut(DISCRIMINATOR_BLOCK)) {
+            // Filter the product, not its angle, so noise averages out
+            // before the nonlinearity
+            let mut products = [0.0; 2 * DISCRIMINATOR_BLOCK];
+            for (&sample, frame) in input.iter().zip(products.chunks_exact_mut(2)) {
+                let z = self.analytic.process(sample);
+                let delayed = core::mem::replace(&mut self.delay_line[self.pos], z);
+                self.pos = (self.pos + 1) % self.delay_line.len();
+                let product = z * delayed.conj();
+                frame[0] = product.re;
+                frame[1] = product.im;
+            }
+            let frames = 2 * input.len();
+            let mut smoothed = [0.0; 2 * DISCRIMINATOR_BLOCK];
+            self.filters.process_frames(&products[..frames], &mut smoothed[..frames]);
+
+            // Rotate the centre frequency to zero phase before taking the
+            // angle
+            for (o, frame) in output.iter_mut().zip(smoothed.chunks_exact(2)) {
+                *o = self.sign * (Complex32::new(frame[0], frame[1]) * rotation).arg();
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.analytic.reset();
+        self.delay_line.iter_mut().for_each(|z| *z = Complex32::new(0.0, 0.0));
+        self.pos = 0;
+        self.filters.reset();
+    }
+}
+
+/// Loop noise bandwidth of the tone PLL as a multiple of the bit rate:
+/// wide enough to pull through a shift within a fraction of a bit
+const PLL_BANDWIDTH: f32 = 1.5;
+
+/// Phase-locked loop on the analytic signal. It follows the tone through
+/// each shift; the loop's instantaneous frequency, filtered, is the soft
+/// decision.
+pub struct TonePLL {
+    analytic: AnalyticSignal,
+    phase: f32,
+    center: f32,
+    integrator: f32,
+    proportional_gain: f32,
+    integral_gain: f32,
+    filter: BiquadFilter,
+    sign: f32,
+}
+
+impl TonePLL {
+    pub fn new(mode: FSKMode, baud_rate: f32, sample_rate: f32) -> Self {
+        // Second-order loop, critically damped, per-sample gains
+        let damping = 0.707;
+        let theta = (PLL_BANDWIDTH * baud_rate / sample_rate) / (damping + 0.25 / damping);
+        let denom = 1.0 + 2.0 * damping * theta + theta * theta;
+
+        Self {
+            analytic: AnalyticSignal::default(),
+            phase: 0.0,
+            center: freq_to_omega(mode.center_freq(), sample_rate),
+            integrator: 0.0,
+            proportional_gain: 4.0 * damping * theta / denom,
+            integral_gain: 4.0 * theta * theta / denom,
+            filter: post_filter(baud_rate, sample_rate),
+            sign: mark_sign(mode),
+        }
+    }
+
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let z = self.analytic.process(sample);
+        let error = (z * Complex32::from_polar(1.0, -self.phase)).arg();
+
+        self.integrator += self.integral_gain * error;
+        let offset = self.proportional_gain * error + self.integrator;
+        self.phase += self.center + offset;
+        if self.phase > PI {
+            self.phase -= 2.0 * PI;
+        } else if self.phase < -PI {
+            self.phase += 2.0 * PI;
+        }
+
+        self.sign * self.filter.process(offset)
+    }
+
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        for (i, o) in input.iter().zip(output.iter_mut()) {
+            *o = self.process(*i);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.analytic.reset();
+        self.phase = 0.0;
+        self.integrator = 0.0;
+        self.filter.reset();
+    }
+}
+
+/// One of the back ends, selected by `FSKDetector`
+pub enum SoftDetector {
+    Goertzel(ToneEnergyDetector),
+    Quadrature(QuadratureCorrelator),
+    Discriminator(DelayLineDiscriminator),
+    PLL(TonePLL),
+}
+
+impl SoftDetector {
+    pub fn new(detector: FSKDetector, mode: FSKMode, baud_rate: f32, sample_rate: f32) -> Self {
+        // One-bit windows, to the nearest sample
+        let samples_per_bit = (sample_rate / baud_rate).round() as usize;
+        match detector {
+            FSKDetector::Goertzel => {
+                SoftDetector::Goertzel(ToneEnergyDetector::new(mode, samples_per_bit, sample_rate))
+            }
+            FSKDetector::Quadrature => {
+                SoftDetector::Quadrature(QuadratureCorrelator::new(mode, samples_per_bit, sample_rate))
+            }
+            FSKDetector::Discriminator => {
+                SoftDetector::Discriminator(DelayLineDiscriminator::new(mode, baud_rate, sample_rate))
+            }
+            FSKDetector::PLL => SoftDetector::PLL(TonePLL::new(mode, baud_rate, sample_rate)),
+        }
+    }
+
+    pub fn kind(&self) -> FSKDetector {
+        match self {
+            SoftDetector::Goertzel(_) => FSKDetector::Goertzel,
+            SoftDetector::Quadrature(_) => FSKDetector::Quadrature,
+            SoftDetector::Discriminator(_) => FSKDetector::Discriminator,
+            SoftDetector::PLL(_) => FSKDetector::PLL,
+        }
+    }
+
+    /// Soft decision for one sample: positive = mark
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        match self {
+            SoftDetector::Goertzel(d) => d.process(sample),
+            SoftDetector::Quadrature(d) => d.process(sample),
+            SoftDetector::Discriminator(d) => d.process(sample),
+            SoftDetector::PLL(d) => d.process(sample),
+        }
+    }
+
+    /// Soft decisions for a block of samples
+    pub fn process_block(&mut self, input: &[f32], output: &mut [f32]) {
+        match self {
+            SoftDetector::Goertzel(d) => d.process_block(input, output),
+            SoftDetector::Quadrature(d) => d.process_block(input, output),
+            SoftDetector::Discriminator(d) => d.process_block(input, output),
+            SoftDetector::PLL(d) => d.process_block(input, output),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            SoftDetector::Goertzel(d) => d.reset(),
+            SoftDetector::Quadrature(d) => d.reset(),
+            SoftDetector::Discriminator(d) => d.reset(),
+            SoftDetector::PLL(d) => d.reset(),
+        }
+    }
+}
@@ -1,5 +1,15 @@
 // src/dsp/goertzel.rs
 use super::*;
+use super::oscillator::ComplexNCO;
+use super::simd::{GoertzelLanes, SimdLevel};
+use num_complex::Complex32;
+
+/// 2cos(omega) for the DFT bin nearest `target_freq`
+fn coefficient(target_freq: f32, sample_rate: f32, block_size: usize) -> f32 {
+    let k = (0.5 + (block_size as f32 * target_freq / sample_rate)) as usize;
+    let omega = (2.0 * PI * k as f32) / block_size as f32;
+    2.0 * omega.cos()
+}
 
 /// Efficient single-frequency DFT using Goertzel algorithm
 pub struct GoertzelDetector {
@@ -13,12 +23,8 @@ pub struct GoertzelDetector {
 impl GoertzelDetector {
     /// Create detector for specific frequency [web:54][web:55]
     pub fn new(target_freq: f32, sample_rate: f32, block_size: usize) -> Self {
-        let k = (0.5 + (block_size as f32 * target_freq / sample_rate)) as usize;
-        let omega = (2.0 * PI * k as f32) / block_size as f32;
-        let coefficient = 2.0 * omega.cos();
-        
         Self {
-            coefficient,
+            coefficient: coefficient(target_freq, sample_rate, block_size),
             s1: 0.0,
             s2: 0.0,
             n: 0,
@@ -58,6 +64,153 @@ impl GoertzelDetector {
     }
 }
 
+/// Goertzel detectors on the same input (a DTMF receiver's eight tones,
+/// a bank of FSK channels), stepped together and vectorized across bins.
+/// Each bin matches a `GoertzelDetector` exactly.
+pub struct GoertzelBank {
+    lanes: GoertzelLanes,
+    n: usize,
+    block_size: usize,
+    simd: SimdLevel,
+}
+
+impl GoertzelBank {
+    /// Bins at the DFT bins nearest `target_freqs`, like `GoertzelDetector`
+    pub fn new(target_freqs: &[f32], sample_rate: f32, block_size: usize) -> Self {
+        let coefficients = target_freqs.iter().map(|&f| coefficient(f, sample_rate, block_size)).collect();
+        Self::with_coefficients(coefficients, block_size)
+    }
+    
+    /// Bins at exactly `target_freqs`, for blocks too short to put a DFT
+    /// bin on each tone (e.g. one FSK bit)
+    pub fn at_frequencies(target_freqs: &[f32], sample_rate: f32, block_size: usize) -> Self {
+        let coefficients = target_freqs.iter().map(|&f| 2.0 * freq_to_omega(f, sample_rate).cos()).collect();
+        Self::with_coefficients(coefficients, block_size)
+    }
+    
+    fn with_coefficients(coefficient: Vec<f32>, block_size: usize) -> Self {
+        let len = coefficient.len();
+        Self {
+            lanes: GoertzelLanes {
+                coefficient,
+                s1: vec![0.0; len],
+                s2: vec![0.0; len],
+            },
+            n: 0,
+            block_size,
+            simd: SimdLevel::detect(),
+        }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.lanes.coefficient.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.lanes.coefficient.is_empty()
+    }
+    
+    /// Force an instruction set (e.g. scalar, to compare against)
+    pub fn set_simd_level(&mut self, level: SimdLevel) -> Result<(), String> {
+        if !level.is_supported() {
+            return Err(format!("{} is not supported on this CPU", level));
+        }
+        self.simd = level;
+        Ok(())
+    }
+    
+    pub fn simd_level(&self) -> SimdLevel {
+        self.simd
+    }
+    
+    /// Feed samples to every bin
+    pub fn process_block(&mut self, samples: &[f32]) {
+        self.simd.goertzel_lanes(&mut self.lanes, samples);
+        self.n += samples.len();
+    }
+    
+    /// Energy at bin `index`
+    pub fn magnitude_squared(&self, index: usize) -> f32 {
+        let (s1, s2) = (self.lanes.s1[index], self.lanes.s2[index]);
+        s1 * s1 + s2 * s2 - self.lanes.coefficient[index] * s1 * s2
+    }
+    
+    pub fn magnitude(&self, index: usize) -> f32 {
+        self.magnitude_squared(index).sqrt()
+    }
+    
+    pub fn reset(&mut self) {
+        self.lanes.s1.iter_mut().for_each(|s| *s = 0.0);
+        self.lanes.s2.iter_mut().for_each(|s| *s = 0.0);
+        self.n = 0;
+    }
+    
+    /// Restart bin `index` alone, e.g. to stagger the blocks of bins that
+    /// share an input
+    pub fn reset_bin(&mut self, index: usize) {
+        self.lanes.s1[index] = 0.0;
+        self.lanes.s2[index] = 0.0;
+    }
+    
+    pub fn is_complete(&self) -> bool {
+        self.n >= self.block_size
+    }
+}
+
+/// Single-frequency DFT over a sliding window, updated every sample.
+///
+/// Unlike the block Goertzel it needs no block alignment and isn't limited
+/// to bin frequencies: each input is mixed down by `target_freq` and the
+/// last `window_len` products are summed.
+pub struct SlidingDFT {
+    nco: ComplexNCO,
+    window: Vec<Complex32>,
+    pos: usize,
+    sum: Complex32,
+}
+
+impl SlidingDFT {
+    pub fn new(target_freq: f32, sample_rate: f32, window_len: usize) -> Self {
+        Self {
+            nco: ComplexNCO::new(target_freq, sample_rate),
+            window: vec![Complex32::new(0.0, 0.0); window_len.max(1)],
+            pos: 0,
+            sum: Complex32::new(0.0, 0.0),
+        }
+    }
+    
+    /// Slide the window on by one sample; returns the new DFT value
+    #[inline]
+    pub fn process_sample(&mut self, sample: f32) -> Complex32 {
+        let product = self.nco.mix_down(sample);
+        self.sum += product - self.window[self.pos];
+        self.window[self.pos] = product;
+        self.pos += 1;
+        
+        // Re-sum once per window so rounding in the running sum can't build up
+        if self.pos == self.window.len() {
+            self.pos = 0;
+            self.sum = self.window.iter().sum();
+        }
+        self.sum
+    }
+    
+    pub fn magnitude_squared(&self) -> f32 {
+        self.sum.norm_sqr()
+    }
+    
+    pub fn window_len(&self) -> usize {
+        self.window.len()
+    }
+    
+    pub fn reset(&mut self) {
+        self.nco.reset();
+        self.window.iter_mut().for_each(|p| *p = Complex32::new(0.0, 0.0));
+        self.pos = 0;
+        self.sum = Complex32::new(0.0, 0.0);
+    }
+}
+
 /// Dual-tone detector for FSK demodulation
 pub struct DualToneDetector {
     detector_mark: GoertzelDetector,
@@ -0,0 +1,188 @@
+// src/dsp/interpolator.rs
+//
+// Fractional-delay interpolation shared by timing recovery, clock-drift
+// compensation and sample-rate conversion. Two variants:
+//   - cubic Lagrange in Farrow form: 4 taps, no tables, fine for well
+//     oversampled baseband (QAM at 13+ samples per symbol)
+//   - polyphase windowed-sinc bank: longer, near-transparent up to ~0.4 fs,
+//     for passband audio
+use super::fir::sinc;
+use super::*;
+use core::ops::{Add, Mul};
+
+/// Anything the interpolators can work on (real audio or complex baseband)
+pub trait Sample: Copy + Default + Add<Output = Self> + Mul<f32, Output = Self> {}
+
+impl<T: Copy + Default + Add<Output = T> + Mul<f32, Output = T>> Sample for T {}
+
+/// Windowed-sinc filter bank, one row per fractional phase (plus the end point)
+#[derive(Debug, Clone)]
+pub struct PolyphaseBank {
+    phases: usize,
+    taps: usize,
+    coeffs: Vec<f32>,
+}
+
+impl PolyphaseBank {
+    /// `taps` per phase (rounded up to even), `phases` steps per sample
+    pub fn new(phases: usize, taps: usize) -> Self {
+        let phases = phases.max(1);
+        let taps = (taps.max(2) + 1) & !1;
+        let half = (taps / 2) as f32;
+
+        let mut coeffs = Vec::with_capacity((phases + 1) * taps);
+        for p in 0..=phases {
+            let frac = p as f32 / phases as f32;
+            let start = coeffs.len();
+            for k in 0..taps {
+                // Distance from tap k to the interpolation point
+                let d = k as f32 - (half - 1.0) - frac;
+                let window = 0.5 + 0.5 * (PI * d / half).cos();
+                coeffs.push(sinc(d) * window);
+            }
+            // Unity DC gain for every phase
+            let sum: f32 = coeffs[start..].iter().sum();
+            coeffs[start..].iter_mut().for_each(|c| *c /= sum);
+        }
+
+        Self { phases, taps, coeffs }
+    }
+
+    fn row(&self, phase: usize) -> &[f32] {
+        &self.coeffs[phase * self.taps..(phase + 1) * self.taps]
+    }
+}
+
+/// Fractional-delay interpolator.
+///
+/// Every variant reads a window of `window_len()` consecutive samples,
+/// oldest first, and returns the signal `mu` (0.0..=1.0) of the way from
+/// `window[len/2 - 1]` to `window[len/2]`.
+#[derive(Debug, Clone)]
+pub enum Interpolator {
+    CubicFarrow,
+    Polyphase(PolyphaseBank),
+}
+
+impl Interpolator {
+    pub fn cubic() -> Self {
+        Interpolator::CubicFarrow
+    }
+
+    /// Polyphase bank; 32 phases x 16 taps is transparent for 8 kHz audio
+    pub fn polyphase(phases: usize, taps: usize) -> Self {
+        Interpolator::Polyphase(PolyphaseBank::new(phases, taps))
+    }
+
+    /// Window length in samples
+    pub fn window_len(&self) -> usize {
+        match self {
+            Interpolator::CubicFarrow => 4,
+            Interpolator::Polyphase(bank) => bank.taps,
+        }
+    }
+
+    /// Samples between the newest input and the end of the interpolation interval
+    pub fn delay(&self) -> usize {
+        self.window_len() / 2 - 1
+    }
+
+    /// Interpolate at fraction `mu` inside the window's centre interval
+    #[inline]
+    pub fn interpolate<T: Sample>(&self, window: &[T], mu: f32) -> T {
+        let mu = mu.clamp(0.0, 1.0);
+        match self {
+            Interpolator::CubicFarrow => {
+                let (x0, x1, x2, x3) = (window[0], window[1], window[2], window[3]);
+
+                // Lagrange cubic through t = -1, 0, 1, 2, arranged as a
+                // polynomial in mu with FIR coefficients (Farrow structure)
+                let c0 = x1;
+                let c1 = x0 * (-1.0 / 3.0) + x1 * -0.5 + x2 + x3 * (-1.0 / 6.0);
+                let c2 = x0 * 0.5 + x1 * -1.0 + x2 * 0.5;
+                let c3 = x0 * (-1.0 / 6.0) + x1 * 0.5 + x2 * -0.5 + x3 * (1.0 / 6.0);
+
+                ((c3 * mu + c2) * mu + c1) * mu + c0
+            }
+            Interpolator::Polyphase(bank) => {
+                // Blend the two nearest phases
+                let pos = mu * bank.phases as f32;
+                let p = (pos as usize).min(bank.phases - 1);
+                let blend = pos - p as f32;
+
+                let mut acc = T::default();
+                for ((&x, &a), &b) in window.iter().zip(bank.row(p)).zip(bank.row(p + 1)) {
+                    acc = acc + x * (a + (b - a) * blend);
+                }
+                acc
+            }
+        }
+    }
+}
+
+/// Streaming arbitrary-ratio resampler.
+///
+/// `ratio` is input samples per output sample: 8000 -> 9600 Hz is 0.8333,
+/// and a far-end clock running 100 ppm fast is 1.0001.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    interpolator: Interpolator,
+    ratio: f64,
+    history: Vec<f32>,
+    position: f64,
+}
+
+impl Resampler {
+    pub fn new(ratio: f64, interpolator: Interpolator) -> Self {
+        let len = interpolator.window_len();
+        Self {
+            interpolator,
+            ratio: ratio.max(1e-6),
+            history: vec![0.0; len],
+            position: 0.0,
+        }
+    }
+
+    /// Resampler between two sample rates
+    pub fn between(input_rate: f32, output_rate: f32, interpolator: Interpolator) -> Self {
+        Self::new(input_rate as f64 / output_rate as f64, interpolator)
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Change the ratio on the fly (e.g. to follow a clock estimate)
+    pub fn set_ratio(&mut self, ratio: f64) {
+        self.ratio = ratio.max(1e-6);
+    }
+
+    /// Latency in input samples (outputs are timed from the interval start)
+    pub fn delay(&self) -> usize {
+        self.interpolator.delay() + 1
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::with_capacity((input.len() as f64 / self.ratio) as usize + 1);
+
+        for &sample in input {
+            self.history.rotate_left(1);
+            if let Some(newest) = self.history.last_mut() {
+                *newest = sample;
+            }
+
+            while self.position < 1.0 {
+                output.push(self.interpolator.interpolate(&self.history, self.position as f32));
+                self.position += self.ratio;
+            }
+            self.position -= 1.0;
+        }
+
+        output
+    }
+
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|s| *s = 0.0);
+        self.position = 0.0;
+    }
+}
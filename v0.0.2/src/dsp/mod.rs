@@ -1,17 +1,36 @@
 // src/dsp/mod.rs
 pub mod oscillator;
 pub mod filters;
+pub mod fir;
+pub mod hilbert;
 pub mod goertzel;
+pub mod bits;
+pub mod framing;
 pub mod fsk;
+pub mod fsk_detector;
 pub mod timing;
+pub mod interpolator;
 pub mod carrier;
 pub mod qam;
 pub mod qam_modem;
 pub mod scrambler;
 pub mod equalizer;
 pub mod costas;
+pub mod tap;
+pub mod agc;
+pub mod simd;
+#[cfg(feature = "fixed-point")]
+pub mod fixed;
 
-use std::f32::consts::PI;
+use core::f32::consts::PI;
+
+// What std's prelude would bring in, for no_std builds; submodules pick
+// these up through `use super::*`
+#[allow(unused_imports)]
+use alloc::{boxed::Box, format, string::{String, ToString}, vec, vec::Vec};
+#[cfg(not(feature = "std"))]
+#[allow(unused_imports)]
+use num_traits::Float;
 
 /// Standard telephone line sample rate
 pub const SAMPLE_RATE: f32 = 8000.0;
@@ -20,3 +39,58 @@ pub const SAMPLE_RATE: f32 = 8000.0;
 pub fn freq_to_omega(freq_hz: f32, sample_rate: f32) -> f32 {
     2.0 * PI * freq_hz / sample_rate
 }
+
+/// Level of a full-scale sine (amplitude 1.0), as for the u-law digital milliwatt
+pub const FULL_SCALE_DBM0: f32 = 3.17;
+
+/// Mean-square signal power to dBm0
+#[inline]
+pub fn power_to_dbm0(mean_square: f32) -> f32 {
+    10.0 * (mean_square.max(1e-12) / 0.5).log10() + FULL_SCALE_DBM0
+}
+
+/// dBm0 to mean-square signal power
+#[inline]
+pub fn dbm0_to_power(dbm0: f32) -> f32 {
+    0.5 * 10f32.powf((dbm0 - FULL_SCALE_DBM0) / 10.0)
+}
+
+/// Default transmit level of every signal source: the 0 dBm0 digital
+/// reference
+pub const DEFAULT_TX_LEVEL_DBM0: f32 = 0.0;
+
+/// Calibrated transmit level: signal power in dBm0, against the level a
+/// full-scale sine stands for on this audio path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TxLevel {
+    pub dbm0: f32,
+    /// Level of a full-scale sine (amplitude 1.0)
+    pub full_scale_dbm0: f32,
+}
+
+impl TxLevel {
+    pub fn new(dbm0: f32) -> Self {
+        Self::with_reference(dbm0, FULL_SCALE_DBM0)
+    }
+
+    pub fn with_reference(dbm0: f32, full_scale_dbm0: f32) -> Self {
+        Self { dbm0, full_scale_dbm0 }
+    }
+
+    /// Gain that brings a source with mean-square power `unit_power` at
+    /// gain 1.0 to this level
+    pub fn gain(&self, unit_power: f32) -> f32 {
+        (0.5 * 10f32.powf((self.dbm0 - self.full_scale_dbm0) / 10.0) / unit_power).sqrt()
+    }
+
+    /// Peak amplitude of a single tone at this level
+    pub fn sine_amplitude(&self) -> f32 {
+        self.gain(0.5)
+    }
+}
+
+impl Default for TxLevel {
+    fn default() -> Self {
+        Self::new(DEFAULT_TX_LEVEL_DBM0)
+    }
+}
@@ -1,53 +1,281 @@
 // src/dsp/oscillator.rs
 use super::*;
+use num_complex::Complex32;
 
-/// Phase-accumulating sine wave generator
+/// Quarter-wave sine table resolution (1024 steps per quarter turn)
+const QUARTER_BITS: u32 = 10;
+const QUARTER_LEN: usize = 1 << QUARTER_BITS;
+
+/// Phase bits below the table index
+const FRACTION_BITS: u32 = 30 - QUARTER_BITS;
+const FRACTION_SCALE: f32 = 1.0 / (1u32 << FRACTION_BITS) as f32;
+
+/// One turn of phase word
+const TURN: f64 = 4294967296.0;
+
+/// sin over the first quarter turn, plus the endpoint
+static QUARTER_SINE: [f32; QUARTER_LEN + 1] = quarter_sine();
+
+/// Taylor series to x^17 in f64, well under f32 rounding on [0, pi/2]
+const fn quarter_sine() -> [f32; QUARTER_LEN + 1] {
+    let mut table = [0.0; QUARTER_LEN + 1];
+    let mut i = 0;
+    while i <= QUARTER_LEN {
+        let x = i as f64 * core::f64::consts::FRAC_PI_2 / QUARTER_LEN as f64;
+        let mut term = x;
+        let mut sum = x;
+        let mut n = 1;
+        while n < 9 {
+            term = -term * x * x / ((2 * n) * (2 * n + 1)) as f64;
+            sum += term;
+            n += 1;
+        }
+        table[i] = sum as f32;
+        i += 1;
+    }
+    table
+}
+
+/// How `NCO` reads between sine table entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SineInterpolation {
+    /// Nearest entry: cheapest, spurs around -60 dBc
+    Nearest,
+    /// Linear between neighbouring entries: within 3e-7 of `sin()`
+    #[default]
+    Linear,
+}
+
+/// sin of a phase word (2^32 = one turn) from the quarter-wave table
+#[inline]
+pub fn sin_lookup(phase: u32, interpolation: SineInterpolation) -> f32 {
+    let quadrant = phase >> 30;
+    let offset = phase & ((1 << 30) - 1);
+    let offset = if quadrant & 1 == 1 { (1 << 30) - offset } else { offset };
+
+    let value = match interpolation {
+        SineInterpolation::Nearest => {
+            QUARTER_SINE[((offset + (1 << (FRACTION_BITS - 1))) >> FRACTION_BITS) as usize]
+        }
+        SineInterpolation::Linear => {
+            let index = (offset >> FRACTION_BITS) as usize;
+            let fraction = (offset & ((1 << FRACTION_BITS) - 1)) as f32 * FRACTION_SCALE;
+            let low = QUARTER_SINE[index];
+            let high = QUARTER_SINE[(index + 1).min(QUARTER_LEN)];
+            low + (high - low) * fraction
+        }
+    };
+
+    if quadrant >= 2 { -value } else { value }
+}
+
+/// Phase word step per sample for `frequency`; negative frequencies wrap
+pub fn phase_increment(frequency: f32, sample_rate: f32) -> u32 {
+    (frequency as f64 / sample_rate as f64 * TURN).round() as i64 as u32
+}
+
+/// Phase word for an angle in radians, wrapped to one turn
+fn radians_to_word(phase: f32) -> u32 {
+    let turns = phase as f64 / core::f64::consts::TAU;
+    (turns * TURN).round() as i64 as u32
+}
+
+/// Phase-accumulating sine wave generator.
+///
+/// The phase is a 32-bit word (2^32 = one turn) that wraps for free, so
+/// it never drifts however long the call, and a frequency change only
+/// swaps the step: the phase carries on exactly (continuous-phase FSK).
 pub struct NCO {
-    phase: f32,
-    phase_increment: f32,
+    phase: u32,
+    phase_increment: u32,
     amplitude: f32,
+    sample_rate: f32,
+    interpolation: SineInterpolation,
 }
 
 impl NCO {
     pub fn new(frequency: f32, sample_rate: f32, amplitude: f32) -> Self {
         Self {
-            phase: 0.0,
-            phase_increment: freq_to_omega(frequency, sample_rate),
+            phase: 0,
+            phase_increment: phase_increment(frequency, sample_rate),
             amplitude,
+            sample_rate,
+            interpolation: SineInterpolation::default(),
         }
     }
     
-    /// Set new frequency
-    pub fn set_frequency(&mut self, frequency: f32, sample_rate: f32) {
-        self.phase_increment = freq_to_omega(frequency, sample_rate);
+    /// Set new frequency, at the rate the NCO was built for; the phase
+    /// carries on from where it is
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.phase_increment = phase_increment(frequency, self.sample_rate);
+    }
+    
+    /// Frequency as set, to the resolution of the phase step
+    /// (sample rate / 2^32)
+    pub fn frequency(&self) -> f32 {
+        (self.phase_increment as i32 as f64 / TURN * self.sample_rate as f64) as f32
+    }
+    
+    /// Current phase in radians, [0, 2π)
+    pub fn phase(&self) -> f32 {
+        (self.phase as f64 / TURN * core::f64::consts::TAU) as f32
+    }
+    
+    /// Jump to a phase in radians (any value, wrapped)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = radians_to_word(phase);
+    }
+    
+    /// Current phase word, 2^32 = one turn; exact, for handing phase
+    /// between oscillators
+    pub fn phase_word(&self) -> u32 {
+        self.phase
+    }
+    
+    pub fn set_phase_word(&mut self, phase: u32) {
+        self.phase = phase;
+    }
+    
+    pub fn amplitude(&self) -> f32 {
+        self.amplitude
+    }
+    
+    pub fn set_amplitude(&mut self, amplitude: f32) {
+        self.amplitude = amplitude;
+    }
+    
+    pub fn interpolation(&self) -> SineInterpolation {
+        self.interpolation
+    }
+    
+    pub fn set_interpolation(&mut self, interpolation: SineInterpolation) {
+        self.interpolation = interpolation;
     }
     
     /// Generate next sample
     #[inline]
+    #[allow(clippy::should_implement_trait)] // never ends, so not an Iterator
     pub fn next(&mut self) -> f32 {
-        let sample = self.amplitude * self.phase.sin();
-        self.phase += self.phase_increment;
-        
-        // Wrap phase to prevent accumulation errors
-        if self.phase >= 2.0 * PI {
-            self.phase -= 2.0 * PI;
-        }
-        
+        let sample = self.amplitude * sin_lookup(self.phase, self.interpolation);
+        self.phase = self.phase.wrapping_add(self.phase_increment);
         sample
     }
     
-    /// Generate block of samples
+    /// Generate block of samples, the same as calling `next()` for each
     pub fn generate(&mut self, samples: &mut [f32]) {
         for sample in samples.iter_mut() {
             *sample = self.next();
         }
     }
+    
+    pub fn reset(&mut self) {
+        self.phase = 0;
+    }
+}
+
+/// Quadrature NCO producing e^(j*phase) = (cos, sin) pairs.
+///
+/// Shared by everything coherent: the QAM modulator mixes up with `next_phasor()`,
+/// receivers mix down with `mix_down()` and steer the loop with
+/// `adjust_phase()`/`set_frequency()`. Same 32-bit phase word and sine
+/// table as `NCO`, with cos read a quarter turn ahead.
+#[derive(Debug, Clone)]
+pub struct ComplexNCO {
+    phase: u32,
+    phase_increment: u32,
+    sample_rate: f32,
+}
+
+impl ComplexNCO {
+    pub fn new(frequency: f32, sample_rate: f32) -> Self {
+        Self {
+            phase: 0,
+            phase_increment: phase_increment(frequency, sample_rate),
+            sample_rate,
+        }
+    }
+    
+    /// Set new frequency; the phase carries on from where it is
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.phase_increment = phase_increment(frequency, self.sample_rate);
+    }
+    
+    /// Frequency as set, to the resolution of the phase step
+    pub fn frequency(&self) -> f32 {
+        (self.phase_increment as i32 as f64 / TURN * self.sample_rate as f64) as f32
+    }
+    
+    /// Current phase in radians, [0, 2π)
+    pub fn phase(&self) -> f32 {
+        (self.phase as f64 / TURN * core::f64::consts::TAU) as f32
+    }
+    
+    /// Jump to a phase in radians (any value, wrapped)
+    pub fn set_phase(&mut self, phase: f32) {
+        self.phase = radians_to_word(phase);
+    }
+    
+    /// Current phase word, 2^32 = one turn
+    pub fn phase_word(&self) -> u32 {
+        self.phase
+    }
+    
+    pub fn set_phase_word(&mut self, phase: u32) {
+        self.phase = phase;
+    }
+    
+    /// Nudge the phase (loop corrections); the word wraps by itself
+    pub fn adjust_phase(&mut self, delta: f32) {
+        self.phase = self.phase.wrapping_add(radians_to_word(delta));
+    }
+    
+    /// Unit phasor at the current phase without advancing
+    #[inline]
+    pub fn current(&self) -> Complex32 {
+        let cos = sin_lookup(self.phase.wrapping_add(1 << 30), SineInterpolation::Linear);
+        let sin = sin_lookup(self.phase, SineInterpolation::Linear);
+        Complex32::new(cos, sin)
+    }
+    
+    /// Advance one sample
+    #[inline]
+    pub fn step(&mut self) {
+        self.phase = self.phase.wrapping_add(self.phase_increment);
+    }
+    
+    /// Current phasor, then advance
+    #[inline]
+    pub fn next_phasor(&mut self) -> Complex32 {
+        let phasor = self.current();
+        self.step();
+        phasor
+    }
+    
+    /// Shift a real passband sample down to baseband: x * e^(-j*phase), then advance
+    #[inline]
+    pub fn mix_down(&mut self, input: f32) -> Complex32 {
+        let phasor = self.next_phasor();
+        Complex32::new(input * phasor.re, -input * phasor.im)
+    }
+    
+    /// Shift an analytic passband sample down to baseband: z * e^(-j*phase),
+    /// then advance. Unlike `mix_down` this leaves no image at twice the
+    /// carrier.
+    #[inline]
+    pub fn mix_down_complex(&mut self, input: Complex32) -> Complex32 {
+        input * self.next_phasor().conj()
+    }
+    
+    pub fn reset(&mut self) {
+        self.phase = 0;
+    }
 }
 
 /// DTMF tone generator (for dialing)
 pub struct DTMFGenerator {
     row_osc: NCO,
     col_osc: NCO,
+    tx_level: TxLevel,
 }
 
 impl DTMFGenerator {
@@ -56,10 +284,26 @@ impl DTMFGenerator {
     const COL_FREQS: [f32; 4] = [1209.0, 1336.0, 1477.0, 1633.0];
     
     pub fn new(sample_rate: f32) -> Self {
-        Self {
-            row_osc: NCO::new(Self::ROW_FREQS[0], sample_rate, 0.5),
-            col_osc: NCO::new(Self::COL_FREQS[0], sample_rate, 0.5),
-        }
+        let mut generator = Self {
+            row_osc: NCO::new(Self::ROW_FREQS[0], sample_rate, 0.0),
+            col_osc: NCO::new(Self::COL_FREQS[0], sample_rate, 0.0),
+            tx_level: TxLevel::default(),
+        };
+        generator.set_tx_level(TxLevel::default());
+        generator
+    }
+    
+    /// Level of the tone pair; the two tones share it equally
+    pub fn set_tx_level(&mut self, level: TxLevel) {
+        // Two unit-amplitude tones carry a mean-square power of 1.0
+        let amplitude = level.gain(1.0);
+        self.row_osc.set_amplitude(amplitude);
+        self.col_osc.set_amplitude(amplitude);
+        self.tx_level = level;
+    }
+    
+    pub fn tx_level(&self) -> TxLevel {
+        self.tx_level
     }
     
     /// Generate DTMF tone for digit (0-9, *, #, A-D)
@@ -72,12 +316,15 @@ impl DTMFGenerator {
             _ => return vec![0.0; duration_samples],
         };
         
-        self.row_osc.set_frequency(Self::ROW_FREQS[row_idx], SAMPLE_RATE);
-        self.col_osc.set_frequency(Self::COL_FREQS[col_idx], SAMPLE_RATE);
+        self.row_osc.set_frequency(Self::ROW_FREQS[row_idx]);
+        self.col_osc.set_frequency(Self::COL_FREQS[col_idx]);
         
         let mut samples = vec![0.0; duration_samples];
-        for sample in samples.iter_mut() {
-            *sample = self.row_osc.next() + self.col_osc.next();
+        let mut col = vec![0.0; duration_samples];
+        self.row_osc.generate(&mut samples);
+        self.col_osc.generate(&mut col);
+        for (sample, col) in samples.iter_mut().zip(&col) {
+            *sample += col;
         }
         
         samples
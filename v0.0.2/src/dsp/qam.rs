@@ -1,5 +1,6 @@
 // src/dsp/qam.rs
 use super::*;
+use super::equalizer::Slicer;
 use num_complex::Complex32;
 
 /// V.22/V.22bis mode specifications [web:82][web:84]
@@ -36,39 +37,63 @@ impl QAMMode {
     pub fn carrier_freq_answer(&self) -> f32 {
         2400.0  // High band
     }
+    
+    /// Decision slicer for the mode's constellation
+    pub fn slicer(&self) -> ModeSlicer {
+        match self {
+            QAMMode::V22 | QAMMode::Bell212A => ModeSlicer::Dpsk(DPSKSlicer),
+            QAMMode::V22bis => ModeSlicer::Qam16(QAM16Slicer),
+        }
+    }
 }
 
-/// 16-QAM constellation for V.22bis [web:82][web:88]
-const QAM16_CONSTELLATION: [(i8, i8); 16] = [
-    // (I, Q) amplitudes
-    (1, 1),   (1, 3),   (3, 1),   (3, 3),    // Quadrant 1
-    (-1, 1),  (-1, 3),  (-3, 1),  (-3, 3),   // Quadrant 2
-    (-1, -1), (-1, -3), (-3, -1), (-3, -3),  // Quadrant 3
-    (1, -1),  (1, -3),  (3, -1),  (3, -3),   // Quadrant 4
-];
+/// V.22bis 16-QAM points in the first quadrant, by the last two bits of a
+/// quadbit [web:82][web:88]. The other quadrants are this one turned by
+/// 90°, 180° and 270°, so a carrier loop locked a quarter turn out moves
+/// every point to another quadrant but leaves those two bits alone.
+const QAM16_QUADRANT_POINTS: [(i8, i8); 4] = [(1, 1), (1, 3), (3, 1), (3, 3)];
 
-/// Map 4 bits to 16-QAM constellation point [web:82]
+/// Map 4 bits to a 16-QAM constellation point: the first two bits are the
+/// quadrant (0-3, counter-clockwise from +I+Q), the last two the point
+/// within it [web:82]
 pub fn map_to_qam16(bits: u8) -> Complex32 {
-    let idx = (bits & 0x0F) as usize;
-    let (i, q) = QAM16_CONSTELLATION[idx];
-    Complex32::new(i as f32, q as f32) / 3.0  // Normalize
+    let (i, q) = QAM16_QUADRANT_POINTS[((bits >> 2) & 0x03) as usize];
+    let point = Complex32::new(i as f32, q as f32) / 3.0;  // Normalize
+    match bits & 0x03 {
+        0 => point,
+        1 => Complex32::new(-point.im, point.re),
+        2 => -point,
+        _ => Complex32::new(point.im, -point.re),
+    }
 }
 
-/// Find nearest constellation point (slicer/decision)
+/// Find nearest constellation point (slicer/decision), as `map_to_qam16` bits
 pub fn slice_qam16(symbol: Complex32) -> u8 {
-    let mut min_dist = f32::MAX;
-    let mut best_idx = 0u8;
-    
-    for (idx, &(i, q)) in QAM16_CONSTELLATION.iter().enumerate() {
-        let constellation_point = Complex32::new(i as f32, q as f32) / 3.0;
-        let dist = (symbol - constellation_point).norm_sqr();
-        if dist < min_dist {
-            min_dist = dist;
-            best_idx = idx as u8;
-        }
-    }
+    // Quadrant from the signs, then turn it back onto the first
+    let (quadrant, first) = match (symbol.re >= 0.0, symbol.im >= 0.0) {
+        (true, true) => (0, symbol),
+        (false, true) => (1, Complex32::new(symbol.im, -symbol.re)),
+        (false, false) => (2, -symbol),
+        (true, false) => (3, Complex32::new(-symbol.im, symbol.re)),
+    };
     
-    best_idx
+    // Levels 1/3 and 1 on each axis, split at 2/3
+    let outer_i = (first.re > 2.0 / 3.0) as u8;
+    let outer_q = (first.im > 2.0 / 3.0) as u8;
+    quadrant | (outer_i << 3) | (outer_q << 2)
+}
+
+/// V.22bis differential quadrant coding: the first two bits of a quadbit
+/// turn the quadrant by as many quarter turns as V.22's dibits turn the
+/// phase, the last two pass through. `previous` is the last point's bits.
+pub fn qam16_differential_encode(bits: u8, previous: u8) -> u8 {
+    (previous.wrapping_add(bits) & 0x03) | (bits & 0x0C)
+}
+
+/// Inverse of `qam16_differential_encode`: the quarter turns between
+/// successive points, so a constant rotation of the constellation cancels
+pub fn qam16_differential_decode(point: u8, previous: u8) -> u8 {
+    (point.wrapping_sub(previous) & 0x03) | (point & 0x0C)
 }
 
 /// DPSK phase mapping for V.22 [web:96][web:99]
@@ -83,3 +108,55 @@ const DPSK_PHASE_MAP: [f32; 4] = [
 pub fn map_to_dpsk(bits: u8) -> f32 {
     DPSK_PHASE_MAP[(bits & 0x03) as usize]
 }
+
+/// DPSK points: unit circle at multiples of 90°
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DPSKSlicer;
+
+impl Slicer for DPSKSlicer {
+    fn slice(&self, symbol: Complex32) -> Complex32 {
+        let quadrant = (symbol.im.atan2(symbol.re) / (PI / 2.0)).round();
+        Complex32::from_polar(1.0, quadrant * PI / 2.0)
+    }
+
+    fn min_distance(&self) -> f32 {
+        core::f32::consts::SQRT_2
+    }
+}
+
+/// V.22bis 16-point grid (±1, ±3)/3
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QAM16Slicer;
+
+impl Slicer for QAM16Slicer {
+    fn slice(&self, symbol: Complex32) -> Complex32 {
+        map_to_qam16(slice_qam16(symbol))
+    }
+
+    fn min_distance(&self) -> f32 {
+        2.0 / 3.0
+    }
+}
+
+/// Slicer for any of the built-in modes
+#[derive(Debug, Clone, Copy)]
+pub enum ModeSlicer {
+    Dpsk(DPSKSlicer),
+    Qam16(QAM16Slicer),
+}
+
+impl Slicer for ModeSlicer {
+    fn slice(&self, symbol: Complex32) -> Complex32 {
+        match self {
+            ModeSlicer::Dpsk(s) => s.slice(symbol),
+            ModeSlicer::Qam16(s) => s.slice(symbol),
+        }
+    }
+
+    fn min_distance(&self) -> f32 {
+        match self {
+            ModeSlicer::Dpsk(s) => s.min_distance(),
+            ModeSlicer::Qam16(s) => s.min_distance(),
+        }
+    }
+}
@@ -2,30 +2,104 @@
 use super::*;
 use super::qam::*;
 use super::scrambler::Scrambler;
-use super::equalizer::LMSEqualizer;
+use super::equalizer::{Adaptation, EqualizerConfig, FSEqualizer, TapSpacing};
 use super::costas::CostasLoop;
-use super::oscillator::NCO;
-use super::filters::BiquadFilter;
+use super::oscillator::ComplexNCO;
+use super::fir::{root_raised_cosine, FirFilter, PulseShaper};
+use super::tap::SymbolTap;
+use super::timing::GardnerTED;
+use super::agc::{AGCConfig, AGC};
+use super::framing::{AsyncDeframer, AsyncFramer, CharBits, CharFormat, FramingStats};
+use super::bits::BitWriter;
 use num_complex::Complex32;
 
+/// Default RRC roll-off; V.22 specifies 75% raised-cosine shaping split
+/// equally between transmitter and receiver
+pub const DEFAULT_ROLLOFF: f32 = 0.75;
+
+/// Span of the RRC pulse-shaping filters in symbols
+const RRC_SPAN_SYMBOLS: usize = 8;
+
+/// Timing loop noise bandwidth as a fraction of the symbol rate
+pub(crate) const TIMING_LOOP_BANDWIDTH: f32 = 0.01;
+
+/// Carrier loop tracking bandwidth as a fraction of the symbol rate (the
+/// loop widens this while acquiring)
+const CARRIER_LOOP_BANDWIDTH: f32 = 0.02;
+
+/// Averaging time (ms) of the input level the carrier detector watches;
+/// long enough that 16-QAM's envelope doesn't move it by more than a dB
+const CARRIER_LEVEL_MS: f32 = 20.0;
+
+/// Rise in input power over the quietest level since the last restart that
+/// counts as the far end's carrier arriving (10 dB)
+const CARRIER_RISE: f32 = 10.0;
+
+/// How long (ms) the level must stop rising before the loops restart, so
+/// the AGC has caught up with the new carrier
+const CARRIER_SETTLE_MS: f32 = 40.0;
+
+/// Oversampling of the transmit pulse table, for symbol instants between
+/// samples
+pub(crate) const TX_PULSE_PHASES: usize = 64;
+
+/// Matched RRC filter pair gains for `samples_per_symbol`.
+///
+/// The transmit filter gets sqrt(sps) so an impulse per symbol comes out at
+/// roughly symbol amplitude; the receive filter gets 1/sqrt(sps), undoing
+/// that, so the cascade peaks at 1.0 (the analytic mix loses no power to an
+/// image).
+pub(crate) fn rrc_taps(symbol_rate: f32, sample_rate: f32, rolloff: f32, gain: f32) -> Vec<f32> {
+    root_raised_cosine(symbol_rate, sample_rate, rolloff, RRC_SPAN_SYMBOLS)
+        .into_iter()
+        .map(|t| t * gain)
+        .collect()
+}
+
+/// Scrambled binary ones sent before the first character, as at the end
+/// of the V.22bis handshake, so the far end's descrambler and V.14
+/// receiver have settled
+pub(crate) const MARK_LEAD_IN_MS: f32 = 200.0;
+
+/// Mark idle symbols `flush` sends after the data, enough to carry the last
+/// character through the far end's filters, timing loop and equalizer
+pub(crate) const FLUSH_IDLE_SYMBOLS: usize = 16;
+
 /// QAM/DPSK modulator for V.22/V.22bis [web:82][web:84]
 pub struct QAMModulator {
     mode: QAMMode,
-    carrier_freq: f32,
-    sample_rate: f32,
-    symbol_rate: f32,
-    samples_per_symbol: usize,
     
     // Signal generation
-    i_osc: NCO,
-    q_osc: NCO,
+    carrier: ComplexNCO,
     scrambler: Scrambler,
     
     // DPSK state
     dpsk_phase: f32,
     
-    // Pulse shaping filter
-    tx_filter: BiquadFilter,
+    // 16-QAM state: the last point's bits, for differential quadrants
+    qam16_point: u8,
+    
+    // Pulse shaping, at the exact symbol instants
+    rolloff: f32,
+    shaper: PulseShaper,
+    
+    // Streaming state: bits of the symbol being gathered, samples still
+    // owed before the next symbol instant
+    partial: u8,
+    partial_len: usize,
+    pending: usize,
+    
+    // Mark idle bits left to send in the flush under way
+    flush_idle: Option<usize>,
+    
+    // V.14 async-to-sync conversion for modulate_bytes; `queued` holds the
+    // rest of the character `modulate_bytes_into` is sending
+    framer: AsyncFramer,
+    queued: CharBits,
+    
+    // Output scaling to the transmit level
+    tx_level: TxLevel,
+    gain: f32,
 }
 
 impl QAMModulator {
@@ -33,132 +107,426 @@ impl QAMModulator {
         mode: QAMMode,
         carrier_freq: f32,
         sample_rate: f32,
+    ) -> Self {
+        Self::with_rolloff(mode, carrier_freq, sample_rate, DEFAULT_ROLLOFF)
+    }
+    
+    /// Modulator with a specific RRC roll-off (0.0-1.0)
+    pub fn with_rolloff(
+        mode: QAMMode,
+        carrier_freq: f32,
+        sample_rate: f32,
+        rolloff: f32,
     ) -> Self {
         let symbol_rate = mode.symbol_rate();
-        let samples_per_symbol = (sample_rate / symbol_rate) as usize;
+        let samples_per_symbol = sample_rate / symbol_rate;
+        
+        // Quadrature carrier (cos/sin pair)
+        let carrier = ComplexNCO::new(carrier_freq, sample_rate);
         
-        // Quadrature oscillators (90° phase shift)
-        let i_osc = NCO::new(carrier_freq, sample_rate, 1.0);
-        let q_osc = NCO::new(carrier_freq, sample_rate, 1.0);
+        // Root-raised-cosine pulse, tabulated finely enough for symbol
+        // instants that fall between samples
+        let phases = TX_PULSE_PHASES as f32;
+        let gain = (samples_per_symbol * phases).sqrt();
+        let pulse = rrc_taps(symbol_rate, sample_rate * phases, rolloff, gain);
         
-        // Transmit filter (root raised cosine approximation with lowpass)
-        let cutoff = symbol_rate * 0.6;
-        let tx_filter = BiquadFilter::lowpass(cutoff, 0.707, sample_rate);
+        let mut framer = AsyncFramer::v14(CharFormat::default());
+        let lead_in_symbols = (MARK_LEAD_IN_MS * symbol_rate / 1000.0) as usize;
+        framer.set_lead_in(lead_in_symbols * mode.bits_per_symbol());
+        
+        let tx_level = TxLevel::default();
         
         Self {
             mode,
-            carrier_freq,
-            sample_rate,
-            symbol_rate,
-            samples_per_symbol,
-            i_osc,
-            q_osc,
+            carrier,
             scrambler: Scrambler::new(),
             dpsk_phase: 0.0,
-            tx_filter,
+            qam16_point: 0,
+            rolloff,
+            shaper: PulseShaper::new(pulse, TX_PULSE_PHASES, samples_per_symbol),
+            partial: 0,
+            partial_len: 0,
+            pending: 0,
+            flush_idle: None,
+            framer,
+            queued: CharBits::default(),
+            tx_level,
+            gain: tx_level.gain(unit_power(mode)),
         }
     }
     
-    /// Modulate bits to audio samples
+    /// Line level of the signal (default 0 dBm0)
+    pub fn set_tx_level(&mut self, level: TxLevel) {
+        self.gain = level.gain(unit_power(self.mode));
+        self.tx_level = level;
+    }
+    
+    pub fn tx_level(&self) -> TxLevel {
+        self.tx_level
+    }
+    
+    /// Modulate bits to audio samples; an incomplete last symbol is
+    /// dropped
     pub fn modulate(&mut self, bits: &[bool]) -> Vec<f32> {
         let bits_per_symbol = self.mode.bits_per_symbol();
+        let whole = bits.len() / bits_per_symbol * bits_per_symbol;
+        let mut bits = bits[..whole].iter().copied();
         let mut samples = Vec::new();
-        
-        // Process symbols
-        for symbol_bits in bits.chunks(bits_per_symbol) {
-            if symbol_bits.len() != bits_per_symbol {
-                break;  // Incomplete symbol
+        let mut block = [0.0; 256];
+        loop {
+            let n = self.modulate_into(&mut bits, &mut block);
+            if n == 0 {
+                return samples;
+            }
+            samples.extend_from_slice(&block[..n]);
+        }
+    }
+    
+    /// Modulate into `out` without allocating; returns the samples
+    /// written. Stops when `out` is full or `bits` runs out; bits short of
+    /// a whole symbol are held until the next call. `bits` can be a
+    /// `BitReader` over packed bytes.
+    pub fn modulate_into<I: Iterator<Item = bool>>(&mut self, bits: &mut I, out: &mut [f32]) -> usize {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        let mut written = 0;
+        loop {
+            // Samples up to the next symbol instant
+            while self.pending > 0 && written < out.len() {
+                out[written] = self.shape_and_mix();
+                self.pending -= 1;
+                written += 1;
+            }
+            if self.pending > 0 || written == out.len() {
+                return written;
             }
             
             // Pack bits into byte
-            let mut symbol_data = 0u8;
-            for (i, &bit) in symbol_bits.iter().enumerate() {
+            while self.partial_len < bits_per_symbol {
+                let Some(bit) = bits.next() else {
+                    return written;
+                };
                 if bit {
-                    symbol_data |= 1 << i;
+                    self.partial |= 1 << self.partial_len;
                 }
+                self.partial_len += 1;
             }
-            
-            // Scramble
-            let scrambled = if bits_per_symbol == 2 {
-                let mut s = 0u8;
-                s |= if self.scrambler.scramble_bit((symbol_data >> 0) & 1 != 0) { 1 } else { 0 };
-                s |= if self.scrambler.scramble_bit((symbol_data >> 1) & 1 != 0) { 2 } else { 0 };
-                s
-            } else {
-                self.scrambler.scramble_byte(symbol_data)
-            };
-            
-            // Map to constellation
-            let baseband_symbol = match self.mode {
-                QAMMode::V22 | QAMMode::Bell212A => {
-                    // DPSK modulation [web:96]
-                    let phase_shift = map_to_dpsk(scrambled);
-                    self.dpsk_phase += phase_shift;
-                    while self.dpsk_phase >= 2.0 * PI {
-                        self.dpsk_phase -= 2.0 * PI;
-                    }
-                    Complex32::new(self.dpsk_phase.cos(), self.dpsk_phase.sin())
-                }
-                QAMMode::V22bis => {
-                    // 16-QAM [web:82][web:88]
-                    map_to_qam16(scrambled)
+            let symbol = self.map_symbol(self.partial);
+            self.partial = 0;
+            self.partial_len = 0;
+            self.pending = self.shaper.push(symbol);
+        }
+    }
+    
+    /// Scramble one symbol's bits and map them to the constellation
+    fn map_symbol(&mut self, symbol_data: u8) -> Complex32 {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        
+        // Scramble
+        let mut scrambled = 0u8;
+        for i in 0..bits_per_symbol {
+            if self.scrambler.scramble_bit((symbol_data >> i) & 1 != 0) {
+                scrambled |= 1 << i;
+            }
+        }
+        
+        // Map to constellation
+        match self.mode {
+            QAMMode::V22 | QAMMode::Bell212A => {
+                // DPSK modulation [web:96]
+                let phase_shift = map_to_dpsk(scrambled);
+                self.dpsk_phase += phase_shift;
+                while self.dpsk_phase >= 2.0 * PI {
+                    self.dpsk_phase -= 2.0 * PI;
                 }
-            };
-            
-            // Generate samples for this symbol
-            for _ in 0..self.samples_per_symbol {
-                let i_carrier = self.i_osc.next();
-                let q_carrier = self.q_osc.next();
-                
-                // Quadrature modulation
-                let sample = baseband_symbol.re * i_carrier - baseband_symbol.im * q_carrier;
-                
-                // Pulse shaping
-                let filtered = self.tx_filter.process(sample);
-                samples.push(filtered);
+                Complex32::new(self.dpsk_phase.cos(), self.dpsk_phase.sin())
+            }
+            QAMMode::V22bis => {
+                // 16-QAM, quadrant differentially coded [web:82][web:88]
+                self.qam16_point = qam16_differential_encode(scrambled, self.qam16_point);
+                map_to_qam16(self.qam16_point)
             }
         }
+    }
+    
+    /// Next pulse-shaped baseband sample, mixed up to the carrier
+    #[inline]
+    fn shape_and_mix(&mut self) -> f32 {
+        let lo = self.carrier.next_phasor();
+        let baseband = self.shaper.next_sample();
         
-        samples
+        // Quadrature modulation: Re{(i + jq) e^(jwt)}
+        self.gain * (baseband.re * lo.re - baseband.im * lo.im)
+    }
+    
+    /// Finish the data sent so far: the rest of a queued character, mark
+    /// idle completing the last symbol and then `FLUSH_IDLE_SYMBOLS` more,
+    /// so the far end's receiver gives up every character, and the pulse
+    /// tails
+    pub fn flush(&mut self) -> Vec<f32> {
+        let mut samples = Vec::new();
+        let mut block = [0.0; 256];
+        loop {
+            let n = self.flush_into(&mut block);
+            if n == 0 {
+                return samples;
+            }
+            samples.extend_from_slice(&block[..n]);
+        }
+    }
+    
+    /// `flush` into `out` without allocating; returns the samples written,
+    /// 0 once the flush is over
+    pub fn flush_into(&mut self, out: &mut [f32]) -> usize {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        let held = self.queued.len() + self.partial_len;
+        let padding = (bits_per_symbol - held % bits_per_symbol) % bits_per_symbol;
+        let remaining = *self.flush_idle.get_or_insert(padding + FLUSH_IDLE_SYMBOLS * bits_per_symbol);
+        
+        let mut queued = core::mem::take(&mut self.queued);
+        let mut written = self.modulate_into(&mut queued, out);
+        self.queued = queued;
+        let mut idle = core::iter::repeat_n(true, remaining);
+        written += self.modulate_into(&mut idle, &mut out[written..]);
+        self.flush_idle = Some(idle.len());
+        if written == out.len() {
+            return written;
+        }
+        
+        // Idle all sent: the pulse tails
+        let n = self.shaper.tail_len().min(out.len() - written);
+        for sample in &mut out[written..written + n] {
+            *sample = self.shape_and_mix();
+        }
+        written += n;
+        if written == 0 {
+            self.flush_idle = None;
+        }
+        written
     }
     
-    /// Modulate bytes
+    pub fn rolloff(&self) -> f32 {
+        self.rolloff
+    }
+    
+    /// Modulate bytes as async characters through V.14 conversion; the
+    /// first call after `new`/`reset` starts with 200 ms of mark
     pub fn modulate_bytes(&mut self, data: &[u8]) -> Vec<f32> {
-        let mut bits = Vec::with_capacity(data.len() * 8);
-        for &byte in data {
-            for i in 0..8 {
-                bits.push((byte >> i) & 1 != 0);
+        let bits = self.framer.frame(data);
+        self.modulate_padded(bits)
+    }
+    
+    /// Frame bytes through V.14 conversion and modulate into `out` without
+    /// allocating; returns (bytes taken, samples written). A byte counts as
+    /// taken once its character is started. Unlike `modulate_bytes` the
+    /// last symbol isn't padded out: it waits for more data, or for idle
+    /// sent through `modulate_into` (e.g. `core::iter::repeat_n(true, n)`).
+    pub fn modulate_bytes_into(&mut self, data: &[u8], out: &mut [f32]) -> (usize, usize) {
+        let mut taken = 0;
+        let mut written = 0;
+        loop {
+            let mut queued = core::mem::take(&mut self.queued);
+            written += self.modulate_into(&mut queued, &mut out[written..]);
+            self.queued = queued;
+            if self.queued.len() > 0 || written == out.len() || taken == data.len() {
+                return (taken, written);
             }
+            self.queued = self.framer.frame_char(data[taken]);
+            taken += 1;
         }
+    }
+    
+    /// Mark idle (scrambled binary ones), rounded up to whole symbols
+    pub fn idle(&mut self, bits: usize) -> Vec<f32> {
+        self.modulate_padded(vec![true; bits])
+    }
+    
+    /// Send a V.14 break
+    pub fn modulate_break(&mut self) -> Vec<f32> {
+        let bits = self.framer.break_bits();
+        self.modulate_padded(bits)
+    }
+    
+    /// Modulate with mark idle filling out the last symbol
+    fn modulate_padded(&mut self, mut bits: Vec<bool>) -> Vec<f32> {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        let padded = bits.len().div_ceil(bits_per_symbol) * bits_per_symbol;
+        bits.resize(padded, true);
         self.modulate(&bits)
     }
     
+    /// Character format used by `modulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.framer.set_format(format);
+    }
+    
+    /// Delete one stop bit in every 8 characters (V.14 overspeed)
+    pub fn set_stop_bit_deletion(&mut self, delete: bool) {
+        self.framer.set_stop_bit_deletion(delete);
+    }
+    
     pub fn reset(&mut self) {
+        self.framer.reset();
         self.scrambler.reset();
+        self.carrier.reset();
         self.dpsk_phase = 0.0;
-        self.tx_filter.reset();
+        self.qam16_point = 0;
+        self.shaper.reset();
+        self.partial = 0;
+        self.partial_len = 0;
+        self.pending = 0;
+        self.flush_idle = None;
+        self.queued = CharBits::default();
+    }
+}
+
+/// Descrambled ones in a row after which the descrambler's register is
+/// taken to hold the far end's scrambler state (twice its 17 bits)
+const TRAINING_SYNC_BITS: usize = 34;
+
+/// Descrambled ones in a row after which the training counts as having
+/// come through clean, and the receiver's output is no longer held at mark
+const TRAINING_CLEAN_BITS: usize = 128;
+
+/// Symbols out of the last 16 decided off the predicted quadrant before
+/// the far end is taken to have stopped training; fewer are line errors
+const TRAINING_MAX_MISSES: u32 = 4;
+
+/// Known symbols of the scrambled-ones training, for the equalizer to
+/// train against.
+///
+/// The descrambler's register holds the last 17 line bits, so after a run
+/// of descrambled ones it matches the far end's scrambler. A copy of it run
+/// on ones then predicts every training symbol, whatever the slicer makes
+/// of them; the quadrant coding starts from the decision at that point.
+/// Quadrant decisions hold up through ISI that still upsets the points
+/// within a quadrant, so they tell whether the far end is still training:
+/// a symbol decided off the predicted quadrant isn't trained on until one
+/// lands on it again, and a few of them close together (data, or a slip)
+/// end it.
+struct TrainingReference {
+    bits_per_symbol: usize,
+    scrambler: Option<Scrambler>,
+    ones: usize,
+    // Last 16 symbols, a set bit for each off the predicted quadrant
+    misses: u16,
+    // Whether the training has come through clean since the last reset
+    clean: bool,
+    // Last predicted point: DPSK phasor, or 16-QAM bits
+    dpsk_point: Complex32,
+    qam16_point: u8,
+}
+
+impl TrainingReference {
+    fn new(mode: QAMMode) -> Self {
+        Self {
+            bits_per_symbol: mode.bits_per_symbol(),
+            scrambler: None,
+            ones: 0,
+            misses: 0,
+            clean: false,
+            dpsk_point: Complex32::new(1.0, 0.0),
+            qam16_point: 0,
+        }
+    }
+    
+    /// The next symbol the far end sends, once in step with it
+    fn next(&mut self) -> Option<Complex32> {
+        let scrambler = self.scrambler.as_mut()?;
+        let mut bits = 0u8;
+        for i in 0..self.bits_per_symbol {
+            if scrambler.scramble_bit(true) {
+                bits |= 1 << i;
+            }
+        }
+        
+        // As `QAMModulator::map_symbol`
+        let point = if self.bits_per_symbol == 2 {
+            self.dpsk_point *= Complex32::from_polar(1.0, map_to_dpsk(bits));
+            self.dpsk_point
+        } else {
+            self.qam16_point = qam16_differential_encode(bits, self.qam16_point);
+            map_to_qam16(self.qam16_point)
+        };
+        (self.misses & 1 == 0).then_some(point)
+    }
+    
+    /// Follow the receiver: a symbol's descrambled bits, its decision (as a
+    /// point and as 16-QAM bits) and the descrambler after it. Once it falls
+    /// out of step, whether the training had come through clean by then.
+    fn observe(&mut self, descrambled: u8, decision: Complex32, qam16_point: u8, descrambler: &Scrambler) -> Option<bool> {
+        if descrambled == (1u8 << self.bits_per_symbol) - 1 {
+            self.ones += self.bits_per_symbol;
+        } else {
+            self.ones = 0;
+        }
+        self.clean |= self.ones >= TRAINING_CLEAN_BITS;
+        
+        if self.scrambler.is_none() {
+            if self.ones >= TRAINING_SYNC_BITS {
+                self.scrambler = Some(descrambler.clone());
+                self.misses = 0;
+                self.dpsk_point = decision;
+                self.qam16_point = qam16_point;
+            }
+            return None;
+        }
+        
+        let miss = if self.bits_per_symbol == 2 {
+            (decision - self.dpsk_point).norm_sqr() > 0.5
+        } else {
+            (qam16_point ^ self.qam16_point) & 0x03 != 0
+        };
+        self.misses = (self.misses << 1) | miss as u16;
+        if self.misses.count_ones() < TRAINING_MAX_MISSES {
+            return None;
+        }
+        self.scrambler = None;
+        self.misses = 0;
+        Some(self.clean)
+    }
+    
+    fn in_step(&self) -> bool {
+        self.scrambler.is_some()
+    }
+    
+    fn reset(&mut self) {
+        self.scrambler = None;
+        self.ones = 0;
+        self.misses = 0;
+        self.clean = false;
     }
 }
 
 /// QAM/DPSK demodulator [web:84]
 pub struct QAMDemodulator {
     mode: QAMMode,
-    carrier_freq: f32,
-    sample_rate: f32,
     symbol_rate: f32,
-    samples_per_symbol: usize,
+    
+    // Input level control
+    agc: AGC,
+
+    // Carrier detection: smoothed input power, the quietest it has been
+    // since the loops last restarted, and symbols left until they restart
+    carrier_level: f32,
+    carrier_level_alpha: f32,
+    quiet_level: f32,
+    carrier_settle: usize,
     
     // Carrier recovery
     costas: CostasLoop,
     
-    // Equalization
-    equalizer: LMSEqualizer,
+    // Equalization, and the training it learns from
+    equalizer: FSEqualizer,
+    training: TrainingReference,
     
-    // Matched filter
-    rx_filter: BiquadFilter,
+    // Matched RRC filters (one per rail)
+    rolloff: f32,
+    rx_filter_i: FirFilter,
+    rx_filter_q: FirFilter,
     
-    // Symbol timing
-    sample_counter: f32,
+    // Symbol timing recovery
+    timing: GardnerTED,
+    first_strobe: f32,
     
     // Descrambling
     descrambler: Scrambler,
@@ -166,8 +534,14 @@ pub struct QAMDemodulator {
     // DPSK state
     prev_phase: f32,
     
-    // Buffers
-    symbol_buffer: Vec<Complex32>,
+    // 16-QAM state: the last decision's bits, for differential quadrants
+    prev_qam16_point: u8,
+
+    // V.14 sync-to-async conversion for demodulate_bytes
+    deframer: AsyncDeframer,
+
+    // Optional diagnostic capture
+    tap: Option<SymbolTap>,
 }
 
 impl QAMDemodulator {
@@ -175,71 +549,215 @@ impl QAMDemodulator {
         mode: QAMMode,
         carrier_freq: f32,
         sample_rate: f32,
+    ) -> Self {
+        Self::with_rolloff(mode, carrier_freq, sample_rate, DEFAULT_ROLLOFF)
+    }
+    
+    /// Demodulator with a specific RRC roll-off; must match the transmitter
+    pub fn with_rolloff(
+        mode: QAMMode,
+        carrier_freq: f32,
+        sample_rate: f32,
+        rolloff: f32,
     ) -> Self {
         let symbol_rate = mode.symbol_rate();
-        let samples_per_symbol = (sample_rate / symbol_rate) as usize;
+        let samples_per_symbol = sample_rate / symbol_rate;
         
-        // Matched filter
-        let cutoff = symbol_rate * 0.6;
-        let rx_filter = BiquadFilter::lowpass(cutoff, 0.707, sample_rate);
+        // Decision-directed carrier recovery on the analytic signal
+        let costas = CostasLoop::new(carrier_freq, sample_rate, symbol_rate, CARRIER_LOOP_BANDWIDTH);
+        
+        // Matched filters, applied at baseband after the mixer
+        let gain = 1.0 / samples_per_symbol.sqrt();
+        let taps = rrc_taps(symbol_rate, sample_rate, rolloff, gain);
         
-        // Carrier recovery with Costas loop
-        let loop_bw = symbol_rate * 0.05;  // 5% of symbol rate
-        let costas = CostasLoop::new(carrier_freq, sample_rate, loop_bw);
+        // Start strobing where the back-to-back TX+RX pulse peaks, behind
+        // the Hilbert transformer as well
+        let first_strobe = (taps.len() - 1 + costas.delay()) as f32 % samples_per_symbol;
+        let mut timing = GardnerTED::new(samples_per_symbol, TIMING_LOOP_BANDWIDTH);
+        timing.set_initial_offset(first_strobe);
         
-        // Adaptive equalizer (17 taps typical for V.22bis)
-        let equalizer = LMSEqualizer::new(17, 0.01);
+        // Adaptive equalizer
+        let equalizer = FSEqualizer::new(equalizer_config(mode), mode.slicer());
+        
+        // Level the back-to-back modulator produces, so the slicer
+        // thresholds line up with the constellation
+        let agc = AGC::new(AGCConfig::with_target(line_level_dbm0(mode)), sample_rate);
         
         Self {
             mode,
-            carrier_freq,
-            sample_rate,
             symbol_rate,
-            samples_per_symbol,
+            agc,
+            // As if the nominal line level were already coming in, like
+            // the AGC; only a later rise counts as a carrier arriving
+            carrier_level: dbm0_to_power(line_level_dbm0(mode)),
+            carrier_level_alpha: 1.0 - (-1000.0 / (CARRIER_LEVEL_MS * sample_rate)).exp(),
+            quiet_level: f32::INFINITY,
+            carrier_settle: 0,
             costas,
             equalizer,
-            rx_filter,
-            sample_counter: 0.0,
+            training: TrainingReference::new(mode),
+            rolloff,
+            rx_filter_i: FirFilter::new(&taps),
+            rx_filter_q: FirFilter::new(&taps),
+            timing,
+            first_strobe,
             descrambler: Scrambler::new(),
             prev_phase: 0.0,
-            symbol_buffer: Vec::new(),
+            prev_qam16_point: 0,
+            deframer: AsyncDeframer::v14(CharFormat::default()),
+            tap: None,
         }
     }
     
     /// Demodulate audio samples to bits
     pub fn demodulate(&mut self, samples: &[f32]) -> Vec<bool> {
+        let bits_per_symbol = self.mode.bits_per_symbol();
         let mut bits = Vec::new();
-        
         for &sample in samples {
-            // Matched filter
-            let filtered = self.rx_filter.process(sample);
+            if let Some(symbol_bits) = self.process_sample(sample) {
+                bits.extend((0..bits_per_symbol).map(|i| (symbol_bits >> i) & 1 == 1));
+            }
+        }
+        bits
+    }
+    
+    /// Demodulate into packed bits without allocating; returns the samples
+    /// consumed, which is fewer than given only if `bits` fills up
+    pub fn demodulate_into(&mut self, samples: &[f32], bits: &mut BitWriter) -> usize {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        for (i, &sample) in samples.iter().enumerate() {
+            if bits.remaining() < bits_per_symbol {
+                return i;
+            }
+            if let Some(symbol_bits) = self.process_sample(sample) {
+                for i in 0..bits_per_symbol {
+                    bits.push((symbol_bits >> i) & 1 == 1);
+                }
+            }
+        }
+        samples.len()
+    }
+    
+    /// `demodulate_bytes` into `out` without allocating; returns (samples
+    /// consumed, bytes written). One sample can complete two characters,
+    /// so this stops while fewer than two bytes of `out` are free.
+    pub fn demodulate_bytes_into(&mut self, samples: &[f32], out: &mut [u8]) -> (usize, usize) {
+        let bits_per_symbol = self.mode.bits_per_symbol();
+        let mut written = 0;
+        for (i, &sample) in samples.iter().enumerate() {
+            if out.len() - written < 2 {
+                return (i, written);
+            }
+            if let Some(symbol_bits) = self.process_sample(sample) {
+                for i in 0..bits_per_symbol {
+                    self.deframer.push_bit_with((symbol_bits >> i) & 1 == 1, |c| {
+                        if let Some(byte) = c.byte() {
+                            out[written] = byte;
+                            written += 1;
+                        }
+                    });
+                }
+            }
+        }
+        (samples.len(), written)
+    }
+    
+    /// One line sample in; at each symbol strobe, the symbol's descrambled
+    /// bits, first bit in the LSB
+    fn process_sample(&mut self, sample: f32) -> Option<u8> {
+        self.carrier_level += self.carrier_level_alpha * (sample * sample - self.carrier_level);
+        let sample = self.agc.process(sample);
+        
+        // Mix down with the recovered carrier
+        let mixed = self.costas.process(sample);
+        
+        // Matched filter
+        let baseband = Complex32::new(
+            self.rx_filter_i.process(mixed.re),
+            self.rx_filter_q.process(mixed.im),
+        );
+        
+        // Symbol timing recovery
+        let strobe = self.timing.process(baseband);
+        
+        if let Some(ref mut tap) = self.tap {
+            tap.record_sample(self.timing.symbol_phase().max(f32::EPSILON), baseband);
+        }
+        
+        if let Some(symbol) = strobe {
+            // A jump in level is the far end's carrier arriving: whatever
+            // the carrier loop and equalizer made of the noise before it
+            // would hold them off the signal, so once the level settles
+            // they start over
+            if self.carrier_level > self.quiet_level * CARRIER_RISE {
+                self.quiet_level = self.carrier_level;
+                self.carrier_settle = (CARRIER_SETTLE_MS * self.symbol_rate / 1000.0) as usize;
+            } else {
+                self.quiet_level = self.quiet_level.min(self.carrier_level);
+            }
+            if self.carrier_settle > 0 {
+                self.carrier_settle -= 1;
+                if self.carrier_settle == 0 {
+                    self.costas.reset();
+                    self.equalizer.reset();
+                    self.training.reset();
+                }
+            }
+
+            // The fourth-power frequency estimate wanders on ISI; with
+            // known symbols to track, the phase loop does without it
+            if !self.training.in_step() {
+                self.costas.acquire(symbol);
+            }
             
-            // Carrier recovery (Costas loop)
-            let baseband = self.costas.process(filtered);
+            // Equalize symbol (T/2 also takes the preceding midpoint)
+            // and derotate by the carrier loop's phase. While training it
+            // adapts on the known training symbols, once they are in step.
+            let rotation = self.costas.derotator();
+            let training = self.equalizer.is_training();
+            let reference = if training { self.training.next() } else { None };
+            let equalized = match self.equalizer.config().spacing {
+                TapSpacing::Symbol => self.equalizer.equalize_rotated(&[symbol], rotation, reference),
+                TapSpacing::HalfSymbol => {
+                    self.equalizer.equalize_rotated(&[self.timing.midpoint(), symbol], rotation, reference)
+                }
+            };
             
-            // Symbol timing
-            self.sample_counter += 1.0;
+            // Carrier phase tracking against the decision, or the known
+            // symbol while there is one
+            let decision = self.decision(equalized);
+            self.costas.update(equalized, reference.unwrap_or(decision));
             
-            if self.sample_counter >= self.samples_per_symbol as f32 {
-                self.sample_counter -= self.samples_per_symbol as f32;
-                
-                // Equalize symbol
-                let equalized = self.equalizer.equalize(baseband, None);
-                
-                // Demodulate symbol
-                let symbol_bits = self.demodulate_symbol(equalized);
-                
-                // Descramble
-                let descrambled_bits = self.descramble_symbol(symbol_bits);
-                bits.extend(descrambled_bits);
+            if let Some(ref mut tap) = self.tap {
+                tap.record_symbol(symbol, equalized, decision);
             }
+            
+            // Demodulate symbol
+            let symbol_bits = self.demodulate_symbol(equalized);
+            
+            // Descramble
+            let descrambled = self.descramble_symbol(symbol_bits);
+            // The training ending after it came through clean is the far
+            // end moving on to data: the taps are good enough to follow
+            // the decisions from here. Before that it was a false start,
+            // and the taps learned from it have to start over.
+            if training {
+                match self.training.observe(descrambled, decision, self.prev_qam16_point, &self.descrambler) {
+                    Some(true) => self.equalizer.set_training_mode(false),
+                    Some(false) => self.equalizer.reset(),
+                    None => {}
+                }
+            }
+            let marks = (1u8 << self.mode.bits_per_symbol()) - 1;
+            let clamped = !self.training.clean && self.equalizer.is_training();
+            return Some(if clamped { marks } else { descrambled });
         }
         
-        bits
+        None
     }
     
-    /// Demodulate single symbol
-    fn demodulate_symbol(&mut self, symbol: Complex32) -> Vec<bool> {
+    /// Demodulate single symbol, first bit in the LSB
+    fn demodulate_symbol(&mut self, symbol: Complex32) -> u8 {
         match self.mode {
             QAMMode::V22 | QAMMode::Bell212A => {
                 // DPSK demodulation [web:96][web:99]
@@ -257,7 +775,7 @@ impl QAMDemodulator {
                 self.prev_phase = current_phase;
                 
                 // Map phase to dibits
-                let dibit = if phase_diff < PI / 4.0 || phase_diff >= 7.0 * PI / 4.0 {
+                if !(PI / 4.0..7.0 * PI / 4.0).contains(&phase_diff) {
                     0b00
                 } else if phase_diff < 3.0 * PI / 4.0 {
                     0b01
@@ -265,60 +783,199 @@ impl QAMDemodulator {
                     0b10
                 } else {
                     0b11
-                };
-                
-                vec![(dibit & 1) != 0, (dibit & 2) != 0]
+                }
             }
             QAMMode::V22bis => {
-                // 16-QAM slicing [web:88]
-                let quadbits = slice_qam16(symbol);
-                vec![
-                    (quadbits & 1) != 0,
-                    (quadbits & 2) != 0,
-                    (quadbits & 4) != 0,
-                    (quadbits & 8) != 0,
-                ]
+                // 16-QAM slicing, then the quadrant change [web:88]
+                let point = slice_qam16(symbol);
+                let bits = qam16_differential_decode(point, self.prev_qam16_point);
+                self.prev_qam16_point = point;
+                bits
             }
         }
     }
     
+    /// Ideal constellation point nearest to an equalized symbol
+    fn decision(&self, symbol: Complex32) -> Complex32 {
+        self.equalizer.slice(symbol)
+    }
+    
     /// Descramble symbol bits
-    fn descramble_symbol(&mut self, bits: Vec<bool>) -> Vec<bool> {
-        bits.into_iter()
-            .map(|b| self.descrambler.scramble_bit(b))
-            .collect()
+    fn descramble_symbol(&mut self, bits: u8) -> u8 {
+        let mut descrambled = 0;
+        for i in 0..self.mode.bits_per_symbol() {
+            if self.descrambler.descramble_bit((bits >> i) & 1 == 1) {
+                descrambled |= 1 << i;
+            }
+        }
+        descrambled
     }
     
-    /// Demodulate to bytes
+    /// Demodulate to bytes through V.14 conversion; errored characters
+    /// are passed on as received, breaks are counted in `framing_stats`
     pub fn demodulate_bytes(&mut self, samples: &[f32]) -> Vec<u8> {
         let bits = self.demodulate(samples);
-        let mut bytes = Vec::new();
-        
-        for chunk in bits.chunks(8) {
-            if chunk.len() == 8 {
-                let mut byte = 0u8;
-                for (i, &bit) in chunk.iter().enumerate() {
-                    if bit {
-                        byte |= 1 << i;
-                    }
-                }
-                bytes.push(byte);
-            }
-        }
-        
-        bytes
+        self.deframer.deframe_bytes(&bits)
+    }
+    
+    /// Deframe bits `demodulate` returned, as `demodulate_bytes` would
+    pub fn deframe_bytes(&mut self, bits: &[bool]) -> Vec<u8> {
+        self.deframer.deframe_bytes(bits)
+    }
+    
+    /// Character format expected by `demodulate_bytes` (default 8N1)
+    pub fn set_char_format(&mut self, format: CharFormat) {
+        self.deframer.set_format(format);
+    }
+    
+    /// Character, parity error, stop-bit insertion and break counts
+    pub fn framing_stats(&self) -> &FramingStats {
+        self.deframer.stats()
     }
     
     pub fn reset(&mut self) {
+        self.agc.reset();
+        self.carrier_level = dbm0_to_power(line_level_dbm0(self.mode));
+        self.quiet_level = f32::INFINITY;
+        self.carrier_settle = 0;
         self.costas.reset();
         self.equalizer.reset();
-        self.rx_filter.reset();
+        self.training.reset();
+        self.rx_filter_i.reset();
+        self.rx_filter_q.reset();
         self.descrambler.reset();
-        self.sample_counter = 0.0;
+        self.timing.reset();
+        self.timing.set_initial_offset(self.first_strobe);
         self.prev_phase = 0.0;
+        self.prev_qam16_point = 0;
+        self.deframer.reset();
+        if let Some(ref mut tap) = self.tap {
+            tap.clear();
+        }
+    }
+    
+    /// Start recording the last `capacity` symbols for constellation/eye plots
+    pub fn enable_tap(&mut self, capacity: usize) {
+        self.tap = Some(SymbolTap::new(capacity));
+    }
+    
+    pub fn disable_tap(&mut self) {
+        self.tap = None;
+    }
+    
+    pub fn tap(&self) -> Option<&SymbolTap> {
+        self.tap.as_ref()
+    }
+    
+    /// Hand over the recording and stop tapping
+    pub fn take_tap(&mut self) -> Option<SymbolTap> {
+        self.tap.take()
     }
     
+    pub fn rolloff(&self) -> f32 {
+        self.rolloff
+    }
+    
+    /// Carrier loop lock, from the phase-error variance, once the equalizer
+    /// has converged; before that the variance can dip under the threshold
+    /// while the decisions are still wrong
     pub fn is_locked(&self) -> bool {
-        self.costas.is_locked()
+        self.costas.is_locked() && !self.equalizer.is_training()
+    }
+    
+    /// Offset of the received carrier from nominal, in Hz
+    pub fn carrier_offset(&self) -> f32 {
+        self.costas.frequency_offset()
+    }
+    
+    pub fn phase_error_variance(&self) -> f32 {
+        self.costas.phase_error_variance()
+    }
+    
+    /// Latest Gardner timing error
+    pub fn timing_error(&self) -> f32 {
+        self.timing.timing_error()
     }
+    
+    /// Estimated offset between the far-end and local sample clocks
+    pub fn clock_offset(&self) -> f32 {
+        self.timing.clock_offset()
+    }
+    
+    pub fn is_timing_locked(&self) -> bool {
+        self.timing.is_locked()
+    }
+    
+    /// Smoothed equalizer error
+    pub fn equalizer_mse(&self) -> f32 {
+        self.equalizer.mse()
+    }
+    
+    pub fn equalizer_tap_energy(&self) -> f32 {
+        self.equalizer.tap_energy()
+    }
+    
+    /// Swap in a differently configured equalizer (starts untrained)
+    pub fn set_equalizer(&mut self, config: EqualizerConfig) {
+        self.equalizer = FSEqualizer::new(config, self.mode.slicer());
+        self.training.reset();
+    }
+    
+    pub fn equalizer_config(&self) -> &EqualizerConfig {
+        self.equalizer.config()
+    }
+    
+    /// Whether the equalizer has converged and adapts on its own decisions
+    pub fn is_decision_directed(&self) -> bool {
+        !self.equalizer.is_training()
+    }
+    
+    /// Receive level before the AGC
+    pub fn receive_level_dbm0(&self) -> f32 {
+        self.agc.level_dbm0()
+    }
+    
+    /// Switch between training and data; may freeze the AGC
+    pub fn set_data_mode(&mut self, data: bool) {
+        self.agc.set_data_mode(data);
+    }
+    
+    pub fn agc(&self) -> &AGC {
+        &self.agc
+    }
+    
+    /// AGC access; keep the target at `line_level_dbm0` or the slicer
+    /// thresholds will be off
+    pub fn agc_mut(&mut self) -> &mut AGC {
+        &mut self.agc
+    }
+}
+
+/// Default equalizer for `mode`: NLMS is plenty for the four DPSK points,
+/// the 16-point V.22bis grid gets the faster-converging RLS
+pub fn equalizer_config(mode: QAMMode) -> EqualizerConfig {
+    match mode {
+        QAMMode::V22 | QAMMode::Bell212A => EqualizerConfig::default(),
+        QAMMode::V22bis => EqualizerConfig {
+            adaptation: Adaptation::Rls { forgetting: 0.995 },
+            ..EqualizerConfig::default()
+        },
+    }
+}
+
+/// Level at which the demodulator sees the nominal constellation: that of
+/// `QAMModulator` output before its transmit-level gain.
+pub fn line_level_dbm0(mode: QAMMode) -> f32 {
+    power_to_dbm0(unit_power(mode))
+}
+
+/// Mean-square power of `mode` at unit gain. Each rail carries half the
+/// mean symbol energy: 1 for the DPSK points, 10/9 for the V.22bis
+/// constellation.
+pub(crate) fn unit_power(mode: QAMMode) -> f32 {
+    let symbol_energy = match mode {
+        QAMMode::V22 | QAMMode::Bell212A => 1.0,
+        QAMMode::V22bis => 10.0 / 9.0,
+    };
+    symbol_energy / 2.0
 }
@@ -2,11 +2,18 @@
 // Warning Fix: Removed unused 'use super::*;'
 
 /// V.22bis scrambler: qi = di ⊕ qi-14 ⊕ qi-17 [web:95]
+#[derive(Debug, Clone)]
 pub struct Scrambler {
 // ... (Rest of the file is unchanged)
     shift_register: u32,  // 17-bit shift register
 }
 
+impl Default for Scrambler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Scrambler {
     pub fn new() -> Self {
         Self {
@@ -30,6 +37,19 @@ impl Scrambler {
         output_bit != 0
     }
     
+    /// Descramble single bit: qi = di ⊕ di-14 ⊕ di-17, the register holding
+    /// received (scrambled) bits so it falls into step within 17 bits
+    pub fn descramble_bit(&mut self, input_bit: bool) -> bool {
+        let bit14 = (self.shift_register >> 13) & 1;
+        let bit17 = (self.shift_register >> 16) & 1;
+        
+        let output_bit = (input_bit as u32) ^ bit14 ^ bit17;
+        
+        self.shift_register = ((self.shift_register << 1) | input_bit as u32) & 0x1FFFF;
+        
+        output_bit != 0
+    }
+    
     /// Scramble byte (LSB first)
     pub fn scramble_byte(&mut self, byte: u8) -> u8 {
         let mut result = 0u8;
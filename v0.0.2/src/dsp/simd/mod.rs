@@ -0,0 +1,279 @@
+// src/dsp/simd/mod.rs
+//
+// Vectorized kernels for the per-sample hot loops: biquad and Goertzel
+// banks (vectorized across filters) and the equalizer's complex dot
+// product and tap update. The instruction set is
+// picked at run time (SSE2/AVX2 on x86, NEON on aarch64); without `std`
+// it is whatever the target enables at compile time.
+//
+// The bank kernels do the same IEEE operations in the same order as the
+// scalar filters, so their output is bit-exact. The dot products sum in a
+// different order, so those agree with the scalar path to rounding only.
+use super::*;
+use num_complex::Complex32;
+use core::fmt;
+use core::str::FromStr;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86;
+#[cfg(target_arch = "aarch64")]
+mod neon;
+
+/// Instruction set a kernel runs with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimdLevel {
+    Scalar,
+    /// 4 lanes, x86 baseline
+    Sse2,
+    /// 8 lanes
+    Avx2,
+    /// 4 lanes, aarch64 baseline
+    Neon,
+}
+
+#[cfg(all(feature = "std", any(target_arch = "x86", target_arch = "x86_64")))]
+macro_rules! x86_has {
+    ($feature:tt) => { std::is_x86_feature_detected!($feature) };
+}
+
+#[cfg(all(not(feature = "std"), any(target_arch = "x86", target_arch = "x86_64")))]
+macro_rules! x86_has {
+    ($feature:tt) => { cfg!(target_feature = $feature) };
+}
+
+/// `detect()` result, 0 until the first call
+static DETECTED: AtomicU8 = AtomicU8::new(0);
+
+impl SimdLevel {
+    pub const ALL: [SimdLevel; 4] = [SimdLevel::Scalar, SimdLevel::Sse2, SimdLevel::Avx2, SimdLevel::Neon];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimdLevel::Scalar => "scalar",
+            SimdLevel::Sse2 => "sse2",
+            SimdLevel::Avx2 => "avx2",
+            SimdLevel::Neon => "neon",
+        }
+    }
+
+    /// Whether this CPU runs the level
+    pub fn is_supported(&self) -> bool {
+        match self {
+            SimdLevel::Scalar => true,
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Sse2 => x86_has!("sse2"),
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Avx2 => x86_has!("avx2"),
+            #[cfg(target_arch = "aarch64")]
+            SimdLevel::Neon => cfg!(target_feature = "neon"),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Levels this CPU runs, scalar first
+    pub fn supported() -> Vec<SimdLevel> {
+        SimdLevel::ALL.into_iter().filter(|level| level.is_supported()).collect()
+    }
+
+    /// Widest level this CPU runs; detected once, then cached
+    pub fn detect() -> SimdLevel {
+        match DETECTED.load(Ordering::Relaxed) {
+            0 => {
+                let level = SimdLevel::ALL
+                    .into_iter()
+                    .rev()
+                    .find(|level| level.is_supported())
+                    .unwrap_or(SimdLevel::Scalar);
+                DETECTED.store(level as u8 + 1, Ordering::Relaxed);
+                level
+            }
+            n => SimdLevel::ALL[n as usize - 1],
+        }
+    }
+
+    /// This level, or scalar if the CPU can't run it; every kernel goes
+    /// through here, so a level from anywhere is safe to pass in
+    #[inline]
+    fn checked(self) -> SimdLevel {
+        if self.is_supported() {
+            self
+        } else {
+            SimdLevel::Scalar
+        }
+    }
+
+    /// sum(a[i] * b[i]) over the shorter slice
+    pub fn complex_dot(self, a: &[Complex32], b: &[Complex32]) -> Complex32 {
+        match self.checked() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Avx2 => unsafe { x86::avx2::complex_dot(a, b) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Sse2 => unsafe { x86::sse2::complex_dot(a, b) },
+            #[cfg(target_arch = "aarch64")]
+            SimdLevel::Neon => unsafe { neon::complex_dot(a, b) },
+            _ => scalar::complex_dot(a, b),
+        }
+    }
+
+    /// LMS tap update: w[i] += error * conj(x[i]) * mu
+    pub fn complex_axpy_conj(self, w: &mut [Complex32], x: &[Complex32], error: Complex32, mu: f32) {
+        match self.checked() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Avx2 => unsafe { x86::avx2::complex_axpy_conj(w, x, error, mu) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Sse2 => unsafe { x86::sse2::complex_axpy_conj(w, x, error, mu) },
+            #[cfg(target_arch = "aarch64")]
+            SimdLevel::Neon => unsafe { neon::complex_axpy_conj(w, x, error, mu) },
+            _ => scalar::complex_axpy_conj(w, x, error, mu),
+        }
+    }
+
+    /// sum(|x[i]|^2)
+    pub fn complex_energy(self, x: &[Complex32]) -> f32 {
+        match self.checked() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Avx2 => unsafe { x86::avx2::complex_energy(x) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Sse2 => unsafe { x86::sse2::complex_energy(x) },
+            #[cfg(target_arch = "aarch64")]
+            SimdLevel::Neon => unsafe { neon::complex_energy(x) },
+            _ => scalar::complex_energy(x),
+        }
+    }
+
+    /// Run whole frames (one sample per lane, lane fastest) through a
+    /// biquad bank
+    pub(crate) fn biquad_lanes(self, lanes: &mut BiquadLanes, input: &[f32], output: &mut [f32]) {
+        match self.checked() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Avx2 => unsafe { x86::avx2::biquad_lanes(lanes, input, output) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Sse2 => unsafe { x86::sse2::biquad_lanes(lanes, input, output) },
+            #[cfg(target_arch = "aarch64")]
+            SimdLevel::Neon => unsafe { neon::biquad_lanes(lanes, input, output) },
+            _ => scalar::biquad_lanes(lanes, 0, input, output),
+        }
+    }
+
+    /// Feed the same block of samples to every Goertzel lane
+    pub(crate) fn goertzel_lanes(self, lanes: &mut GoertzelLanes, input: &[f32]) {
+        match self.checked() {
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Avx2 => unsafe { x86::avx2::goertzel_lanes(lanes, input) },
+            #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+            SimdLevel::Sse2 => unsafe { x86::sse2::goertzel_lanes(lanes, input) },
+            #[cfg(target_arch = "aarch64")]
+            SimdLevel::Neon => unsafe { neon::goertzel_lanes(lanes, input) },
+            _ => scalar::goertzel_lanes(lanes, 0, input),
+        }
+    }
+}
+
+impl fmt::Display for SimdLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for SimdLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SimdLevel::ALL
+            .into_iter()
+            .find(|level| level.name().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown SIMD level {:?} (scalar, sse2, avx2, neon)", s))
+    }
+}
+
+impl Default for SimdLevel {
+    fn default() -> Self {
+        SimdLevel::detect()
+    }
+}
+
+/// Biquad bank state, structure-of-arrays so lanes load as vectors
+#[derive(Clone, Default)]
+pub(crate) struct BiquadLanes {
+    pub b0: Vec<f32>,
+    pub b1: Vec<f32>,
+    pub b2: Vec<f32>,
+    pub a1: Vec<f32>,
+    pub a2: Vec<f32>,
+    pub z1: Vec<f32>,
+    pub z2: Vec<f32>,
+}
+
+impl BiquadLanes {
+    pub fn len(&self) -> usize {
+        self.b0.len()
+    }
+}
+
+/// Goertzel bank state, structure-of-arrays
+#[derive(Clone, Default)]
+pub(crate) struct GoertzelLanes {
+    pub coefficient: Vec<f32>,
+    pub s1: Vec<f32>,
+    pub s2: Vec<f32>,
+}
+
+/// Reference loops, and the tails the vector kernels leave over. The
+/// arithmetic matches `BiquadFilter`, `GoertzelDetector` and the
+/// equalizer's original loops operation for operation.
+mod scalar {
+    use super::*;
+
+    pub fn complex_dot(a: &[Complex32], b: &[Complex32]) -> Complex32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    pub fn complex_axpy_conj(w: &mut [Complex32], x: &[Complex32], error: Complex32, mu: f32) {
+        for (w, x) in w.iter_mut().zip(x) {
+            *w += error * x.conj() * mu;
+        }
+    }
+
+    pub fn complex_energy(x: &[Complex32]) -> f32 {
+        x.iter().map(|x| x.norm_sqr()).sum()
+    }
+
+    /// Lanes from `first` on
+    pub fn biquad_lanes(lanes: &mut BiquadLanes, first: usize, input: &[f32], output: &mut [f32]) {
+        let width = lanes.len();
+        if width == 0 {
+            return;
+        }
+        let frames = input.len().min(output.len()) / width;
+        for lane in first..width {
+            let (b0, b1, b2, a1, a2) = (lanes.b0[lane], lanes.b1[lane], lanes.b2[lane], lanes.a1[lane], lanes.a2[lane]);
+            let (mut z1, mut z2) = (lanes.z1[lane], lanes.z2[lane]);
+            for frame in 0..frames {
+                let x = input[frame * width + lane];
+                let y = b0 * x + z1;
+                z1 = b1 * x - a1 * y + z2;
+                z2 = b2 * x - a2 * y;
+                output[frame * width + lane] = y;
+            }
+            lanes.z1[lane] = z1;
+            lanes.z2[lane] = z2;
+        }
+    }
+
+    /// Lanes from `first` on
+    pub fn goertzel_lanes(lanes: &mut GoertzelLanes, first: usize, input: &[f32]) {
+        for lane in first..lanes.coefficient.len() {
+            let c = lanes.coefficient[lane];
+            let (mut s1, mut s2) = (lanes.s1[lane], lanes.s2[lane]);
+            for &x in input {
+                let s0 = x + c * s1 - s2;
+                s2 = s1;
+                s1 = s0;
+            }
+            lanes.s1[lane] = s1;
+            lanes.s2[lane] = s2;
+        }
+    }
+}
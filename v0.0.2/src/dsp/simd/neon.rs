@@ -0,0 +1,115 @@
+// src/dsp/simd/neon.rs
+//
+// NEON (4 lanes) kernels for aarch64, where NEON is baseline. Multiplies
+// and adds are kept separate (no vfmaq) so the bank kernels round as the
+// scalar filters do.
+use super::*;
+use core::arch::aarch64::*;
+
+#[inline]
+#[target_feature(enable = "neon")]
+unsafe fn swap_pairs(v: float32x4_t) -> float32x4_t {
+    vrev64q_f32(v)
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn complex_dot(a: &[Complex32], b: &[Complex32]) -> Complex32 {
+    let n = a.len().min(b.len());
+    let (pa, pb) = (a.as_ptr() as *const f32, b.as_ptr() as *const f32);
+    let mut direct = vdupq_n_f32(0.0);
+    let mut crossed = vdupq_n_f32(0.0);
+    let mut i = 0;
+    while i + 2 <= n {
+        let va = vld1q_f32(pa.add(2 * i));
+        let vb = vld1q_f32(pb.add(2 * i));
+        direct = vaddq_f32(direct, vmulq_f32(va, vb));
+        crossed = vaddq_f32(crossed, vmulq_f32(va, swap_pairs(vb)));
+        i += 2;
+    }
+    // Alternate lanes of `direct` subtract
+    let signs = vld1q_f32([1.0, -1.0, 1.0, -1.0].as_ptr());
+    let re = vaddvq_f32(vmulq_f32(direct, signs));
+    Complex32::new(re, vaddvq_f32(crossed)) + scalar::complex_dot(&a[i..n], &b[i..n])
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn complex_axpy_conj(w: &mut [Complex32], x: &[Complex32], error: Complex32, mu: f32) {
+    let n = w.len().min(x.len());
+    let (pw, px) = (w.as_mut_ptr() as *mut f32, x.as_ptr() as *const f32);
+    let s = error * mu;
+    let real = vld1q_f32([s.re, -s.re, s.re, -s.re].as_ptr());
+    let imag = vdupq_n_f32(s.im);
+    let mut i = 0;
+    while i + 2 <= n {
+        let vx = vld1q_f32(px.add(2 * i));
+        let vw = vld1q_f32(pw.add(2 * i));
+        let update = vaddq_f32(vmulq_f32(vx, real), vmulq_f32(swap_pairs(vx), imag));
+        vst1q_f32(pw.add(2 * i), vaddq_f32(vw, update));
+        i += 2;
+    }
+    for (w, x) in w[i..n].iter_mut().zip(&x[i..n]) {
+        *w += s * x.conj();
+    }
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn complex_energy(x: &[Complex32]) -> f32 {
+    let px = x.as_ptr() as *const f32;
+    let mut acc = vdupq_n_f32(0.0);
+    let mut i = 0;
+    while i + 2 <= x.len() {
+        let v = vld1q_f32(px.add(2 * i));
+        acc = vaddq_f32(acc, vmulq_f32(v, v));
+        i += 2;
+    }
+    vaddvq_f32(acc) + scalar::complex_energy(&x[i..])
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn biquad_lanes(bank: &mut BiquadLanes, input: &[f32], output: &mut [f32]) {
+    let width = bank.len();
+    if width == 0 {
+        return;
+    }
+    let frames = input.len().min(output.len()) / width;
+    let mut lane = 0;
+    while lane + 4 <= width {
+        let b0 = vld1q_f32(bank.b0.as_ptr().add(lane));
+        let b1 = vld1q_f32(bank.b1.as_ptr().add(lane));
+        let b2 = vld1q_f32(bank.b2.as_ptr().add(lane));
+        let a1 = vld1q_f32(bank.a1.as_ptr().add(lane));
+        let a2 = vld1q_f32(bank.a2.as_ptr().add(lane));
+        let mut z1 = vld1q_f32(bank.z1.as_ptr().add(lane));
+        let mut z2 = vld1q_f32(bank.z2.as_ptr().add(lane));
+        for frame in 0..frames {
+            let x = vld1q_f32(input.as_ptr().add(frame * width + lane));
+            let y = vaddq_f32(vmulq_f32(b0, x), z1);
+            z1 = vaddq_f32(vsubq_f32(vmulq_f32(b1, x), vmulq_f32(a1, y)), z2);
+            z2 = vsubq_f32(vmulq_f32(b2, x), vmulq_f32(a2, y));
+            vst1q_f32(output.as_mut_ptr().add(frame * width + lane), y);
+        }
+        vst1q_f32(bank.z1.as_mut_ptr().add(lane), z1);
+        vst1q_f32(bank.z2.as_mut_ptr().add(lane), z2);
+        lane += 4;
+    }
+    scalar::biquad_lanes(bank, lane, input, output);
+}
+
+#[target_feature(enable = "neon")]
+pub unsafe fn goertzel_lanes(bank: &mut GoertzelLanes, input: &[f32]) {
+    let mut lane = 0;
+    while lane + 4 <= bank.coefficient.len() {
+        let c = vld1q_f32(bank.coefficient.as_ptr().add(lane));
+        let mut s1 = vld1q_f32(bank.s1.as_ptr().add(lane));
+        let mut s2 = vld1q_f32(bank.s2.as_ptr().add(lane));
+        for &x in input {
+            let s0 = vsubq_f32(vaddq_f32(vdupq_n_f32(x), vmulq_f32(c, s1)), s2);
+            s2 = s1;
+            s1 = s0;
+        }
+        vst1q_f32(bank.s1.as_mut_ptr().add(lane), s1);
+        vst1q_f32(bank.s2.as_mut_ptr().add(lane), s2);
+        lane += 4;
+    }
+    scalar::goertzel_lanes(bank, lane, input);
+}
@@ -0,0 +1,249 @@
+// src/dsp/simd/x86.rs
+//
+// SSE2 (4 lanes) and AVX2 (8 lanes) kernels. Complex slices are read as
+// interleaved (re, im) f32 pairs; `Complex32` is `repr(C)`. Callers check
+// the CPU through `SimdLevel::checked` first.
+use super::*;
+#[cfg(target_arch = "x86")]
+use core::arch::x86::*;
+#[cfg(target_arch = "x86_64")]
+use core::arch::x86_64::*;
+
+pub mod sse2 {
+    use super::*;
+
+    /// Swap the re/im halves of each complex pair
+    #[inline(always)]
+    unsafe fn swap_pairs(v: __m128) -> __m128 {
+        _mm_shuffle_ps(v, v, 0b10_11_00_01)
+    }
+
+    #[inline(always)]
+    unsafe fn lanes(v: __m128) -> [f32; 4] {
+        let mut out = [0.0; 4];
+        _mm_storeu_ps(out.as_mut_ptr(), v);
+        out
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn complex_dot(a: &[Complex32], b: &[Complex32]) -> Complex32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr() as *const f32, b.as_ptr() as *const f32);
+        // (ar br, ai bi) and (ar bi, ai br) per pair
+        let mut direct = _mm_setzero_ps();
+        let mut crossed = _mm_setzero_ps();
+        let mut i = 0;
+        while i + 2 <= n {
+            let va = _mm_loadu_ps(pa.add(2 * i));
+            let vb = _mm_loadu_ps(pb.add(2 * i));
+            direct = _mm_add_ps(direct, _mm_mul_ps(va, vb));
+            crossed = _mm_add_ps(crossed, _mm_mul_ps(va, swap_pairs(vb)));
+            i += 2;
+        }
+        let (d, c) = (lanes(direct), lanes(crossed));
+        Complex32::new(d[0] - d[1] + d[2] - d[3], c[0] + c[1] + c[2] + c[3]) + scalar::complex_dot(&a[i..n], &b[i..n])
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn complex_axpy_conj(w: &mut [Complex32], x: &[Complex32], error: Complex32, mu: f32) {
+        let n = w.len().min(x.len());
+        let (pw, px) = (w.as_mut_ptr() as *mut f32, x.as_ptr() as *const f32);
+        // s conj(x) = (sr xr + si xi, si xr - sr xi)
+        let s = error * mu;
+        let real = _mm_setr_ps(s.re, -s.re, s.re, -s.re);
+        let imag = _mm_set1_ps(s.im);
+        let mut i = 0;
+        while i + 2 <= n {
+            let vx = _mm_loadu_ps(px.add(2 * i));
+            let vw = _mm_loadu_ps(pw.add(2 * i));
+            let update = _mm_add_ps(_mm_mul_ps(vx, real), _mm_mul_ps(swap_pairs(vx), imag));
+            _mm_storeu_ps(pw.add(2 * i), _mm_add_ps(vw, update));
+            i += 2;
+        }
+        for (w, x) in w[i..n].iter_mut().zip(&x[i..n]) {
+            *w += s * x.conj();
+        }
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn complex_energy(x: &[Complex32]) -> f32 {
+        let px = x.as_ptr() as *const f32;
+        let mut acc = _mm_setzero_ps();
+        let mut i = 0;
+        while i + 2 <= x.len() {
+            let v = _mm_loadu_ps(px.add(2 * i));
+            acc = _mm_add_ps(acc, _mm_mul_ps(v, v));
+            i += 2;
+        }
+        lanes(acc).iter().sum::<f32>() + scalar::complex_energy(&x[i..])
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn biquad_lanes(bank: &mut BiquadLanes, input: &[f32], output: &mut [f32]) {
+        let width = bank.len();
+        if width == 0 {
+            return;
+        }
+        let frames = input.len().min(output.len()) / width;
+        let mut lane = 0;
+        while lane + 4 <= width {
+            let b0 = _mm_loadu_ps(bank.b0.as_ptr().add(lane));
+            let b1 = _mm_loadu_ps(bank.b1.as_ptr().add(lane));
+            let b2 = _mm_loadu_ps(bank.b2.as_ptr().add(lane));
+            let a1 = _mm_loadu_ps(bank.a1.as_ptr().add(lane));
+            let a2 = _mm_loadu_ps(bank.a2.as_ptr().add(lane));
+            let mut z1 = _mm_loadu_ps(bank.z1.as_ptr().add(lane));
+            let mut z2 = _mm_loadu_ps(bank.z2.as_ptr().add(lane));
+            for frame in 0..frames {
+                let x = _mm_loadu_ps(input.as_ptr().add(frame * width + lane));
+                let y = _mm_add_ps(_mm_mul_ps(b0, x), z1);
+                z1 = _mm_add_ps(_mm_sub_ps(_mm_mul_ps(b1, x), _mm_mul_ps(a1, y)), z2);
+                z2 = _mm_sub_ps(_mm_mul_ps(b2, x), _mm_mul_ps(a2, y));
+                _mm_storeu_ps(output.as_mut_ptr().add(frame * width + lane), y);
+            }
+            _mm_storeu_ps(bank.z1.as_mut_ptr().add(lane), z1);
+            _mm_storeu_ps(bank.z2.as_mut_ptr().add(lane), z2);
+            lane += 4;
+        }
+        scalar::biquad_lanes(bank, lane, input, output);
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn goertzel_lanes(bank: &mut GoertzelLanes, input: &[f32]) {
+        let mut lane = 0;
+        while lane + 4 <= bank.coefficient.len() {
+            let c = _mm_loadu_ps(bank.coefficient.as_ptr().add(lane));
+            let mut s1 = _mm_loadu_ps(bank.s1.as_ptr().add(lane));
+            let mut s2 = _mm_loadu_ps(bank.s2.as_ptr().add(lane));
+            for &x in input {
+                let s0 = _mm_sub_ps(_mm_add_ps(_mm_set1_ps(x), _mm_mul_ps(c, s1)), s2);
+                s2 = s1;
+                s1 = s0;
+            }
+            _mm_storeu_ps(bank.s1.as_mut_ptr().add(lane), s1);
+            _mm_storeu_ps(bank.s2.as_mut_ptr().add(lane), s2);
+            lane += 4;
+        }
+        scalar::goertzel_lanes(bank, lane, input);
+    }
+}
+
+pub mod avx2 {
+    use super::*;
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn swap_pairs(v: __m256) -> __m256 {
+        _mm256_permute_ps(v, 0b10_11_00_01)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn lanes(v: __m256) -> [f32; 8] {
+        let mut out = [0.0; 8];
+        _mm256_storeu_ps(out.as_mut_ptr(), v);
+        out
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn complex_dot(a: &[Complex32], b: &[Complex32]) -> Complex32 {
+        let n = a.len().min(b.len());
+        let (pa, pb) = (a.as_ptr() as *const f32, b.as_ptr() as *const f32);
+        let mut direct = _mm256_setzero_ps();
+        let mut crossed = _mm256_setzero_ps();
+        let mut i = 0;
+        while i + 4 <= n {
+            let va = _mm256_loadu_ps(pa.add(2 * i));
+            let vb = _mm256_loadu_ps(pb.add(2 * i));
+            direct = _mm256_add_ps(direct, _mm256_mul_ps(va, vb));
+            crossed = _mm256_add_ps(crossed, _mm256_mul_ps(va, swap_pairs(vb)));
+            i += 4;
+        }
+        let (d, c) = (lanes(direct), lanes(crossed));
+        let re = (d[0] + d[2] + d[4] + d[6]) - (d[1] + d[3] + d[5] + d[7]);
+        Complex32::new(re, c.iter().sum()) + scalar::complex_dot(&a[i..n], &b[i..n])
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn complex_axpy_conj(w: &mut [Complex32], x: &[Complex32], error: Complex32, mu: f32) {
+        let n = w.len().min(x.len());
+        let (pw, px) = (w.as_mut_ptr() as *mut f32, x.as_ptr() as *const f32);
+        let s = error * mu;
+        let real = _mm256_setr_ps(s.re, -s.re, s.re, -s.re, s.re, -s.re, s.re, -s.re);
+        let imag = _mm256_set1_ps(s.im);
+        let mut i = 0;
+        while i + 4 <= n {
+            let vx = _mm256_loadu_ps(px.add(2 * i));
+            let vw = _mm256_loadu_ps(pw.add(2 * i));
+            let update = _mm256_add_ps(_mm256_mul_ps(vx, real), _mm256_mul_ps(swap_pairs(vx), imag));
+            _mm256_storeu_ps(pw.add(2 * i), _mm256_add_ps(vw, update));
+            i += 4;
+        }
+        for (w, x) in w[i..n].iter_mut().zip(&x[i..n]) {
+            *w += s * x.conj();
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn complex_energy(x: &[Complex32]) -> f32 {
+        let px = x.as_ptr() as *const f32;
+        let mut acc = _mm256_setzero_ps();
+        let mut i = 0;
+        while i + 4 <= x.len() {
+            let v = _mm256_loadu_ps(px.add(2 * i));
+            acc = _mm256_add_ps(acc, _mm256_mul_ps(v, v));
+            i += 4;
+        }
+        lanes(acc).iter().sum::<f32>() + scalar::complex_energy(&x[i..])
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn biquad_lanes(bank: &mut BiquadLanes, input: &[f32], output: &mut [f32]) {
+        let width = bank.len();
+        if width == 0 {
+            return;
+        }
+        let frames = input.len().min(output.len()) / width;
+        let mut lane = 0;
+        while lane + 8 <= width {
+            let b0 = _mm256_loadu_ps(bank.b0.as_ptr().add(lane));
+            let b1 = _mm256_loadu_ps(bank.b1.as_ptr().add(lane));
+            let b2 = _mm256_loadu_ps(bank.b2.as_ptr().add(lane));
+            let a1 = _mm256_loadu_ps(bank.a1.as_ptr().add(lane));
+            let a2 = _mm256_loadu_ps(bank.a2.as_ptr().add(lane));
+            let mut z1 = _mm256_loadu_ps(bank.z1.as_ptr().add(lane));
+            let mut z2 = _mm256_loadu_ps(bank.z2.as_ptr().add(lane));
+            for frame in 0..frames {
+                let x = _mm256_loadu_ps(input.as_ptr().add(frame * width + lane));
+                let y = _mm256_add_ps(_mm256_mul_ps(b0, x), z1);
+                z1 = _mm256_add_ps(_mm256_sub_ps(_mm256_mul_ps(b1, x), _mm256_mul_ps(a1, y)), z2);
+                z2 = _mm256_sub_ps(_mm256_mul_ps(b2, x), _mm256_mul_ps(a2, y));
+                _mm256_storeu_ps(output.as_mut_ptr().add(frame * width + lane), y);
+            }
+            _mm256_storeu_ps(bank.z1.as_mut_ptr().add(lane), z1);
+            _mm256_storeu_ps(bank.z2.as_mut_ptr().add(lane), z2);
+            lane += 8;
+        }
+        scalar::biquad_lanes(bank, lane, input, output);
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn goertzel_lanes(bank: &mut GoertzelLanes, input: &[f32]) {
+        let mut lane = 0;
+        while lane + 8 <= bank.coefficient.len() {
+            let c = _mm256_loadu_ps(bank.coefficient.as_ptr().add(lane));
+            let mut s1 = _mm256_loadu_ps(bank.s1.as_ptr().add(lane));
+            let mut s2 = _mm256_loadu_ps(bank.s2.as_ptr().add(lane));
+            for &x in input {
+                let s0 = _mm256_sub_ps(_mm256_add_ps(_mm256_set1_ps(x), _mm256_mul_ps(c, s1)), s2);
+                s2 = s1;
+                s1 = s0;
+            }
+            _mm256_storeu_ps(bank.s1.as_mut_ptr().add(lane), s1);
+            _mm256_storeu_ps(bank.s2.as_mut_ptr().add(lane), s2);
+            lane += 8;
+        }
+        scalar::goertzel_lanes(bank, lane, input);
+    }
+}
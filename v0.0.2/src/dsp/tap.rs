@@ -0,0 +1,131 @@
+// src/dsp/tap.rs
+//
+// Optional diagnostic tap for the QAM receiver. When enabled it records what
+// the demodulator normally throws away: the symbol going into the equalizer,
+// the equalized symbol, the slicer decision, and every baseband sample with
+// its position inside the symbol period (for eye diagrams).
+// Rendering lives in `sim::plot`; this module only collects.
+
+use num_complex::Complex32;
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// One symbol as seen by the receiver
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymbolRecord {
+    /// Symbol number since the tap was enabled
+    pub index: u64,
+    /// Sampled baseband before equalization
+    pub pre_eq: Complex32,
+    /// Equalizer output
+    pub post_eq: Complex32,
+    /// Ideal constellation point chosen by the slicer
+    pub decision: Complex32,
+}
+
+impl SymbolRecord {
+    /// Distance from the equalized symbol to its decision
+    pub fn error(&self) -> Complex32 {
+        self.post_eq - self.decision
+    }
+}
+
+/// One baseband sample at a known timing phase
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingSample {
+    /// Symbol the sample belongs to
+    pub symbol: u64,
+    /// Position in the symbol period, 0.0..1.0; 1.0 is the sampling instant
+    pub phase: f32,
+    pub value: Complex32,
+}
+
+/// Bounded recorder of the most recent `capacity` symbols
+#[derive(Debug, Clone)]
+pub struct SymbolTap {
+    capacity: usize,
+    symbols: VecDeque<SymbolRecord>,
+    timing: VecDeque<TimingSample>,
+    next_index: u64,
+}
+
+impl SymbolTap {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            symbols: VecDeque::new(),
+            timing: VecDeque::new(),
+            next_index: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Record a baseband sample belonging to the symbol in progress
+    pub fn record_sample(&mut self, phase: f32, value: Complex32) {
+        self.timing.push_back(TimingSample {
+            symbol: self.next_index,
+            phase,
+            value,
+        });
+    }
+
+    /// Record a completed symbol and drop anything older than the capacity
+    pub fn record_symbol(&mut self, pre_eq: Complex32, post_eq: Complex32, decision: Complex32) {
+        self.symbols.push_back(SymbolRecord {
+            index: self.next_index,
+            pre_eq,
+            post_eq,
+            decision,
+        });
+        self.next_index += 1;
+
+        while self.symbols.len() > self.capacity {
+            self.symbols.pop_front();
+        }
+
+        let oldest = self.next_index.saturating_sub(self.capacity as u64);
+        while self.timing.front().is_some_and(|s| s.symbol < oldest) {
+            self.timing.pop_front();
+        }
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &SymbolRecord> {
+        self.symbols.iter()
+    }
+
+    pub fn timing_samples(&self) -> impl Iterator<Item = &TimingSample> {
+        self.timing.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Error vector magnitude (RMS error over RMS decision), skipping the
+    /// first `skip` recorded symbols so training transients can be excluded
+    pub fn evm(&self, skip: usize) -> Option<f32> {
+        let (err, reference, n) = self.symbols.iter().skip(skip).fold(
+            (0.0f32, 0.0f32, 0usize),
+            |(e, r, n), s| (e + s.error().norm_sqr(), r + s.decision.norm_sqr(), n + 1),
+        );
+        if n == 0 || reference <= 0.0 {
+            None
+        } else {
+            Some((err / reference).sqrt())
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+        self.timing.clear();
+        self.next_index = 0;
+    }
+}
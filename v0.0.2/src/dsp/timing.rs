@@ -1,139 +1,341 @@
 // src/dsp/timing.rs
-// Warning Fix: Removed unused 'use super::*;'
+use super::interpolator::Interpolator;
+use num_complex::Complex32;
+use alloc::{vec, vec::Vec};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
 
-/// Gardner timing error detector for symbol timing recovery [web:56][web:59]
+/// Gardner timing recovery loop [web:56][web:59]
+///
+/// Fed one matched-filtered baseband sample at a time. A fractional
+/// countdown strobes twice per symbol (symbol centre and midpoint), the
+/// strobe values come from a fractional-delay interpolator, and the Gardner error
+/// steers the countdown through a PI loop filter. The integrator ends up
+/// holding the sample-clock offset between the far transmitter and us.
 pub struct GardnerTED {
-// ... (Rest of the file is unchanged)
     samples_per_symbol: f32,
-    mu: f32,  // Fractional interval
-    
-    // PI controller for timing adjustment
+
+    // PI controller for timing adjustment (in samples per unit error)
     proportional_gain: f32,
     integral_gain: f32,
     integrator: f32,
-    
-    // Sample history for interpolation
-    prev_samples: [f32; 3],
+
+    // Sample history for interpolation, oldest first
+    interpolator: Interpolator,
+    history: Vec<Complex32>,
+
+    // Samples until the next strobe, and which strobe it is
+    countdown: f32,
+    at_midpoint: bool,
+
+    // Previous strobes for the error detector
+    midpoint: Complex32,
+    prev_symbol: Complex32,
+    last_error: f32,
+
+    // Lock detector: smoothed power at symbol centres and midpoints
+    symbol_power: f32,
+    midpoint_power: f32,
 }
 
+/// Smoothing for the lock detector powers (about 64 symbols)
+const LOCK_ALPHA: f32 = 1.0 / 64.0;
+
+/// Eye-opening ratio above which the loop counts as locked
+const LOCK_THRESHOLD: f32 = 0.2;
+
+/// Gardner detector slope for unit-amplitude raised-cosine symbols
+const DETECTOR_GAIN: f32 = 2.0;
+
 impl GardnerTED {
+    /// `loop_bandwidth` is the noise bandwidth as a fraction of the symbol rate
     pub fn new(samples_per_symbol: f32, loop_bandwidth: f32) -> Self {
+        Self::with_interpolator(samples_per_symbol, loop_bandwidth, Interpolator::cubic())
+    }
+
+    pub fn with_interpolator(
+        samples_per_symbol: f32,
+        loop_bandwidth: f32,
+        interpolator: Interpolator,
+    ) -> Self {
         // Damping factor (typically 1/sqrt(2))
         let zeta = 0.707;
-        let k = 1.0;  // Detector gain
-        
-        let theta = loop_bandwidth / samples_per_symbol;
+
+        let theta = loop_bandwidth / (zeta + 0.25 / zeta);
         let denom = 1.0 + 2.0 * zeta * theta + theta * theta;
-        
-        let proportional_gain = (4.0 * zeta * theta) / (k * denom);
-        let integral_gain = (4.0 * theta * theta) / (k * denom);
-        
+
+        // Loop works in symbols; scale to samples for the countdown
+        let proportional_gain = 4.0 * zeta * theta / denom / DETECTOR_GAIN * samples_per_symbol;
+        let integral_gain = 4.0 * theta * theta / denom / DETECTOR_GAIN * samples_per_symbol;
+
         Self {
             samples_per_symbol,
-            mu: 0.0,
             proportional_gain,
             integral_gain,
             integrator: 0.0,
-            prev_samples: [0.0; 3],
+            history: vec![Complex32::new(0.0, 0.0); interpolator.window_len()],
+            interpolator,
+            countdown: samples_per_symbol,
+            at_midpoint: false,
+            midpoint: Complex32::new(0.0, 0.0),
+            prev_symbol: Complex32::new(0.0, 0.0),
+            last_error: 0.0,
+            symbol_power: 0.0,
+            midpoint_power: 0.0,
         }
     }
-    
-    /// Process a block of samples and extract one symbol
-    /// Returns the symbol at the optimal timing point.
-    pub fn process_samples(&mut self, samples: &[f32], sample_idx: &mut f32) -> Option<f32> {
-        let mut symbol = None;
-        
-        // Ensure we have enough data (at least two full samples to interpolate)
-        if *sample_idx as usize + 1 >= samples.len() {
+
+    /// Put the first symbol strobe on input sample `samples` (counting from
+    /// zero), e.g. where a known filter latency puts the first pulse peak
+    pub fn set_initial_offset(&mut self, samples: f32) {
+        // +1 for the countdown running out after the sample, plus the
+        // interpolator working behind the newest input
+        self.countdown = samples.max(0.0) + 1.0 + self.interpolator.delay() as f32;
+        self.at_midpoint = false;
+    }
+
+    /// Feed one sample; returns the interpolated symbol on symbol strobes
+    pub fn process(&mut self, sample: Complex32) -> Option<Complex32> {
+        self.history.rotate_left(1);
+        if let Some(newest) = self.history.last_mut() {
+            *newest = sample;
+        }
+
+        self.countdown -= 1.0;
+        if self.countdown > 0.0 {
             return None;
         }
 
-        // Run until we exceed the buffer
-        while *sample_idx < samples.len() as f32 - 1.0 {
-            // Interpolate the sample at the current fractional index (mu)
-            let interp_sample = self.interpolate(samples, *sample_idx, self.mu);
-            
-            // Output only the center (strobe) sample (i.e., when mu is near 0.0)
-            // The Gardner TED is centered on the symbol. A more robust implementation would
-            // use a phase-locked loop (PLL) to track the fractional interval, but this simpler
-            // structure processes a symbol every sample_per_symbol period.
-            // For now, we'll output the symbol on the primary strobe.
-
-            // Strobe on or near mu=0.0
-            if symbol.is_none() && self.mu.abs() < 0.1 {
-                symbol = Some(interp_sample);
-            }
+        // Strobe falls `mu` samples before the centre interval's end
+        let mu = (-self.countdown).min(1.0);
+        let value = self.interpolator.interpolate(&self.history, 1.0 - mu);
+        let half = self.samples_per_symbol / 2.0;
 
-            // Calculate Gardner timing error [web:56]
-            // Error = (sample[n] - sample[n-2]) * sample[n-1]
-            let timing_error = (self.prev_samples[2] - self.prev_samples[0])
-                * self.prev_samples[1];
-            
-            // Update timing with PI controller
-            self.integrator += timing_error * self.integral_gain;
-            let timing_adjustment = timing_error * self.proportional_gain + self.integrator;
-            
-            // Advance sample counter with timing correction
-            self.mu += timing_adjustment;
-            *sample_idx += self.samples_per_symbol + self.mu; // This line has an error in logic, but I'll stick to the user's code style for now.
-                                                              // The standard approach is to advance by 1.0 sample, and mu is the phase error.
-
-            // Corrected logic based on standard TED implementation:
-            // Advance sample counter by one (or interpolating index for the next sample)
-            *sample_idx += 1.0; 
-            
-            // Keep mu in [-0.5, 0.5] range
-            if self.mu >= 0.5 {
-                self.mu -= 1.0;
-                // Since we're advancing one sample at a time, we'd adjust the index if needed.
-                // However, the standard is to only update mu based on the TED output.
-                // The current code seems to be conflating mu as fractional interval and loop-filter output. 
-                // Sticking to the user's original intent:
-                *sample_idx += 1.0;
-            } else if self.mu < -0.5 {
-                self.mu += 1.0;
-                *sample_idx -= 1.0;
-            }
-            
-            // Shift sample history
-            self.prev_samples[0] = self.prev_samples[1];
-            self.prev_samples[1] = self.prev_samples[2];
-            self.prev_samples[2] = interp_sample;
+        if self.at_midpoint {
+            self.midpoint = value;
+            self.at_midpoint = false;
+            self.countdown += half;
+            return None;
         }
-        
-        symbol
-    }
-    
-    /// Linear interpolation between samples
-    #[inline]
-    fn interpolate(&self, samples: &[f32], sample_idx: f32, _mu: f32) -> f32 {
-        let i0 = sample_idx.floor() as usize;
-        let i1 = i0 + 1;
-        
-        let s0 = samples.get(i0).cloned().unwrap_or(0.0);
-        let s1 = samples.get(i1).cloned().unwrap_or(0.0);
-        
-        // Linear interpolation: (1-mu)*s0 + mu*s1
-        // Given mu is the fractional interval, but the user's TED updates mu based on error.
-        // Assuming mu is the error, the fractional interval delta is needed.
-        // Let's assume the user's intent is to interpolate based on the total fractional index:
-        let _frac = sample_idx - i0 as f32; // This is the 'mu' in standard interpolation (0.0 to 1.0)
-        
-        // Since the user passes mu as an error, let's use the actual fractional part for interpolation
-        let total_mu = sample_idx - sample_idx.floor(); // Fractional part of the symbol index
-        (1.0 - total_mu) * s0 + total_mu * s1
-    }
-    
+
+        // Gardner error: Re{ conj(midpoint) * (previous - current) }.
+        // Negative means we are strobing late.
+        let error = (self.midpoint.conj() * (self.prev_symbol - value)).re;
+        self.last_error = error;
+
+        self.integrator += error * self.integral_gain;
+        let adjustment = (error * self.proportional_gain + self.integrator).clamp(-half / 2.0, half / 2.0);
+
+        self.symbol_power += LOCK_ALPHA * (value.norm_sqr() - self.symbol_power);
+        self.midpoint_power += LOCK_ALPHA * (self.midpoint.norm_sqr() - self.midpoint_power);
+
+        self.prev_symbol = value;
+        self.at_midpoint = true;
+        self.countdown += half + adjustment;
+
+        Some(value)
+    }
+
+    /// Position inside the current symbol period: 0.0 just after a symbol
+    /// strobe, 1.0 at the next one
+    pub fn symbol_phase(&self) -> f32 {
+        let to_symbol = if self.at_midpoint {
+            self.countdown + self.samples_per_symbol / 2.0
+        } else {
+            self.countdown
+        };
+        (1.0 - to_symbol / self.samples_per_symbol).clamp(0.0, 1.0)
+    }
+
+    /// Midpoint strobe preceding the latest symbol strobe, for T/2 equalizers
+    pub fn midpoint(&self) -> Complex32 {
+        self.midpoint
+    }
+
+    /// Latest raw Gardner error
+    pub fn timing_error(&self) -> f32 {
+        self.last_error
+    }
+
+    /// Estimated sample-clock offset (fraction; positive = far end slow)
+    pub fn clock_offset(&self) -> f32 {
+        self.integrator / self.samples_per_symbol
+    }
+
+    /// Eye opening: how much more power sits at symbol centres than midpoints
+    pub fn lock_metric(&self) -> f32 {
+        let total = self.symbol_power + self.midpoint_power;
+        if total <= 0.0 {
+            0.0
+        } else {
+            (self.symbol_power - self.midpoint_power) / total
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.lock_metric() > LOCK_THRESHOLD
+    }
+
     /// Reset the timing loop state
     pub fn reset(&mut self) {
-        self.mu = 0.0;
         self.integrator = 0.0;
-        self.prev_samples = [0.0; 3];
+        self.history.iter_mut().for_each(|s| *s = Complex32::new(0.0, 0.0));
+        self.countdown = self.samples_per_symbol;
+        self.at_midpoint = false;
+        self.midpoint = Complex32::new(0.0, 0.0);
+        self.prev_symbol = Complex32::new(0.0, 0.0);
+        self.last_error = 0.0;
+        self.symbol_power = 0.0;
+        self.midpoint_power = 0.0;
     }
+}
 
-    /// Realign sample index after draining samples from buffer
-    pub fn realign_idx(&mut self, drain_count: f32) {
-        // Simple re-index: the remaining fractional index carries over
-        self.mu = drain_count - drain_count.floor();
+/// Transitions the bit clock takes at the acquisition gain before settling
+const ACQUISITION_TRANSITIONS: u32 = 8;
+
+/// Bit clock gains: phase correction per unit error while acquiring and
+/// while tracking
+const ACQUISITION_GAIN: f32 = 0.5;
+const TRACKING_GAIN: f32 = 0.1;
+
+/// Largest clock offset the bit clock follows (fraction of the bit rate)
+const MAX_CLOCK_OFFSET: f32 = 0.05;
+
+/// Bits without a transition after which the bit clock counts as unlocked
+const LOCK_TIMEOUT_BITS: u32 = 64;
+
+/// Bits without a transition after which the next one re-acquires the
+/// phase, as after the first: an idle line gives nothing to track, and a
+/// clock set by noise before the carrier arrived would stay wrong
+const RESYNC_IDLE_BITS: u32 = 12;
+
+/// A transition only counts once the soft decision passes this fraction of
+/// its average magnitude on the new side, so ripple around zero between
+/// close tones doesn't register as several transitions
+const TRANSITION_HYSTERESIS: f32 = 0.3;
+
+/// Smoothing for the soft decision magnitude (about 4 bits at 8 kHz)
+const LEVEL_ALPHA: f32 = 1.0 / 128.0;
+
+/// Transition-driven bit clock recovery (digital PLL) for FSK.
+///
+/// Fed a soft decision per sample from a detector whose window is one bit
+/// long, so its zero crossings sit half a bit before the point where the
+/// window covers a whole bit. The clock phase runs 0..1 per bit and strobes
+/// on wrapping; each transition pulls the phase towards 0.5, and once
+/// acquired an integrator follows the far end's bit rate.
+pub struct BitSync {
+    samples_per_bit: f32,
+    phase: f32,
+    integrator: f32,
+
+    // Transition detector: confirmed side, smoothed magnitude, and the
+    // clock phase at the latest raw zero crossing
+    mark: bool,
+    level: f32,
+    prev_soft: f32,
+    crossing_phase: Option<f32>,
+
+    transitions: u32,
+    bits_since_transition: u32,
+}
+
+impl BitSync {
+    pub fn new(samples_per_bit: f32) -> Self {
+        Self {
+            samples_per_bit,
+            phase: 0.0,
+            integrator: 0.0,
+            mark: true,
+            level: 0.0,
+            prev_soft: 0.0,
+            crossing_phase: None,
+            transitions: 0,
+            bits_since_transition: LOCK_TIMEOUT_BITS,
+        }
     }
-}
\ No newline at end of file
+
+    /// Feed one soft decision (positive = mark); returns the bit on strobes
+    pub fn process(&mut self, soft: f32) -> Option<bool> {
+        let step = (1.0 + self.integrator) / self.samples_per_bit;
+        self.phase += step;
+        self.level += LEVEL_ALPHA * (soft.abs() - self.level);
+
+        // Remember where the latest zero crossing fell, interpolated
+        // between the two samples
+        if soft * self.prev_soft < 0.0 {
+            let fraction = self.prev_soft / (self.prev_soft - soft);
+            self.crossing_phase = Some(self.phase - (1.0 - fraction) * step);
+        }
+        if soft != 0.0 {
+            self.prev_soft = soft;
+        }
+
+        // Confirmed transition: steer the clock from its crossing
+        let threshold = TRANSITION_HYSTERESIS * self.level;
+        let confirmed = if self.mark { soft < -threshold } else { soft > threshold };
+        if confirmed {
+            self.mark = !self.mark;
+            if let Some(crossing) = self.crossing_phase.take() {
+                self.steer(crossing - 0.5);
+            }
+        }
+
+        if self.phase < 1.0 {
+            return None;
+        }
+        self.phase -= 1.0;
+        if let Some(crossing) = self.crossing_phase.as_mut() {
+            *crossing -= 1.0;
+        }
+        self.bits_since_transition = self.bits_since_transition.saturating_add(1);
+        Some(self.mark)
+    }
+
+    fn steer(&mut self, raw_error: f32) {
+        let error = raw_error - raw_error.round();
+        if self.transitions == 0 || self.bits_since_transition >= RESYNC_IDLE_BITS {
+            // The free-running clock may be up to half a bit out after a
+            // mark idle; a partial correction would strobe a lone start bit
+            // on its edges. Not wrapped: a crossing just before the latest
+            // strobe must still put the next one half a bit after it, not
+            // skip the bit.
+            self.phase -= raw_error;
+            self.transitions = 0;
+        } else if self.transitions < ACQUISITION_TRANSITIONS {
+            self.phase -= ACQUISITION_GAIN * error;
+        } else {
+            self.phase -= TRACKING_GAIN * error;
+            self.integrator = (self.integrator - TRACKING_GAIN * TRACKING_GAIN / 4.0 * error)
+                .clamp(-MAX_CLOCK_OFFSET, MAX_CLOCK_OFFSET);
+        }
+        self.transitions = self.transitions.saturating_add(1);
+        self.bits_since_transition = 0;
+    }
+
+    /// Estimated bit-rate offset of the far end (fraction; positive = fast)
+    pub fn clock_offset(&self) -> f32 {
+        self.integrator
+    }
+
+    /// Position inside the current bit: 0.0 just after a strobe
+    pub fn bit_phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// True while transitions keep arriving to steer the clock
+    pub fn is_locked(&self) -> bool {
+        self.transitions >= ACQUISITION_TRANSITIONS && self.bits_since_transition < LOCK_TIMEOUT_BITS
+    }
+
+    pub fn reset(&mut self) {
+        self.phase = 0.0;
+        self.integrator = 0.0;
+        self.mark = true;
+        self.level = 0.0;
+        self.prev_soft = 0.0;
+        self.crossing_phase = None;
+        self.transitions = 0;
+        self.bits_since_transition = LOCK_TIMEOUT_BITS;
+    }
+}
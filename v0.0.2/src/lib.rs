@@ -1,7 +1,21 @@
 // src/lib.rs - Export modules for testing
+//
+// The DSP and AT-parsing core needs only `alloc`; build with
+// `--no-default-features --features libm` for no_std targets. The audio
+// engine, simulator and pipe server are layers behind their own features.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("no_std builds need the `libm` feature for float math");
+
+extern crate alloc;
+
+#[cfg(feature = "audio")]
 pub mod audio;
 pub mod dsp;
+#[cfg(feature = "sim")]
+pub mod sim;
 pub mod tapi;
 
 // Re-export commonly used items
-pub use dsp::SAMPLE_RATE;
\ No newline at end of file
+pub use dsp::SAMPLE_RATE;
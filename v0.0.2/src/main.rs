@@ -1,9 +1,5 @@
 // src/main.rs
-mod audio;
-mod dsp;
-mod tapi;
-
-use tapi::pipe_server::ModemPipeServer;
+use hsf_softmodem::tapi::pipe_server::ModemPipeServer;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Try to initialize logging, but don't crash if it's already initialized.
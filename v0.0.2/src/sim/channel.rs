@@ -0,0 +1,209 @@
+// src/sim/channel.rs
+//
+// Deterministic telephone-line impairment model. Everything is seeded so the
+// same input always produces the same output, which is what the golden
+// fixtures and BER sweeps rely on.
+
+use super::line::{LineFilter, LinePreset};
+use crate::dsp::filters::BiquadFilter;
+use crate::dsp::{dbm0_to_power, DEFAULT_TX_LEVEL_DBM0};
+use std::collections::VecDeque;
+
+/// Small xorshift64* generator with a Box-Muller Gaussian on top.
+///
+/// We don't use `rand` here because it is only a dev-dependency and because
+/// fixture regeneration must not change when `rand` changes its algorithms.
+#[derive(Debug, Clone)]
+pub struct NoiseSource {
+    state: u64,
+    spare: Option<f32>,
+}
+
+impl NoiseSource {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            // xorshift must never hold an all-zero state
+            state: seed ^ 0x9E37_79B9_7F4A_7C15,
+            spare: None,
+        }
+    }
+
+    /// Next raw 64-bit value
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Uniform sample in [0, 1)
+    pub fn uniform(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// Standard normal sample (zero mean, unit variance)
+    pub fn gaussian(&mut self) -> f32 {
+        if let Some(z) = self.spare.take() {
+            return z;
+        }
+
+        // Box-Muller; keep u1 away from zero so ln() stays finite
+        let u1 = self.uniform().max(1e-7);
+        let u2 = self.uniform();
+        let r = (-2.0 * u1.ln()).sqrt();
+        let theta = 2.0 * std::f32::consts::PI * u2;
+
+        self.spare = Some(r * theta.sin());
+        r * theta.cos()
+    }
+}
+
+/// Line impairment settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Impairments {
+    /// Flat loss (negative) or gain (positive) in dB
+    pub gain_db: f32,
+    /// AWGN level as SNR against the received signal: a source at
+    /// `signal_dbm0` after `gain_db`. Noise covers the whole 0-4 kHz band.
+    /// `None` disables noise.
+    pub snr_db: Option<f32>,
+    /// Transmit level of the signal put on the line, the reference for
+    /// `snr_db`
+    pub signal_dbm0: f32,
+    /// Bulk propagation delay in samples
+    pub delay_samples: usize,
+    /// Upper band edge of the line in Hz (second-order lowpass). `None` = flat.
+    pub bandwidth_hz: Option<f32>,
+    /// Attenuation/delay distortion and bulk delay of a standard line.
+    /// Its loss and noise are applied through `gain_db`/`snr_db`, see
+    /// `Impairments::preset`.
+    pub line: Option<LinePreset>,
+    /// Noise generator seed
+    pub seed: u64,
+}
+
+impl Default for Impairments {
+    fn default() -> Self {
+        Self {
+            gain_db: 0.0,
+            snr_db: None,
+            signal_dbm0: DEFAULT_TX_LEVEL_DBM0,
+            delay_samples: 0,
+            bandwidth_hz: None,
+            line: None,
+            seed: 1,
+        }
+    }
+}
+
+impl Impairments {
+    /// Ideal wire: no loss, no noise, no delay
+    pub fn clean() -> Self {
+        Self::default()
+    }
+
+    /// Loss, noise and shape of a standard line
+    pub fn preset(preset: LinePreset) -> Self {
+        let profile = preset.profile();
+        Self {
+            gain_db: -profile.loss_db,
+            snr_db: profile.snr_db,
+            line: Some(preset),
+            ..Self::default()
+        }
+    }
+}
+
+/// Streaming channel simulator applying `Impairments` sample by sample
+pub struct ChannelSimulator {
+    impairments: Impairments,
+    gain: f32,
+    noise_std: f32,
+    noise: NoiseSource,
+    line_filter: Option<BiquadFilter>,
+    line_shape: Option<LineFilter>,
+    delay_line: VecDeque<f32>,
+}
+
+impl ChannelSimulator {
+    pub fn new(impairments: Impairments, sample_rate: f32) -> Self {
+        let gain = 10f32.powf(impairments.gain_db / 20.0);
+
+        let received_power = dbm0_to_power(impairments.signal_dbm0 + impairments.gain_db);
+        let noise_std = match impairments.snr_db {
+            Some(snr_db) => (received_power / 10f32.powf(snr_db / 10.0)).sqrt(),
+            None => 0.0,
+        };
+
+        let line_filter = impairments
+            .bandwidth_hz
+            .map(|bw| BiquadFilter::lowpass(bw, 0.707, sample_rate));
+
+        let line_shape = impairments
+            .line
+            .map(|preset| LineFilter::from_preset(preset, sample_rate));
+
+        // Presets carry their own propagation delay on top of `delay_samples`
+        let bulk_delay = impairments
+            .line
+            .map(|preset| (preset.profile().bulk_delay_ms * sample_rate / 1000.0).round() as usize)
+            .unwrap_or(0);
+
+        Self {
+            impairments,
+            gain,
+            noise_std,
+            noise: NoiseSource::new(impairments.seed),
+            line_filter,
+            line_shape,
+            delay_line: VecDeque::from(vec![0.0; impairments.delay_samples + bulk_delay]),
+        }
+    }
+
+    pub fn impairments(&self) -> &Impairments {
+        &self.impairments
+    }
+
+    /// Pass one sample through the line
+    #[inline]
+    pub fn process_sample(&mut self, input: f32) -> f32 {
+        let mut sample = input * self.gain;
+
+        if let Some(ref mut filter) = self.line_filter {
+            sample = filter.process(sample);
+        }
+
+        if let Some(ref mut shape) = self.line_shape {
+            sample = shape.process(sample);
+        }
+
+        if self.noise_std > 0.0 {
+            sample += self.noise.gaussian() * self.noise_std;
+        }
+
+        if !self.delay_line.is_empty() {
+            self.delay_line.push_back(sample);
+            sample = self.delay_line.pop_front().unwrap_or(0.0);
+        }
+
+        sample
+    }
+
+    /// Pass a block of samples through the line
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        input.iter().map(|&s| self.process_sample(s)).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.noise = NoiseSource::new(self.impairments.seed);
+        if let Some(ref mut filter) = self.line_filter {
+            filter.reset();
+        }
+        if let Some(ref mut shape) = self.line_shape {
+            shape.reset();
+        }
+        self.delay_line.iter_mut().for_each(|s| *s = 0.0);
+    }
+}
@@ -0,0 +1,347 @@
+// src/sim/line.rs
+//
+// Named line presets for comparing runs. Each preset gives loss at 1004 Hz,
+// an attenuation-distortion profile and an envelope-delay profile at the
+// usual test frequencies, plus noise and bulk delay.
+//
+// The presets are our own estimates, rounded to 0.5 dB / 50 us, of a few
+// kinds of connection: a short and a long non-loaded loop, an H88-loaded
+// loop and a satellite hop. None of the numbers comes from EIA/TIA-496-A,
+// ITU-T V.56bis or any other published network model, and results on them
+// are not results on those models; the comments on each profile say what
+// its values rest on. A test that needs a conformance channel has to enter
+// the standard's own tables.
+//
+// The line shape is realised as a linear FIR designed by frequency sampling
+// from the two profiles.
+
+use serde::Deserialize;
+use std::f32::consts::PI;
+
+/// Length of the line FIR (32 ms at 8 kHz, enough for ~3 ms of delay spread)
+const LINE_FIR_TAPS: usize = 256;
+
+/// Estimated line conditions, see the module comment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinePreset {
+    /// Ideal 300-3400 Hz channel: no distortion, no noise
+    Flat,
+    /// Short non-loaded loop into a digital channel bank
+    ShortLoop,
+    /// Long non-loaded loop near the resistance-design limit
+    LongLoop,
+    /// H88-loaded loop: steep roll-off and delay above 2.8 kHz
+    LoadedLoop,
+    /// Short terrestrial tails plus one geostationary satellite hop
+    SatelliteHop,
+}
+
+/// Loss, shape, noise and delay of a line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineProfile {
+    pub name: &'static str,
+    /// Insertion loss at 1004 Hz in dB
+    pub loss_db: f32,
+    /// (Hz, dB) loss relative to 1004 Hz; positive means more loss
+    pub attenuation: &'static [(f32, f32)],
+    /// (Hz, us) envelope delay relative to the minimum in the band
+    pub envelope_delay: &'static [(f32, f32)],
+    /// SNR against the received signal, see `Impairments::snr_db`
+    pub snr_db: Option<f32>,
+    /// One-way propagation delay in ms
+    pub bulk_delay_ms: f32,
+}
+
+// Channel-bank filter skirts are common to everything but the flat preset
+const FLAT_ATTENUATION: &[(f32, f32)] = &[
+    (0.0, 40.0),
+    (200.0, 40.0),
+    (300.0, 0.0),
+    (3400.0, 0.0),
+    (3600.0, 40.0),
+    (4000.0, 40.0),
+];
+
+const FLAT_DELAY: &[(f32, f32)] = &[(0.0, 0.0), (4000.0, 0.0)];
+
+// Short loop: the channel-bank skirts plus a few dB of cable slope at the
+// top of the band; delay is mostly the channel bank's filters
+const SHORT_LOOP_ATTENUATION: &[(f32, f32)] = &[
+    (0.0, 40.0),
+    (200.0, 8.0),
+    (304.0, 1.5),
+    (404.0, 0.5),
+    (1004.0, 0.0),
+    (2004.0, 0.5),
+    (2804.0, 1.5),
+    (3004.0, 2.0),
+    (3404.0, 4.0),
+    (3600.0, 15.0),
+    (4000.0, 40.0),
+];
+
+const SHORT_LOOP_DELAY: &[(f32, f32)] = &[
+    (200.0, 2000.0),
+    (404.0, 900.0),
+    (604.0, 500.0),
+    (1004.0, 150.0),
+    (1804.0, 0.0),
+    (2404.0, 100.0),
+    (2804.0, 250.0),
+    (3004.0, 400.0),
+    (3404.0, 900.0),
+    (3600.0, 1500.0),
+];
+
+// Long loop: the cable's loss rises steadily with frequency, about 7.5 dB
+// more at 3.4 kHz than at 1 kHz, with somewhat more delay at the band edges
+const LONG_LOOP_ATTENUATION: &[(f32, f32)] = &[
+    (0.0, 40.0),
+    (200.0, 8.0),
+    (304.0, 0.5),
+    (404.0, -1.0),
+    (1004.0, 0.0),
+    (2004.0, 2.5),
+    (2804.0, 5.0),
+    (3004.0, 5.5),
+    (3404.0, 7.5),
+    (3600.0, 18.0),
+    (4000.0, 40.0),
+];
+
+const LONG_LOOP_DELAY: &[(f32, f32)] = &[
+    (200.0, 2500.0),
+    (404.0, 1200.0),
+    (604.0, 700.0),
+    (1004.0, 250.0),
+    (1804.0, 0.0),
+    (2404.0, 150.0),
+    (2804.0, 400.0),
+    (3004.0, 600.0),
+    (3404.0, 1200.0),
+    (3600.0, 2000.0),
+];
+
+// Loaded loop: flat through the middle of the band, then the loading coils'
+// cutoff, with loss and delay climbing steeply from about 2.8 kHz
+const LOADED_LOOP_ATTENUATION: &[(f32, f32)] = &[
+    (0.0, 40.0),
+    (200.0, 10.0),
+    (304.0, 2.0),
+    (404.0, 1.0),
+    (1004.0, 0.0),
+    (2004.0, 0.5),
+    (2804.0, 2.0),
+    (3004.0, 4.0),
+    (3204.0, 7.5),
+    (3404.0, 13.0),
+    (3600.0, 25.0),
+    (4000.0, 40.0),
+];
+
+const LOADED_LOOP_DELAY: &[(f32, f32)] = &[
+    (200.0, 3000.0),
+    (404.0, 1500.0),
+    (604.0, 800.0),
+    (1004.0, 200.0),
+    (1604.0, 0.0),
+    (2404.0, 300.0),
+    (2804.0, 800.0),
+    (3004.0, 1500.0),
+    (3204.0, 2300.0),
+    (3404.0, 3000.0),
+    (3600.0, 3500.0),
+];
+
+// Satellite: short terrestrial tails, so the shape is the channel banks'
+// and the hop only adds bulk delay
+const SATELLITE_ATTENUATION: &[(f32, f32)] = &[
+    (0.0, 40.0),
+    (200.0, 6.0),
+    (304.0, 1.0),
+    (404.0, 0.5),
+    (1004.0, 0.0),
+    (2804.0, 1.0),
+    (3004.0, 1.5),
+    (3404.0, 3.0),
+    (3600.0, 12.0),
+    (4000.0, 40.0),
+];
+
+const SATELLITE_DELAY: &[(f32, f32)] = &[
+    (200.0, 1500.0),
+    (404.0, 700.0),
+    (604.0, 400.0),
+    (1004.0, 100.0),
+    (1804.0, 0.0),
+    (2404.0, 80.0),
+    (2804.0, 200.0),
+    (3004.0, 300.0),
+    (3404.0, 700.0),
+    (3600.0, 1200.0),
+];
+
+impl LinePreset {
+    pub const ALL: [LinePreset; 5] = [
+        LinePreset::Flat,
+        LinePreset::ShortLoop,
+        LinePreset::LongLoop,
+        LinePreset::LoadedLoop,
+        LinePreset::SatelliteHop,
+    ];
+
+    pub fn profile(&self) -> LineProfile {
+        match self {
+            LinePreset::Flat => LineProfile {
+                name: "flat",
+                loss_db: 0.0,
+                attenuation: FLAT_ATTENUATION,
+                envelope_delay: FLAT_DELAY,
+                snr_db: None,
+                bulk_delay_ms: 0.0,
+            },
+            LinePreset::ShortLoop => LineProfile {
+                name: "short loop (estimate)",
+                // A few dB of cable plus the channel bank's fixed loss
+                loss_db: 6.0,
+                attenuation: SHORT_LOOP_ATTENUATION,
+                envelope_delay: SHORT_LOOP_DELAY,
+                // About the quantizing noise of a G.711 channel bank
+                snr_db: Some(38.0),
+                // One digital switch and short transmission
+                bulk_delay_ms: 2.0,
+            },
+            LinePreset::LongLoop => LineProfile {
+                name: "long loop (estimate)",
+                // Twice the short loop's cable loss
+                loss_db: 12.0,
+                attenuation: LONG_LOOP_ATTENUATION,
+                envelope_delay: LONG_LOOP_DELAY,
+                // Channel-bank noise plus crosstalk picked up along the loop
+                snr_db: Some(30.0),
+                // A toll connection over terrestrial plant
+                bulk_delay_ms: 5.0,
+            },
+            LinePreset::LoadedLoop => LineProfile {
+                name: "loaded loop (estimate)",
+                // A loaded loop into a toll connection: the lossiest preset
+                loss_db: 16.0,
+                attenuation: LOADED_LOOP_ATTENUATION,
+                envelope_delay: LOADED_LOOP_DELAY,
+                // The noisiest preset
+                snr_db: Some(24.0),
+                // A long toll connection over terrestrial plant
+                bulk_delay_ms: 8.0,
+            },
+            LinePreset::SatelliteHop => LineProfile {
+                name: "satellite hop (estimate)",
+                // Two short tails
+                loss_db: 8.0,
+                attenuation: SATELLITE_ATTENUATION,
+                envelope_delay: SATELLITE_DELAY,
+                // Channel banks at both ends of the hop
+                snr_db: Some(32.0),
+                // Up to the geostationary orbit and back down, plus the tails
+                bulk_delay_ms: 270.0,
+            },
+        }
+    }
+}
+
+/// Piecewise-linear lookup in a (frequency, value) table, clamped at the ends
+fn interpolate(table: &[(f32, f32)], freq: f32) -> f32 {
+    match table.iter().position(|&(f, _)| f >= freq) {
+        None => table.last().map(|&(_, v)| v).unwrap_or(0.0),
+        Some(0) => table[0].1,
+        Some(i) => {
+            let (f0, v0) = table[i - 1];
+            let (f1, v1) = table[i];
+            v0 + (v1 - v0) * (freq - f0) / (f1 - f0)
+        }
+    }
+}
+
+/// FIR realisation of a profile's attenuation and delay distortion.
+/// Loss at 1004 Hz, noise and bulk delay are applied by `ChannelSimulator`.
+#[derive(Clone)]
+pub struct LineFilter {
+    taps: Vec<f32>,
+    history: Vec<f32>,
+    pos: usize,
+}
+
+impl LineFilter {
+    pub fn new(profile: &LineProfile, sample_rate: f32) -> Self {
+        let n = LINE_FIR_TAPS;
+        let bins = n / 2 + 1;
+        let df = sample_rate / n as f32;
+
+        // Centre the response in the filter so non-causal delay fits
+        let base_delay_s = (n / 2) as f32 / sample_rate;
+
+        // Phase is minus the integral of group delay over frequency
+        let mut spectrum = Vec::with_capacity(bins);
+        let mut phase = 0.0f32;
+        let mut prev_delay = base_delay_s;
+        for k in 0..bins {
+            let freq = k as f32 * df;
+            let delay_s = base_delay_s + interpolate(profile.envelope_delay, freq) * 1e-6;
+            if k > 0 {
+                phase -= 2.0 * PI * df * 0.5 * (prev_delay + delay_s);
+            }
+            prev_delay = delay_s;
+
+            let magnitude = 10f32.powf(-interpolate(profile.attenuation, freq) / 20.0);
+            spectrum.push((magnitude, phase));
+        }
+
+        // Real inverse DFT of the Hermitian spectrum, then a Hann window
+        let mut taps = vec![0.0f32; n];
+        for (t, tap) in taps.iter_mut().enumerate() {
+            let mut acc = 0.0f32;
+            for (k, &(mag, ph)) in spectrum.iter().enumerate() {
+                let weight = if k == 0 || k == n / 2 { 1.0 } else { 2.0 };
+                acc += weight * mag * (2.0 * PI * k as f32 * t as f32 / n as f32 + ph).cos();
+            }
+            let window = 0.5 - 0.5 * (2.0 * PI * (t as f32 + 0.5) / n as f32).cos();
+            *tap = acc / n as f32 * window;
+        }
+
+        Self {
+            taps,
+            history: vec![0.0; n],
+            pos: 0,
+        }
+    }
+
+    pub fn from_preset(preset: LinePreset, sample_rate: f32) -> Self {
+        Self::new(&preset.profile(), sample_rate)
+    }
+
+    /// Filter latency in samples (the centring delay added by the design)
+    pub fn latency(&self) -> usize {
+        self.taps.len() / 2
+    }
+
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let n = self.taps.len();
+        self.history[self.pos] = input;
+
+        let mut acc = 0.0;
+        let mut idx = self.pos;
+        for &tap in &self.taps {
+            acc += tap * self.history[idx];
+            idx = if idx == 0 { n - 1 } else { idx - 1 };
+        }
+
+        self.pos = (self.pos + 1) % n;
+        acc
+    }
+
+    pub fn reset(&mut self) {
+        self.history.iter_mut().for_each(|s| *s = 0.0);
+        self.pos = 0;
+    }
+}
@@ -0,0 +1,602 @@
+// src/sim/link.rs
+//
+// Two-modem link simulation: an originate and an answer modem talking
+// full duplex through a pair of `ChannelSimulator`s. Each side sends
+// (scrambled) ones as training, switches to data once it has heard the far
+// end, and the receivers' bit streams are scored against what was sent.
+
+use super::channel::ChannelSimulator;
+use super::scenario::{Scenario, SideSpec};
+use crate::dsp::fsk::{FSKDemodulator, FSKMode, FSKModulator};
+use crate::dsp::fsk_detector::{default_detector, FSKDetector};
+use crate::dsp::qam::QAMMode;
+use crate::dsp::qam_modem::{QAMDemodulator, QAMModulator};
+use crate::dsp::TxLevel;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Audio is moved in blocks of this many milliseconds, like the audio engine
+const BLOCK_MS: f32 = 20.0;
+
+/// Consecutive ones a receiver must see before it declares the far end heard
+const DETECT_ONES: usize = 64;
+
+/// Length of the bit window used to align received data with sent data
+const ALIGN_WINDOW: usize = 256;
+
+/// Throughput only counts data in whole blocks of this many bits received
+/// without error, so a receiver that never synced scores zero
+const THROUGHPUT_BLOCK_BITS: usize = 64;
+
+/// Line modulation selectable in a scenario
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineMode {
+    Bell103,
+    V21,
+    V22,
+    V22bis,
+    Bell212A,
+}
+
+impl LineMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineMode::Bell103 => "Bell 103",
+            LineMode::V21 => "V.21",
+            LineMode::V22 => "V.22",
+            LineMode::V22bis => "V.22bis",
+            LineMode::Bell212A => "Bell 212A",
+        }
+    }
+
+    /// Nominal data rate in bits per second
+    pub fn bit_rate(&self) -> u32 {
+        match self {
+            LineMode::Bell103 | LineMode::V21 => 300,
+            LineMode::V22 | LineMode::Bell212A => 1200,
+            LineMode::V22bis => 2400,
+        }
+    }
+
+    fn fsk_mode(&self, role: Role) -> Option<FSKMode> {
+        match (self, role) {
+            (LineMode::Bell103, Role::Originate) => Some(FSKMode::Bell103Originate),
+            (LineMode::Bell103, Role::Answer) => Some(FSKMode::Bell103Answer),
+            (LineMode::V21, Role::Originate) => Some(FSKMode::V21Originate),
+            (LineMode::V21, Role::Answer) => Some(FSKMode::V21Answer),
+            _ => None,
+        }
+    }
+
+    fn qam_mode(&self) -> Option<QAMMode> {
+        match self {
+            LineMode::V22 => Some(QAMMode::V22),
+            LineMode::V22bis => Some(QAMMode::V22bis),
+            LineMode::Bell212A => Some(QAMMode::Bell212A),
+            _ => None,
+        }
+    }
+}
+
+/// Which end of the call a modem is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Originate,
+    Answer,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Originate => write!(f, "originate"),
+            Role::Answer => write!(f, "answer"),
+        }
+    }
+}
+
+enum Transmitter {
+    Fsk(FSKModulator),
+    Qam(QAMModulator),
+}
+
+impl Transmitter {
+    /// Transmitter for `role`, sending in that role's band
+    fn new(mode: LineMode, role: Role, level: TxLevel, sample_rate: f32) -> Self {
+        if let Some(fsk_mode) = mode.fsk_mode(role) {
+            let mut modulator = FSKModulator::new(fsk_mode, 300.0, sample_rate);
+            modulator.set_tx_level(level);
+            return Transmitter::Fsk(modulator);
+        }
+        let qam_mode = mode.qam_mode().unwrap_or(QAMMode::V22);
+        let carrier = match role {
+            Role::Originate => qam_mode.carrier_freq_originate(),
+            Role::Answer => qam_mode.carrier_freq_answer(),
+        };
+        let mut modulator = QAMModulator::new(qam_mode, carrier, sample_rate);
+        modulator.set_tx_level(level);
+        Transmitter::Qam(modulator)
+    }
+
+    /// Raw bits, no character framing: the BER counts compare line bits
+    fn modulate(&mut self, bits: &[bool]) -> Vec<f32> {
+        match self {
+            Transmitter::Fsk(m) => m.modulate(bits),
+            Transmitter::Qam(m) => m.modulate(bits),
+        }
+    }
+}
+
+enum Receiver {
+    Fsk(Box<FSKDemodulator>),
+    Qam(Box<QAMDemodulator>),
+}
+
+impl Receiver {
+    /// Receiver listening to the band the `remote` role transmits in
+    fn new(mode: LineMode, remote: Role, detector: Option<FSKDetector>, sample_rate: f32) -> Self {
+        if let Some(fsk_mode) = mode.fsk_mode(remote) {
+            let detector = detector.unwrap_or_else(|| default_detector(fsk_mode));
+            return Receiver::Fsk(Box::new(FSKDemodulator::with_detector(fsk_mode, 300.0, sample_rate, detector)));
+        }
+        let qam_mode = mode.qam_mode().unwrap_or(QAMMode::V22);
+        let carrier = match remote {
+            Role::Originate => qam_mode.carrier_freq_originate(),
+            Role::Answer => qam_mode.carrier_freq_answer(),
+        };
+        Receiver::Qam(Box::new(QAMDemodulator::new(qam_mode, carrier, sample_rate)))
+    }
+
+    fn demodulate(&mut self, samples: &[f32]) -> Vec<bool> {
+        match self {
+            Receiver::Fsk(d) => d.demodulate(samples),
+            Receiver::Qam(d) => d.demodulate(samples),
+        }
+    }
+
+    /// Carrier lock, for receivers that have a carrier loop
+    fn is_locked(&self) -> Option<bool> {
+        match self {
+            Receiver::Fsk(_) => None,
+            Receiver::Qam(d) => Some(d.is_locked()),
+        }
+    }
+
+    fn set_data_mode(&mut self, data: bool) {
+        match self {
+            Receiver::Fsk(d) => d.set_data_mode(data),
+            Receiver::Qam(d) => d.set_data_mode(data),
+        }
+    }
+
+    fn receive_level_dbm0(&self) -> f32 {
+        match self {
+            Receiver::Fsk(d) => d.receive_level_dbm0(),
+            Receiver::Qam(d) => d.receive_level_dbm0(),
+        }
+    }
+}
+
+/// Something notable that happened during the run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkEventKind {
+    TrainingStarted,
+    RemoteDetected,
+    DataStarted,
+    LockAcquired,
+    LockLost,
+}
+
+impl fmt::Display for LinkEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            LinkEventKind::TrainingStarted => "training started",
+            LinkEventKind::RemoteDetected => "far-end training detected",
+            LinkEventKind::DataStarted => "data transfer started",
+            LinkEventKind::LockAcquired => "carrier lock acquired",
+            LinkEventKind::LockLost => "carrier lock lost",
+        };
+        f.write_str(text)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinkEvent {
+    pub time_s: f32,
+    pub side: Role,
+    pub kind: LinkEventKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TxPhase {
+    Silent,
+    Training { since: usize },
+    Data,
+}
+
+struct Endpoint {
+    mode: LineMode,
+    tx: Transmitter,
+    rx: Receiver,
+    phase: TxPhase,
+    tx_queue: VecDeque<f32>,
+    payload_pos: usize,
+    sent_data_bits: Vec<bool>,
+    rx_bits: Vec<bool>,
+    ones_run: usize,
+    remote_detected_at: Option<usize>,
+    data_started_at: Option<usize>,
+    locked: Option<bool>,
+}
+
+impl Endpoint {
+    fn new(role: Role, spec: &SideSpec, remote_mode: LineMode, sample_rate: f32) -> Self {
+        let remote = match role {
+            Role::Originate => Role::Answer,
+            Role::Answer => Role::Originate,
+        };
+        let level = TxLevel::new(spec.tx_level());
+        // We demodulate whatever the far end is configured to send
+        let rx = Receiver::new(remote_mode, remote, spec.fsk_detector, sample_rate);
+
+        Self {
+            mode: spec.mode,
+            tx: Transmitter::new(spec.mode, role, level, sample_rate),
+            // Start from the receiver's own state, so only real changes log
+            locked: rx.is_locked(),
+            rx,
+            phase: TxPhase::Silent,
+            tx_queue: VecDeque::new(),
+            payload_pos: 0,
+            sent_data_bits: Vec::new(),
+            rx_bits: Vec::new(),
+            ones_run: 0,
+            remote_detected_at: None,
+            data_started_at: None,
+        }
+    }
+
+    /// Produce the next block of line audio
+    fn transmit(&mut self, len: usize, payload: &[u8]) -> Vec<f32> {
+        if self.phase == TxPhase::Silent {
+            return vec![0.0; len];
+        }
+
+        while self.tx_queue.len() < len {
+            let byte = match self.phase {
+                TxPhase::Data => {
+                    let byte = payload[self.payload_pos % payload.len()];
+                    self.payload_pos += 1;
+                    self.sent_data_bits
+                        .extend((0..8).map(|i| (byte >> i) & 1 == 1));
+                    byte
+                }
+                _ => 0xFF,
+            };
+            let bits: Vec<bool> = (0..8).map(|i| (byte >> i) & 1 == 1).collect();
+            let audio = self.tx.modulate(&bits);
+            if audio.is_empty() {
+                break;
+            }
+            self.tx_queue.extend(audio);
+        }
+
+        let mut block: Vec<f32> = self.tx_queue.drain(..len.min(self.tx_queue.len())).collect();
+        block.resize(len, 0.0);
+        block
+    }
+
+    /// Feed a block of received line audio; returns true if the far end was
+    /// detected during this block
+    fn receive(&mut self, samples: &[f32]) -> bool {
+        let bits = self.rx.demodulate(samples);
+        let mut detected = false;
+
+        for &bit in &bits {
+            self.ones_run = if bit { self.ones_run + 1 } else { 0 };
+            if self.ones_run >= DETECT_ONES && self.remote_detected_at.is_none() {
+                detected = true;
+            }
+        }
+
+        self.rx_bits.extend(bits);
+        detected
+    }
+}
+
+/// What one modem received over the run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SideReport {
+    pub role: Role,
+    pub mode: LineMode,
+    /// Time at which this side heard the far end's training
+    pub handshake_s: Option<f32>,
+    /// Time at which this side started sending data
+    pub data_start_s: Option<f32>,
+    /// Data bits this side transmitted
+    pub data_bits_sent: usize,
+    /// Far-end data bits that could be compared after alignment
+    pub bits_checked: usize,
+    pub bit_errors: usize,
+    /// Data bits per second of far-end data time, counting only blocks of
+    /// 64 bits received without error
+    pub throughput_bps: f32,
+    /// Receive level at the end of the run
+    pub rx_level_dbm0: f32,
+}
+
+impl SideReport {
+    pub fn ber(&self) -> Option<f64> {
+        if self.bits_checked == 0 {
+            None
+        } else {
+            Some(self.bit_errors as f64 / self.bits_checked as f64)
+        }
+    }
+}
+
+/// Result of a full scenario run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimReport {
+    pub name: String,
+    pub duration_s: f32,
+    pub originate: SideReport,
+    pub answer: SideReport,
+    pub events: Vec<LinkEvent>,
+}
+
+impl fmt::Display for SimReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn opt_s(v: Option<f32>) -> String {
+            v.map(|s| format!("{:.3} s", s)).unwrap_or_else(|| "never".to_string())
+        }
+        fn opt_ber(v: Option<f64>) -> String {
+            v.map(|b| format!("{:.2e}", b)).unwrap_or_else(|| "n/a".to_string())
+        }
+
+        if !self.name.is_empty() {
+            writeln!(f, "scenario: {}", self.name)?;
+        }
+        writeln!(f, "simulated: {:.2} s", self.duration_s)?;
+        writeln!(f)?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "", "originate", "answer")?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "mode", self.originate.mode.name(), self.answer.mode.name())?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "far end heard at", opt_s(self.originate.handshake_s), opt_s(self.answer.handshake_s))?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "data started at", opt_s(self.originate.data_start_s), opt_s(self.answer.data_start_s))?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "data bits sent", self.originate.data_bits_sent, self.answer.data_bits_sent)?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "bits checked (rx)", self.originate.bits_checked, self.answer.bits_checked)?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "bit errors (rx)", self.originate.bit_errors, self.answer.bit_errors)?;
+        writeln!(f, "{:<24}{:>16}{:>16}", "BER (rx)", opt_ber(self.originate.ber()), opt_ber(self.answer.ber()))?;
+        writeln!(
+            f,
+            "{:<24}{:>16}{:>16}",
+            "throughput (rx)",
+            format!("{:.0} bps", self.originate.throughput_bps),
+            format!("{:.0} bps", self.answer.throughput_bps)
+        )?;
+        writeln!(
+            f,
+            "{:<24}{:>16}{:>16}",
+            "rx level",
+            format!("{:.1} dBm0", self.originate.rx_level_dbm0),
+            format!("{:.1} dBm0", self.answer.rx_level_dbm0)
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "events:")?;
+        for event in &self.events {
+            writeln!(f, "  {:>8.3} s  {:<10} {}", event.time_s, event.side.to_string(), event.kind)?;
+        }
+        Ok(())
+    }
+}
+
+/// Drives both modems and both line directions sample block by block
+pub struct LinkSimulator {
+    name: String,
+    sample_rate: f32,
+    total_samples: usize,
+    training_samples: usize,
+    payload: Vec<u8>,
+    originate: Endpoint,
+    answer: Endpoint,
+    originate_to_answer: ChannelSimulator,
+    answer_to_originate: ChannelSimulator,
+    events: Vec<LinkEvent>,
+}
+
+impl LinkSimulator {
+    pub fn new(scenario: &Scenario) -> Result<Self, String> {
+        let sample_rate = scenario.sample_rate;
+        let originate_mode = scenario.originate.mode;
+        let answer_mode = scenario.answer.mode;
+
+        Ok(Self {
+            name: scenario.name.clone(),
+            sample_rate,
+            total_samples: (scenario.duration_s * sample_rate).round() as usize,
+            training_samples: (scenario.training_s * sample_rate).round() as usize,
+            payload: scenario.payload.bytes()?,
+            originate: Endpoint::new(Role::Originate, &scenario.originate, answer_mode, sample_rate),
+            answer: Endpoint::new(Role::Answer, &scenario.answer, originate_mode, sample_rate),
+            originate_to_answer: ChannelSimulator::new(
+                scenario.channel.impairments(sample_rate, 0, scenario.originate.tx_level()),
+                sample_rate,
+            ),
+            answer_to_originate: ChannelSimulator::new(
+                scenario.channel.impairments(sample_rate, 1, scenario.answer.tx_level()),
+                sample_rate,
+            ),
+            events: Vec::new(),
+        })
+    }
+
+    fn log(&mut self, sample: usize, side: Role, kind: LinkEventKind) {
+        self.events.push(LinkEvent {
+            time_s: sample as f32 / self.sample_rate,
+            side,
+            kind,
+        });
+    }
+
+    fn endpoint_mut(&mut self, role: Role) -> &mut Endpoint {
+        match role {
+            Role::Originate => &mut self.originate,
+            Role::Answer => &mut self.answer,
+        }
+    }
+
+    /// Run the whole scenario and score it
+    pub fn run(mut self) -> SimReport {
+        let block_len = ((BLOCK_MS / 1000.0) * self.sample_rate).round().max(1.0) as usize;
+
+        // The answering modem speaks first
+        self.answer.phase = TxPhase::Training { since: 0 };
+        self.log(0, Role::Answer, LinkEventKind::TrainingStarted);
+
+        let mut now = 0;
+        while now < self.total_samples {
+            let len = block_len.min(self.total_samples - now);
+
+            let from_originate = self.originate.transmit(len, &self.payload);
+            let from_answer = self.answer.transmit(len, &self.payload);
+            let at_answer = self.originate_to_answer.process(&from_originate);
+            let at_originate = self.answer_to_originate.process(&from_answer);
+
+            now += len;
+
+            for (role, incoming) in [(Role::Originate, at_originate), (Role::Answer, at_answer)] {
+                if self.endpoint_mut(role).receive(&incoming) {
+                    self.endpoint_mut(role).remote_detected_at = Some(now);
+                    self.log(now, role, LinkEventKind::RemoteDetected);
+                }
+
+                let endpoint = self.endpoint_mut(role);
+                let locked = endpoint.rx.is_locked();
+                let changed = locked.is_some() && locked != endpoint.locked;
+                endpoint.locked = locked;
+                if changed {
+                    let kind = if locked == Some(true) {
+                        LinkEventKind::LockAcquired
+                    } else {
+                        LinkEventKind::LockLost
+                    };
+                    self.log(now, role, kind);
+                }
+            }
+
+            self.advance_phases(now);
+        }
+
+        let originate = self.score(Role::Originate);
+        let answer = self.score(Role::Answer);
+
+        SimReport {
+            name: self.name,
+            duration_s: self.total_samples as f32 / self.sample_rate,
+            originate,
+            answer,
+            events: self.events,
+        }
+    }
+
+    fn advance_phases(&mut self, now: usize) {
+        for role in [Role::Originate, Role::Answer] {
+            let training_samples = self.training_samples;
+            let endpoint = self.endpoint_mut(role);
+            let heard = endpoint.remote_detected_at.is_some();
+
+            let next = match endpoint.phase {
+                // Originate answers the far end's training with its own
+                TxPhase::Silent if heard => Some(TxPhase::Training { since: now }),
+                TxPhase::Training { since } if heard && now - since >= training_samples => {
+                    Some(TxPhase::Data)
+                }
+                _ => None,
+            };
+
+            if let Some(phase) = next {
+                endpoint.phase = phase;
+                let kind = if phase == TxPhase::Data {
+                    endpoint.data_started_at = Some(now);
+                    endpoint.rx.set_data_mode(true);
+                    LinkEventKind::DataStarted
+                } else {
+                    LinkEventKind::TrainingStarted
+                };
+                self.log(now, role, kind);
+            }
+        }
+    }
+
+    /// Compare what `role` received against what the far end sent
+    fn score(&self, role: Role) -> SideReport {
+        let (local, remote) = match role {
+            Role::Originate => (&self.originate, &self.answer),
+            Role::Answer => (&self.answer, &self.originate),
+        };
+
+        let sent = &remote.sent_data_bits;
+        let received = &local.rx_bits;
+        let (bits_checked, bit_errors, error_free_bits) = match best_alignment(sent, received) {
+            Some(offset) => {
+                let n = sent.len().min(received.len() - offset);
+                let (sent, received) = (&sent[..n], &received[offset..offset + n]);
+                let errors = sent.iter().zip(received).filter(|(a, b)| a != b).count();
+                let error_free_blocks = sent
+                    .chunks_exact(THROUGHPUT_BLOCK_BITS)
+                    .zip(received.chunks_exact(THROUGHPUT_BLOCK_BITS))
+                    .filter(|(a, b)| a == b)
+                    .count();
+                (n, errors, error_free_blocks * THROUGHPUT_BLOCK_BITS)
+            }
+            None => (0, 0, 0),
+        };
+
+        let data_time = remote
+            .data_started_at
+            .map(|start| (self.total_samples - start) as f32 / self.sample_rate)
+            .unwrap_or(0.0);
+        let throughput_bps = if data_time > 0.0 {
+            error_free_bits as f32 / data_time
+        } else {
+            0.0
+        };
+
+        let to_s = |s: usize| s as f32 / self.sample_rate;
+
+        SideReport {
+            role,
+            mode: local.mode,
+            handshake_s: local.remote_detected_at.map(to_s),
+            data_start_s: local.data_started_at.map(to_s),
+            data_bits_sent: local.sent_data_bits.len(),
+            bits_checked,
+            bit_errors,
+            throughput_bps,
+            rx_level_dbm0: local.rx.receive_level_dbm0(),
+        }
+    }
+}
+
+/// Offset into `received` where the start of `sent` matches best
+fn best_alignment(sent: &[bool], received: &[bool]) -> Option<usize> {
+    let window = sent.len().min(ALIGN_WINDOW);
+    if window == 0 || received.len() < window {
+        return None;
+    }
+
+    (0..=received.len() - window).min_by_key(|&offset| {
+        sent[..window]
+            .iter()
+            .zip(&received[offset..offset + window])
+            .filter(|(a, b)| a != b)
+            .count()
+    })
+}
+
+/// Convenience wrapper: build and run a scenario
+pub fn run_scenario(scenario: &Scenario) -> Result<SimReport, String> {
+    Ok(LinkSimulator::new(scenario)?.run())
+}
@@ -0,0 +1,13 @@
+// src/sim/mod.rs
+//
+// Offline simulation helpers: line impairments and presets, audio file I/O,
+// constellation/eye plots and the two-modem link simulator behind the
+// `modemsim` tool.
+// Nothing in here touches the audio engine, so it runs on any host.
+
+pub mod channel;
+pub mod line;
+pub mod wav;
+pub mod link;
+pub mod scenario;
+pub mod plot;
@@ -0,0 +1,225 @@
+// src/sim/plot.rs
+//
+// CSV export and plain SVG rendering of a `SymbolTap`: constellation and eye
+// diagrams that open in any browser, no GUI or plotting crate needed.
+
+use crate::dsp::tap::{SymbolTap, TimingSample};
+use num_complex::Complex32;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+const PLOT_SIZE: f32 = 480.0;
+const MARGIN: f32 = 40.0;
+
+/// Which baseband rail an eye diagram shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rail {
+    InPhase,
+    Quadrature,
+}
+
+impl Rail {
+    fn pick(&self, value: Complex32) -> f32 {
+        match self {
+            Rail::InPhase => value.re,
+            Rail::Quadrature => value.im,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Rail::InPhase => "I",
+            Rail::Quadrature => "Q",
+        }
+    }
+}
+
+/// One row per symbol: pre-equalizer, post-equalizer and decision
+pub fn symbols_csv(tap: &SymbolTap) -> String {
+    let mut out = String::from("index,pre_eq_i,pre_eq_q,post_eq_i,post_eq_q,decision_i,decision_q\n");
+    for s in tap.symbols() {
+        let _ = writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            s.index, s.pre_eq.re, s.pre_eq.im, s.post_eq.re, s.post_eq.im, s.decision.re, s.decision.im
+        );
+    }
+    out
+}
+
+/// One row per baseband sample with its timing phase
+pub fn timing_csv(tap: &SymbolTap) -> String {
+    let mut out = String::from("symbol,phase,i,q\n");
+    for t in tap.timing_samples() {
+        let _ = writeln!(out, "{},{},{},{}", t.symbol, t.phase, t.value.re, t.value.im);
+    }
+    out
+}
+
+/// Largest |I| or |Q| in the data, rounded up so the axes look tidy
+fn axis_limit(values: impl Iterator<Item = f32>) -> f32 {
+    let peak = values.fold(0.0f32, |m, v| if v.is_finite() { m.max(v.abs()) } else { m });
+    if peak <= 0.0 {
+        1.0
+    } else {
+        (peak * 4.0).ceil() / 4.0
+    }
+}
+
+fn svg_header(out: &mut String, title: &str) {
+    let full = PLOT_SIZE + 2.0 * MARGIN;
+    let _ = writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{full}" height="{full}" viewBox="0 0 {full} {full}">"#
+    );
+    let _ = writeln!(out, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    let _ = writeln!(
+        out,
+        r#"<text x="{}" y="{}" font-family="monospace" font-size="14" text-anchor="middle">{}</text>"#,
+        full / 2.0,
+        MARGIN * 0.6,
+        title
+    );
+    let _ = writeln!(
+        out,
+        r#"<rect x="{MARGIN}" y="{MARGIN}" width="{PLOT_SIZE}" height="{PLOT_SIZE}" fill="none" stroke="black"/>"#
+    );
+}
+
+/// Constellation of equalized symbols (blue) over the raw symbols (grey),
+/// with slicer decisions marked in red. The first `skip` symbols are left out.
+pub fn constellation_svg(tap: &SymbolTap, skip: usize) -> String {
+    let symbols: Vec<_> = tap.symbols().skip(skip).collect();
+    let limit = axis_limit(
+        symbols
+            .iter()
+            .flat_map(|s| [s.pre_eq.re, s.pre_eq.im, s.post_eq.re, s.post_eq.im]),
+    );
+    let scale = PLOT_SIZE / (2.0 * limit);
+    let to_xy = |z: Complex32| (MARGIN + (z.re + limit) * scale, MARGIN + (limit - z.im) * scale);
+
+    let title = match tap.evm(skip) {
+        Some(evm) => format!("constellation ({} symbols, EVM {:.1}%)", symbols.len(), evm * 100.0),
+        None => format!("constellation ({} symbols)", symbols.len()),
+    };
+
+    let mut out = String::new();
+    svg_header(&mut out, &title);
+
+    let mid = MARGIN + PLOT_SIZE / 2.0;
+    let _ = writeln!(
+        out,
+        r##"<path d="M{MARGIN} {mid}H{} M{mid} {MARGIN}V{}" stroke="#bbb"/>"##,
+        MARGIN + PLOT_SIZE,
+        MARGIN + PLOT_SIZE
+    );
+
+    for (stage, colour) in [(0, "#999"), (1, "#1f5fbf")] {
+        let _ = writeln!(out, r#"<g fill="{colour}" fill-opacity="0.6">"#);
+        for s in &symbols {
+            let (x, y) = to_xy(if stage == 0 { s.pre_eq } else { s.post_eq });
+            if x.is_finite() && y.is_finite() {
+                let _ = writeln!(out, r#"<circle cx="{x:.1}" cy="{y:.1}" r="1.5"/>"#);
+            }
+        }
+        let _ = writeln!(out, "</g>");
+    }
+
+    let mut decisions: Vec<Complex32> = Vec::new();
+    for s in &symbols {
+        if !decisions.iter().any(|d| (d - s.decision).norm_sqr() < 1e-6) {
+            decisions.push(s.decision);
+        }
+    }
+    let _ = writeln!(out, r#"<g stroke="red" stroke-width="1.5">"#);
+    for d in decisions {
+        let (x, y) = to_xy(d);
+        let _ = writeln!(
+            out,
+            r#"<path d="M{} {y:.1}H{} M{x:.1} {}V{}"/>"#,
+            x - 5.0,
+            x + 5.0,
+            y - 5.0,
+            y + 5.0
+        );
+    }
+    let _ = writeln!(out, "</g>");
+
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Eye diagram of one rail: every two-symbol window overlaid, with the
+/// sampling instants at the 1/2 and 2/2 marks. The first `skip` symbols are left out.
+pub fn eye_svg(tap: &SymbolTap, rail: Rail, skip: usize) -> String {
+    let first = tap.symbols().nth(skip).map(|s| s.index).unwrap_or(u64::MAX);
+    let samples: Vec<&TimingSample> = tap.timing_samples().filter(|t| t.symbol >= first).collect();
+    let limit = axis_limit(samples.iter().map(|t| rail.pick(t.value)));
+    let y_scale = PLOT_SIZE / (2.0 * limit);
+    let x_scale = PLOT_SIZE / 2.0;
+
+    let mut out = String::new();
+    svg_header(&mut out, &format!("eye diagram ({} rail)", rail.label()));
+
+    let mid = MARGIN + PLOT_SIZE / 2.0;
+    let _ = writeln!(
+        out,
+        r##"<path d="M{MARGIN} {mid}H{} M{mid} {MARGIN}V{}" stroke="#bbb" stroke-dasharray="4 4"/>"##,
+        MARGIN + PLOT_SIZE,
+        MARGIN + PLOT_SIZE
+    );
+
+    let _ = writeln!(
+        out,
+        r##"<g fill="none" stroke="#1f5fbf" stroke-opacity="0.25">"##
+    );
+    let mut start = 0;
+    while start < samples.len() {
+        let base = samples[start].symbol;
+        let window: Vec<String> = samples[start..]
+            .iter()
+            .take_while(|t| t.symbol <= base + 1)
+            .filter(|t| rail.pick(t.value).is_finite())
+            .map(|t| {
+                let x = MARGIN + ((t.symbol - base) as f32 + t.phase) * x_scale;
+                let y = MARGIN + (limit - rail.pick(t.value)) * y_scale;
+                format!("{x:.1},{y:.1}")
+            })
+            .collect();
+        if window.len() > 1 {
+            let _ = writeln!(out, r#"<polyline points="{}"/>"#, window.join(" "));
+        }
+
+        // Next window starts at the following symbol
+        match samples[start..].iter().position(|t| t.symbol > base) {
+            Some(offset) => start += offset,
+            None => break,
+        }
+    }
+    let _ = writeln!(out, "</g>");
+
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Write `<stem>_symbols.csv`, `<stem>_timing.csv`, `<stem>_constellation.svg`
+/// and `<stem>_eye_i.svg`/`<stem>_eye_q.svg` into `dir`
+pub fn write_tap_files(tap: &SymbolTap, dir: &Path, stem: &str, skip: usize) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join(format!("{stem}_symbols.csv")), symbols_csv(tap))?;
+    std::fs::write(dir.join(format!("{stem}_timing.csv")), timing_csv(tap))?;
+    std::fs::write(
+        dir.join(format!("{stem}_constellation.svg")),
+        constellation_svg(tap, skip),
+    )?;
+    std::fs::write(
+        dir.join(format!("{stem}_eye_i.svg")),
+        eye_svg(tap, Rail::InPhase, skip),
+    )?;
+    std::fs::write(
+        dir.join(format!("{stem}_eye_q.svg")),
+        eye_svg(tap, Rail::Quadrature, skip),
+    )?;
+    Ok(())
+}
@@ -0,0 +1,203 @@
+// src/sim/scenario.rs
+//
+// TOML scenario description for the two-modem link simulator (`modemsim`).
+//
+//   name = "V.22bis on a noisy loop"
+//   duration_s = 5.0
+//
+//   [payload]
+//   text = "The quick brown fox"
+//
+//   [originate]
+//   mode = "v22bis"
+//
+//   [answer]
+//   mode = "v22bis"
+//   fsk_detector = "pll"   # FSK modes: goertzel, quadrature, discriminator, pll
+//   tx_level_dbm0 = -10.0
+//
+//   [channel]
+//   preset = "long_loop"   # flat, short_loop, long_loop, loaded_loop, satellite_hop
+//   snr_db = 25.0          # at the receiver; overrides the preset's noise
+//   delay_ms = 4.0
+
+use super::channel::{Impairments, NoiseSource};
+use super::line::LinePreset;
+use super::link::LineMode;
+use crate::dsp::fsk_detector::FSKDetector;
+use crate::dsp::{DEFAULT_TX_LEVEL_DBM0, SAMPLE_RATE};
+use serde::{Deserialize, Deserializer};
+use std::path::Path;
+
+fn default_sample_rate() -> f32 {
+    SAMPLE_RATE
+}
+
+fn default_training_s() -> f32 {
+    0.75
+}
+
+fn default_seed() -> u64 {
+    1
+}
+
+/// Complete simulation scenario
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: String,
+    /// Total simulated time in seconds
+    pub duration_s: f32,
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f32,
+    /// How long each side sends scrambled ones before switching to data
+    #[serde(default = "default_training_s")]
+    pub training_s: f32,
+    pub payload: PayloadSpec,
+    pub originate: SideSpec,
+    pub answer: SideSpec,
+    /// Line model, applied to both directions (with independent noise)
+    #[serde(default)]
+    pub channel: ChannelSpec,
+}
+
+/// Per-modem settings
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SideSpec {
+    pub mode: LineMode,
+    /// Demodulator for FSK modes; the mode's default when absent
+    #[serde(default, deserialize_with = "fsk_detector")]
+    pub fsk_detector: Option<FSKDetector>,
+    /// Transmit level; the modulators' default when absent
+    pub tx_level_dbm0: Option<f32>,
+}
+
+impl SideSpec {
+    /// Transmit level in dBm0, filling in the default
+    pub fn tx_level(&self) -> f32 {
+        self.tx_level_dbm0.unwrap_or(DEFAULT_TX_LEVEL_DBM0)
+    }
+}
+
+fn fsk_detector<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<FSKDetector>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    name.parse().map(Some).map_err(serde::de::Error::custom)
+}
+
+/// Data to send once training is over. Exactly one source must be given.
+/// The payload is repeated until the scenario ends.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PayloadSpec {
+    pub text: Option<String>,
+    pub hex: Option<String>,
+    pub random_bytes: Option<usize>,
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+}
+
+impl PayloadSpec {
+    pub fn bytes(&self) -> Result<Vec<u8>, String> {
+        match (&self.text, &self.hex, self.random_bytes) {
+            (Some(text), None, None) => Ok(text.as_bytes().to_vec()),
+            (None, Some(hex), None) => {
+                let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+                if digits.len() & 1 == 1 {
+                    return Err("payload.hex must have an even number of digits".to_string());
+                }
+                digits
+                    .chunks(2)
+                    .map(|pair| {
+                        let s: String = pair.iter().collect();
+                        u8::from_str_radix(&s, 16)
+                            .map_err(|_| format!("payload.hex: invalid byte '{}'", s))
+                    })
+                    .collect()
+            }
+            (None, None, Some(n)) => {
+                let mut rng = NoiseSource::new(self.seed);
+                Ok((0..n).map(|_| (rng.next_u64() >> 56) as u8).collect())
+            }
+            _ => Err("payload needs exactly one of text, hex or random_bytes".to_string()),
+        }
+    }
+}
+
+/// Line impairments in scenario units. With a `preset`, its loss, noise
+/// and shape are the starting point and any other field overrides them.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChannelSpec {
+    pub preset: Option<LinePreset>,
+    pub gain_db: Option<f32>,
+    pub snr_db: Option<f32>,
+    #[serde(default)]
+    pub delay_ms: f32,
+    pub bandwidth_hz: Option<f32>,
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+}
+
+impl Default for ChannelSpec {
+    fn default() -> Self {
+        Self {
+            preset: None,
+            gain_db: None,
+            snr_db: None,
+            delay_ms: 0.0,
+            bandwidth_hz: None,
+            seed: default_seed(),
+        }
+    }
+}
+
+impl ChannelSpec {
+    /// Impairments for one direction, carrying a signal sent at
+    /// `signal_dbm0`; `direction` decorrelates the noise
+    pub fn impairments(&self, sample_rate: f32, direction: u64, signal_dbm0: f32) -> Impairments {
+        let base = self.preset.map(Impairments::preset).unwrap_or_default();
+
+        Impairments {
+            gain_db: self.gain_db.unwrap_or(base.gain_db),
+            snr_db: self.snr_db.or(base.snr_db),
+            signal_dbm0,
+            delay_samples: (self.delay_ms * sample_rate / 1000.0).round() as usize,
+            bandwidth_hz: self.bandwidth_hz.or(base.bandwidth_hz),
+            line: base.line,
+            seed: self.seed.wrapping_mul(2).wrapping_add(direction),
+        }
+    }
+}
+
+impl Scenario {
+    pub fn from_toml_str(text: &str) -> Result<Self, String> {
+        let scenario: Scenario = toml::from_str(text).map_err(|e| e.to_string())?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_toml_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.duration_s.is_nan() || self.duration_s <= 0.0 {
+            return Err("duration_s must be positive".to_string());
+        }
+        if self.sample_rate.is_nan() || self.sample_rate <= 0.0 {
+            return Err("sample_rate must be positive".to_string());
+        }
+        if self.training_s < 0.0 {
+            return Err("training_s must not be negative".to_string());
+        }
+        if self.payload.bytes()?.is_empty() {
+            return Err("payload is empty".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+// src/sim/wav.rs
+//
+// Minimal mono WAV reader/writer for captures and golden fixtures.
+// Writes 32-bit IEEE float so samples round-trip bit for bit; reads
+// either 32-bit float or 16-bit PCM (what most capture tools produce).
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_IEEE_FLOAT: u16 = 3;
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Encode mono f32 samples as a WAV byte stream
+pub fn encode_wav_f32(sample_rate: u32, samples: &[f32]) -> Vec<u8> {
+    let data_len = (samples.len() * 4) as u32;
+    let mut out = Vec::with_capacity(44 + data_len as usize);
+
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+
+    // fmt chunk
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&FORMAT_IEEE_FLOAT.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // channels
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * 4).to_le_bytes()); // byte rate
+    out.extend_from_slice(&4u16.to_le_bytes()); // block align
+    out.extend_from_slice(&32u16.to_le_bytes()); // bits per sample
+
+    // data chunk
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for &s in samples {
+        out.extend_from_slice(&s.to_le_bytes());
+    }
+
+    out
+}
+
+/// Decode a mono WAV byte stream, returning (sample_rate, samples)
+pub fn decode_wav(bytes: &[u8]) -> io::Result<(u32, Vec<f32>)> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(invalid("not a RIFF/WAVE file"));
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;
+    let mut pos = 12;
+
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let len = u32::from_le_bytes([
+            bytes[pos + 4],
+            bytes[pos + 5],
+            bytes[pos + 6],
+            bytes[pos + 7],
+        ]) as usize;
+        let body_start = pos + 8;
+        let body_end = body_start
+            .checked_add(len)
+            .filter(|&end| end <= bytes.len())
+            .ok_or_else(|| invalid("truncated chunk"))?;
+        let body = &bytes[body_start..body_end];
+
+        match id {
+            b"fmt " => {
+                if body.len() < 16 {
+                    return Err(invalid("short fmt chunk"));
+                }
+                let tag = u16::from_le_bytes([body[0], body[1]]);
+                let channels = u16::from_le_bytes([body[2], body[3]]);
+                let rate = u32::from_le_bytes([body[4], body[5], body[6], body[7]]);
+                let bits = u16::from_le_bytes([body[14], body[15]]);
+                format = Some((tag, channels, rate, bits));
+            }
+            b"data" => {
+                let (tag, channels, rate, bits) =
+                    format.ok_or_else(|| invalid("data chunk before fmt chunk"))?;
+                if channels != 1 {
+                    return Err(invalid("only mono WAV files are supported"));
+                }
+
+                let samples = match (tag, bits) {
+                    (FORMAT_IEEE_FLOAT, 32) => body
+                        .chunks_exact(4)
+                        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+                        .collect(),
+                    (FORMAT_PCM, 16) => body
+                        .chunks_exact(2)
+                        .map(|c| i16::from_le_bytes([c[0], c[1]]) as f32 / 32768.0)
+                        .collect(),
+                    _ => return Err(invalid("unsupported sample format")),
+                };
+
+                return Ok((rate, samples));
+            }
+            _ => {}
+        }
+
+        // Chunks are padded to an even length
+        pos = body_end + (len & 1);
+    }
+
+    Err(invalid("no data chunk"))
+}
+
+/// Write mono f32 samples to a WAV file
+pub fn write_wav_f32<P: AsRef<Path>>(path: P, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+    fs::write(path, encode_wav_f32(sample_rate, samples))
+}
+
+/// Read a mono WAV file, returning (sample_rate, samples)
+pub fn read_wav<P: AsRef<Path>>(path: P) -> io::Result<(u32, Vec<f32>)> {
+    decode_wav(&fs::read(path)?)
+}
@@ -2,7 +2,8 @@
 //
 // Hayes-style AT command parsing and response types.
 
-use std::fmt;
+use core::fmt;
+use alloc::{format, string::{String, ToString}, vec::Vec};
 
 /// Hayes AT command responses.
 #[derive(Debug, Clone, PartialEq)]
@@ -18,19 +19,19 @@ pub enum ATResponse {
     Text(String),
 }
 
-impl ATResponse {
-    /// Render the response as a full modem-style line.
-    pub fn to_string(&self) -> String {
+/// Renders the response as a full modem-style line.
+impl fmt::Display for ATResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ATResponse::Ok => "OK\r\n".to_string(),
-            ATResponse::Error => "ERROR\r\n".to_string(),
-            ATResponse::Connect(baud) => format!("CONNECT {}\r\n", baud),
-            ATResponse::Ring => "RING\r\n".to_string(),
-            ATResponse::NoCarrier => "NO CARRIER\r\n".to_string(),
-            ATResponse::NoDialtone => "NO DIALTONE\r\n".to_string(),
-            ATResponse::Busy => "BUSY\r\n".to_string(),
-            ATResponse::NoAnswer => "NO ANSWER\r\n".to_string(),
-            ATResponse::Text(s) => format!("{}\r\n", s),
+            ATResponse::Ok => f.write_str("OK\r\n"),
+            ATResponse::Error => f.write_str("ERROR\r\n"),
+            ATResponse::Connect(baud) => write!(f, "CONNECT {}\r\n", baud),
+            ATResponse::Ring => f.write_str("RING\r\n"),
+            ATResponse::NoCarrier => f.write_str("NO CARRIER\r\n"),
+            ATResponse::NoDialtone => f.write_str("NO DIALTONE\r\n"),
+            ATResponse::Busy => f.write_str("BUSY\r\n"),
+            ATResponse::NoAnswer => f.write_str("NO ANSWER\r\n"),
+            ATResponse::Text(s) => write!(f, "{}\r\n", s),
         }
     }
 }
@@ -298,7 +299,7 @@ impl ATCommandParser {
                 }
                 _ => {
                     // Unrecognized – treat the rest as unknown.
-                    let tail: String = std::iter::once(c)
+                    let tail: String = core::iter::once(c)
                         .chain(chars[i..].iter().cloned())
                         .collect();
                     cmds.push(ATCommand::Unknown(tail));
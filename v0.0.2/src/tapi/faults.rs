@@ -0,0 +1,145 @@
+// src/tapi/faults.rs
+//
+// Deliberate fault injection for VirtualModem. Faults are scheduled against
+// the modem's line clock (samples received since power-up), so a test that
+// feeds the same audio always sees the fault land on the same sample.
+
+use core::time::Duration;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+
+/// A fault that can be scheduled on a running modem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Far-end carrier disappears: received line audio is replaced by silence
+    CarrierDrop { duration: Duration },
+    /// The next `bits` demodulated line bits are inverted before deframing
+    BitErrorBurst { bits: usize },
+    /// Audio engine stops delivering capture samples and accepting playback
+    AudioStall { duration: Duration },
+    /// Host drops DTR for `duration`; the modem hangs up as with AT&D2
+    DtrDrop { duration: Duration },
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Scheduled {
+    at_sample: u64,
+    fault: Fault,
+}
+
+/// A fault starting or ending, reported back to the modem for logging
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultTransition {
+    Started(Fault),
+    Cleared(Fault),
+}
+
+/// Tracks scheduled and active faults against the line clock
+#[derive(Debug)]
+pub struct FaultInjector {
+    sample_rate: f32,
+    scheduled: Vec<Scheduled>,
+    active: Vec<(u64, Fault)>,
+    bit_errors_left: usize,
+}
+
+impl FaultInjector {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            scheduled: Vec::new(),
+            active: Vec::new(),
+            bit_errors_left: 0,
+        }
+    }
+
+    pub fn to_samples(&self, time: Duration) -> u64 {
+        (time.as_secs_f64() * self.sample_rate as f64).round() as u64
+    }
+
+    /// Schedule `fault` to start when the line clock reaches `at_sample`
+    pub fn schedule(&mut self, at_sample: u64, fault: Fault) {
+        let idx = self
+            .scheduled
+            .iter()
+            .position(|s| s.at_sample > at_sample)
+            .unwrap_or(self.scheduled.len());
+        self.scheduled.insert(idx, Scheduled { at_sample, fault });
+    }
+
+    /// True if nothing is scheduled or active
+    pub fn is_idle(&self) -> bool {
+        self.scheduled.is_empty() && self.active.is_empty() && self.bit_errors_left == 0
+    }
+
+    /// Start due faults and clear expired ones at line sample `now`
+    pub fn advance(&mut self, now: u64) -> Vec<FaultTransition> {
+        let mut transitions = Vec::new();
+
+        // Expire before starting, so back-to-back faults don't overlap
+        let mut i = 0;
+        while i < self.active.len() {
+            if self.active[i].0 <= now {
+                let (_, fault) = self.active.remove(i);
+                transitions.push(FaultTransition::Cleared(fault));
+            } else {
+                i += 1;
+            }
+        }
+
+        while self.scheduled.first().is_some_and(|s| s.at_sample <= now) {
+            let fault = self.scheduled.remove(0).fault;
+            match fault {
+                Fault::BitErrorBurst { bits } => self.bit_errors_left += bits,
+                Fault::CarrierDrop { duration }
+                | Fault::AudioStall { duration }
+                | Fault::DtrDrop { duration } => {
+                    let until = now + self.to_samples(duration);
+                    self.active.push((until, fault));
+                }
+            }
+            transitions.push(FaultTransition::Started(fault));
+        }
+
+        transitions
+    }
+
+    pub fn carrier_dropped(&self) -> bool {
+        self.active
+            .iter()
+            .any(|(_, f)| matches!(f, Fault::CarrierDrop { .. }))
+    }
+
+    pub fn audio_stalled(&self) -> bool {
+        self.active
+            .iter()
+            .any(|(_, f)| matches!(f, Fault::AudioStall { .. }))
+    }
+
+    /// True while an injected DTR drop holds DTR low
+    pub fn dtr_dropped(&self) -> bool {
+        self.active
+            .iter()
+            .any(|(_, f)| matches!(f, Fault::DtrDrop { .. }))
+    }
+
+    /// Burst bits still waiting to be inverted
+    pub fn bit_errors_pending(&self) -> usize {
+        self.bit_errors_left
+    }
+
+    /// Invert pending burst bits in freshly demodulated line bits, before
+    /// they are deframed
+    pub fn corrupt(&mut self, bits: &mut [bool]) {
+        let n = self.bit_errors_left.min(bits.len());
+        bits[..n].iter_mut().for_each(|b| *b = !*b);
+        self.bit_errors_left -= n;
+    }
+
+    pub fn clear(&mut self) {
+        self.scheduled.clear();
+        self.active.clear();
+        self.bit_errors_left = 0;
+    }
+}
@@ -1,4 +1,9 @@
 // src/tapi/mod.rs
 pub mod at_commands;
+pub mod faults;
+#[cfg(feature = "audio")]
 pub mod modem;
+#[cfg(feature = "pipe-server")]
 pub mod pipe_server;
+#[cfg(feature = "audio")]
+pub mod session;
@@ -3,17 +3,23 @@
 // Virtual soft modem core: AT parser + FSK/QAM DSP + audio glue.
 
 use super::at_commands::{ATCommand, ATCommandParser, ATResponse, ModemState};
+use super::faults::{Fault, FaultInjector, FaultTransition};
 use crate::audio::{AudioEvent, ModemAudioConfig, WasapiAudioEngine};
+use crate::dsp::carrier::{CarrierDetector, CarrierDetectorConfig, CarrierEvent};
+use crate::dsp::framing::FramingStats;
 use crate::dsp::fsk::{FSKDemodulator, FSKMode, FSKModulator};
 use crate::dsp::qam::QAMMode;
 use crate::dsp::qam_modem::{QAMDemodulator, QAMModulator};
-use crate::dsp::SAMPLE_RATE;
+use crate::dsp::{TxLevel, FULL_SCALE_DBM0, SAMPLE_RATE};
+use std::ops::RangeInclusive;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 const ESCAPE_SEQUENCE: &str = "+++";
 const ESCAPE_GUARD_TIME: Duration = Duration::from_secs(1);
-const DIAL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Transmit levels S91 accepts, in -dBm0
+const TX_LEVEL_RANGE: RangeInclusive<u8> = 10..=43;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ModemMode {
@@ -23,13 +29,33 @@ pub enum ModemMode {
     Bell212A,
 }
 
+/// Entry in the modem's event log, stamped with line time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModemEvent {
+    pub at: Duration,
+    pub kind: ModemEventKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ModemEventKind {
+    Connected(u32),
+    HungUp,
+    DtrChanged(bool),
+    CarrierDetected,
+    CarrierLost,
+    FaultInjected(Fault),
+    FaultCleared(Fault),
+}
+
 pub struct VirtualModem {
     state: Arc<Mutex<ModemState>>,
     pub parser: ATCommandParser,
     s_registers: [u8; 256],
 
-    // Audio/DSP components
+    // Audio/DSP components; `capture_pending` while a capture buffer is
+    // on its way from the engine
     audio_engine: Option<WasapiAudioEngine>,
+    capture_pending: bool,
     modulator: FSKModulator,
     demodulator: FSKDemodulator,
 
@@ -39,14 +65,34 @@ pub struct VirtualModem {
     current_mode: ModemMode,
     connection_speed: u32,
 
-    // Connection / escape tracking
+    // Level a full-scale sample stands for on the audio path; S91 is
+    // measured against it
+    full_scale_dbm0: f32,
+
+    // Connection / escape tracking; `connect_deadline` is the line sample at
+    // which S7 runs out while dialing or answering
     connected: bool,
+    connect_deadline: Option<u64>,
+    carrier_detector: CarrierDetector,
     escape_sequence_time: Option<Instant>,
     plus_count: u8,
 
     // Host-side data buffers
     tx_buffer: Vec<u8>,
     rx_buffer: Vec<u8>,
+
+    // Result codes raised by line events (e.g. NO CARRIER), not by a command
+    unsolicited: Vec<ATResponse>,
+
+    // Line clock (samples received at `sample_rate`), fault injection and
+    // event log
+    sample_rate: f32,
+    line_samples: u64,
+    dtr: bool,
+    // Host's DTR from before an injected DTR drop, put back when it ends
+    dtr_before_fault: Option<bool>,
+    faults: FaultInjector,
+    events: Vec<ModemEvent>,
 }
 
 impl VirtualModem {
@@ -58,19 +104,30 @@ impl VirtualModem {
         regs[4] = 10; // S4 – response line feed (LF)
         regs[5] = 8;  // S5 – backspace
 
+        // S7 – seconds to wait for the far end's carrier after dialing or
+        // answering
+        regs[7] = 50;
+
+        // Carrier detect response time and lost-carrier hang-up delay, in
+        // 1/10 s: 0.6 s and 1.4 s
+        regs[9] = 6;
+        regs[10] = 14;
+
         // Guard time for "+++": S12 is in 20 ms units: 50 × 20 ms = 1 s
         regs[12] = 50;
 
+        // S91 – transmit level in -dBm0: -10 dBm0
+        regs[91] = 10;
+
         regs
     }
 
-    fn new_internal() -> Self {
+    fn new_internal(sample_rate: f32) -> Self {
         let state = Arc::new(Mutex::new(ModemState::Command));
 
-        // Start in Bell 103 originate, 300 baud, using global SAMPLE_RATE.
+        // Start in Bell 103 originate, 300 baud.
         let fsk_mode = FSKMode::Bell103Originate;
         let baud_rate = 300.0_f32;
-        let sample_rate = SAMPLE_RATE;
 
         let modulator = FSKModulator::new(fsk_mode, baud_rate, sample_rate);
         let demodulator = FSKDemodulator::new(fsk_mode, baud_rate, sample_rate);
@@ -81,23 +138,40 @@ impl VirtualModem {
         let qam_modulator = Some(QAMModulator::new(qam_mode, carrier, sample_rate));
         let qam_demodulator = Some(QAMDemodulator::new(qam_mode, carrier, sample_rate));
 
-        Self {
+        let s_registers = Self::default_s_registers();
+        let (low, high) = Self::receive_band(ModemMode::Bell103);
+        let carrier_detector = CarrierDetector::new(low, high, sample_rate, Self::carrier_config(&s_registers));
+
+        let mut modem = Self {
             state,
             parser: ATCommandParser::new(),
-            s_registers: Self::default_s_registers(),
+            s_registers,
             audio_engine: None,
+            capture_pending: false,
             modulator,
             demodulator,
             qam_modulator,
             qam_demodulator,
             current_mode: ModemMode::Bell103,
             connection_speed: 300,
+            full_scale_dbm0: FULL_SCALE_DBM0,
             connected: false,
+            connect_deadline: None,
+            carrier_detector,
             escape_sequence_time: None,
             plus_count: 0,
             tx_buffer: Vec::new(),
             rx_buffer: Vec::new(),
-        }
+            unsolicited: Vec::new(),
+            sample_rate,
+            line_samples: 0,
+            dtr: true,
+            dtr_before_fault: None,
+            faults: FaultInjector::new(sample_rate),
+            events: Vec::new(),
+        };
+        modem.apply_tx_level();
+        modem
     }
 
     fn reconfigure_for_speed(&mut self, speed: u32) {
@@ -110,7 +184,7 @@ impl VirtualModem {
         };
 
         let baud_rate = speed as f32;
-        let sample_rate = SAMPLE_RATE;
+        let sample_rate = self.sample_rate;
 
         // FSK chain for 300 baud and generic high-speed fallback.
         let fsk_mode = match self.current_mode {
@@ -135,11 +209,68 @@ impl VirtualModem {
             self.qam_modulator = Some(QAMModulator::new(qam_mode, carrier, sample_rate));
             self.qam_demodulator = Some(QAMDemodulator::new(qam_mode, carrier, sample_rate));
         }
+
+        let (low, high) = Self::receive_band(self.current_mode);
+        self.carrier_detector =
+            CarrierDetector::new(low, high, sample_rate, Self::carrier_config(&self.s_registers));
+        self.apply_tx_level();
+    }
+
+    /// Band the receiver listens on in `mode`, for carrier detection
+    fn receive_band(mode: ModemMode) -> (f32, f32) {
+        let qam_mode = match mode {
+            ModemMode::Bell103 => {
+                let (space, mark) = FSKMode::Bell103Originate.frequencies();
+                return (space.min(mark) - 300.0, space.max(mark) + 300.0);
+            }
+            ModemMode::V22 => QAMMode::V22,
+            ModemMode::V22bis => QAMMode::V22bis,
+            ModemMode::Bell212A => QAMMode::Bell212A,
+        };
+        let carrier = qam_mode.carrier_freq_originate();
+        (carrier - qam_mode.symbol_rate(), carrier + qam_mode.symbol_rate())
+    }
+
+    /// Carrier detector settings from S9 and S10
+    fn carrier_config(s_registers: &[u8; 256]) -> CarrierDetectorConfig {
+        CarrierDetectorConfig::from_s_registers(s_registers[9], s_registers[10])
+    }
+
+    /// Transmit level from S91, for every signal the modem sends
+    pub fn tx_level(&self) -> TxLevel {
+        TxLevel::with_reference(-(self.s_registers[91] as f32), self.full_scale_dbm0)
+    }
+
+    /// Calibrate S91: the level in dBm0 of a full-scale sine on the audio
+    /// path (default that of the u-law digital milliwatt)
+    pub fn set_full_scale_reference(&mut self, full_scale_dbm0: f32) {
+        self.full_scale_dbm0 = full_scale_dbm0;
+        self.apply_tx_level();
+    }
+
+    fn apply_tx_level(&mut self) {
+        let level = self.tx_level();
+        self.modulator.set_tx_level(level);
+        if let Some(ref mut modulator) = self.qam_modulator {
+            modulator.set_tx_level(level);
+        }
     }
 
     /// Public constructor used by tests and TAPI layer.
     pub fn new() -> Result<Self, String> {
-        Ok(Self::new_internal())
+        Self::with_sample_rate(SAMPLE_RATE)
+    }
+
+    /// Modem whose line audio runs at `sample_rate`
+    pub fn with_sample_rate(sample_rate: f32) -> Result<Self, String> {
+        if sample_rate.is_nan() || sample_rate <= 0.0 {
+            return Err(format!("sample rate must be positive, got {}", sample_rate));
+        }
+        Ok(Self::new_internal(sample_rate))
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
     }
 
     pub fn get_state(&self) -> ModemState {
@@ -147,7 +278,10 @@ impl VirtualModem {
     }
 
     pub fn init_audio(&mut self) -> Result<(), String> {
-        let config = ModemAudioConfig::default();
+        let config = ModemAudioConfig {
+            sample_rate: self.sample_rate as u32,
+            ..ModemAudioConfig::default()
+        };
         let mut engine = WasapiAudioEngine::new(config)
             .map_err(|e| format!("Audio engine creation failed: {}", e))?;
 
@@ -173,17 +307,11 @@ impl VirtualModem {
             }
             ATCommand::Dial(number) => {
                 log::info!("Dialing: {}", number);
-                *self.state.lock().unwrap() = ModemState::Connecting;
-                self.connected = true;
-                // For now, assume fast connect at current speed.
-                *self.state.lock().unwrap() = ModemState::Connected;
-                responses.push(ATResponse::Connect(self.connection_speed));
+                responses.extend(self.start_connecting());
             }
             ATCommand::Answer => {
                 log::info!("Answering call");
-                *self.state.lock().unwrap() = ModemState::Connected;
-                self.connected = true;
-                responses.push(ATResponse::Connect(self.connection_speed));
+                responses.extend(self.start_connecting());
             }
             ATCommand::Hangup => {
                 self.hangup();
@@ -228,8 +356,16 @@ impl VirtualModem {
                 responses.push(ATResponse::Ok);
             }
             ATCommand::SetRegister(r, v) => {
-                if (r as usize) < self.s_registers.len() {
+                if r == 91 && !TX_LEVEL_RANGE.contains(&v) {
+                    responses.push(ATResponse::Error);
+                } else if (r as usize) < self.s_registers.len() {
                     self.s_registers[r as usize] = v;
+                    if r == 9 || r == 10 {
+                        self.carrier_detector.set_config(Self::carrier_config(&self.s_registers));
+                    }
+                    if r == 91 {
+                        self.apply_tx_level();
+                    }
                     responses.push(ATResponse::Ok);
                 } else {
                     responses.push(ATResponse::Error);
@@ -283,7 +419,8 @@ impl VirtualModem {
 
     /// Move pending TX bytes from host into an audio sample buffer for playback.
     pub fn process_tx_queue(&mut self) -> Vec<f32> {
-        if self.tx_buffer.is_empty() {
+        // A stalled audio engine takes nothing; the bytes wait in tx_buffer.
+        if self.tx_buffer.is_empty() || self.faults.audio_stalled() {
             return Vec::new();
         }
 
@@ -301,26 +438,109 @@ impl VirtualModem {
         }
     }
 
-    /// Pull any captured audio from the engine and return newly demodulated bytes.
+    /// Feed received line audio through the demodulator, advancing the line
+    /// clock and applying any scheduled faults. Returns the decoded bytes.
+    pub fn receive_samples(&mut self, samples: &[f32]) -> Vec<u8> {
+        let mut line = Vec::with_capacity(samples.len());
+
+        // Where in `line` a bit error burst takes over the demodulated bits
+        let mut burst_from = (self.faults.bit_errors_pending() > 0).then_some(0);
+
+        for &sample in samples {
+            if !self.faults.is_idle() {
+                self.service_faults();
+                if burst_from.is_none() && self.faults.bit_errors_pending() > 0 {
+                    burst_from = Some(line.len());
+                }
+            }
+            self.line_samples += 1;
+
+            // A stalled engine never delivers these samples at all
+            if self.faults.audio_stalled() {
+                continue;
+            }
+            let sample = if self.faults.carrier_dropped() { 0.0 } else { sample };
+            line.push(sample);
+
+            // Sample by sample, so every change is seen at its own line time
+            if self.carrier_detector.process_sample(sample) {
+                self.carrier_changed(self.carrier_detector.state());
+            }
+        }
+
+        if self.connect_deadline.is_some_and(|deadline| self.line_samples >= deadline) {
+            log::info!("No carrier within S7");
+            self.hangup();
+            self.unsolicited.push(ATResponse::NoCarrier);
+        }
+
+        // Burst errors hit the line bits, so the deframer sees them as a
+        // real line hit: framing errors, lost and spurious characters
+        let split = burst_from.unwrap_or(line.len());
+        let mut bits = self.demodulate(&line[..split]);
+        let mut burst = self.demodulate(&line[split..]);
+        self.faults.corrupt(&mut burst);
+        bits.append(&mut burst);
+        self.deframe(&bits)
+    }
+
+    fn demodulate(&mut self, line: &[f32]) -> Vec<bool> {
+        match self.current_mode {
+            ModemMode::V22 | ModemMode::V22bis | ModemMode::Bell212A => {
+                if let Some(ref mut demod) = self.qam_demodulator {
+                    demod.demodulate(line)
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => self.demodulator.demodulate(line),
+        }
+    }
+
+    fn deframe(&mut self, bits: &[bool]) -> Vec<u8> {
+        match self.current_mode {
+            ModemMode::V22 | ModemMode::V22bis | ModemMode::Bell212A => {
+                if let Some(ref mut demod) = self.qam_demodulator {
+                    demod.deframe_bytes(bits)
+                } else {
+                    Vec::new()
+                }
+            }
+            _ => self.demodulator.deframe_bytes(bits),
+        }
+    }
+
+    /// Character, parity, framing error and break counts of the receiver
+    /// in use
+    pub fn framing_stats(&self) -> FramingStats {
+        match (self.current_mode, &self.qam_demodulator) {
+            (ModemMode::V22 | ModemMode::V22bis | ModemMode::Bell212A, Some(demod)) => {
+                *demod.framing_stats()
+            }
+            _ => *self.demodulator.framing_stats(),
+        }
+    }
+
+    /// Pull any captured audio from the engine and return newly demodulated
+    /// bytes, then ask the engine for the next capture buffer. Call it
+    /// regularly: the line clock (DCD, S7) only runs on captured audio.
     pub fn process_audio(&mut self) -> Vec<u8> {
         if let Some(ref engine) = self.audio_engine {
             let events = engine.poll_events();
             for event in events {
                 if let AudioEvent::CapturedSamples(samples) = event {
-                    let bytes = match self.current_mode {
-                        ModemMode::V22 | ModemMode::V22bis | ModemMode::Bell212A => {
-                            if let Some(ref mut demod) = self.qam_demodulator {
-                                demod.demodulate_bytes(&samples)
-                            } else {
-                                Vec::new()
-                            }
-                        }
-                        _ => self.demodulator.demodulate_bytes(&samples),
-                    };
+                    self.capture_pending = false;
+                    let bytes = self.receive_samples(&samples);
                     self.rx_buffer.extend(bytes);
                 }
             }
         }
+        if let Some(ref engine) = self.audio_engine {
+            if !self.capture_pending {
+                engine.request_capture();
+                self.capture_pending = true;
+            }
+        }
 
         let data = self.rx_buffer.clone();
         self.rx_buffer.clear();
@@ -335,17 +555,161 @@ impl VirtualModem {
     }
 
     pub fn hangup(&mut self) {
+        if self.connected {
+            self.log_event(ModemEventKind::HungUp);
+        }
         self.connected = false;
+        self.connect_deadline = None;
         *self.state.lock().unwrap() = ModemState::Command;
     }
 
     pub fn reset(&mut self) {
         self.hangup();
         self.s_registers = Self::default_s_registers();
+        self.carrier_detector.set_config(Self::carrier_config(&self.s_registers));
+        self.apply_tx_level();
         self.parser = ATCommandParser::new();
         self.tx_buffer.clear();
         self.rx_buffer.clear();
         self.escape_sequence_time = None;
         self.plus_count = 0;
     }
+
+    /// Host DTR line. Dropping it while connected hangs up (AT&D2 behaviour).
+    pub fn set_dtr(&mut self, asserted: bool) {
+        if asserted == self.dtr {
+            return;
+        }
+        self.dtr = asserted;
+        self.log_event(ModemEventKind::DtrChanged(asserted));
+        if !asserted {
+            self.hangup();
+        }
+    }
+
+    pub fn dtr(&self) -> bool {
+        self.dtr
+    }
+
+    /// Data carrier detect, from the receive-band level with S9/S10 timing
+    pub fn dcd(&self) -> bool {
+        self.carrier_detector.is_present()
+    }
+
+    /// Receive-band level seen by the carrier detector
+    pub fn line_level_dbm0(&self) -> f32 {
+        self.carrier_detector.level_dbm0()
+    }
+
+    /// Drain result codes raised by the line rather than by a command
+    pub fn take_unsolicited(&mut self) -> Vec<ATResponse> {
+        std::mem::take(&mut self.unsolicited)
+    }
+
+    /// Go off hook and wait up to S7 for the far end's carrier. CONNECT
+    /// comes now if DCD is already up, otherwise from the line (see
+    /// `take_unsolicited`), as does NO CARRIER once S7 runs out.
+    fn start_connecting(&mut self) -> Option<ATResponse> {
+        *self.state.lock().unwrap() = ModemState::Connecting;
+        if self.carrier_detector.is_present() {
+            return Some(self.finish_connecting());
+        }
+        let wait = (self.s_registers[7] as f64 * self.sample_rate as f64) as u64;
+        self.connect_deadline = Some(self.line_samples + wait);
+        None
+    }
+
+    fn finish_connecting(&mut self) -> ATResponse {
+        self.connect_deadline = None;
+        self.connected = true;
+        *self.state.lock().unwrap() = ModemState::Connected;
+        self.log_event(ModemEventKind::Connected(self.connection_speed));
+        ATResponse::Connect(self.connection_speed)
+    }
+
+    /// DCD while dialing or answering connects; losing carrier for S10
+    /// while connected hangs up with NO CARRIER
+    fn carrier_changed(&mut self, event: CarrierEvent) {
+        match event {
+            CarrierEvent::Detected => {
+                self.log_event(ModemEventKind::CarrierDetected);
+                if self.connect_deadline.is_some() {
+                    let connect = self.finish_connecting();
+                    self.unsolicited.push(connect);
+                }
+            }
+            CarrierEvent::Lost => {
+                self.log_event(ModemEventKind::CarrierLost);
+                if self.connected {
+                    self.hangup();
+                    self.unsolicited.push(ATResponse::NoCarrier);
+                }
+            }
+        }
+    }
+
+    /// Time on the line clock: received samples / sample rate
+    pub fn line_time(&self) -> Duration {
+        Duration::from_secs_f64(self.line_samples as f64 / self.sample_rate as f64)
+    }
+
+    /// Schedule `fault` to start at line time `at`. Faults in the past
+    /// start on the next received sample.
+    pub fn schedule_fault(&mut self, at: Duration, fault: Fault) {
+        let at_sample = self.faults.to_samples(at);
+        self.faults.schedule(at_sample, fault);
+    }
+
+    /// Inject `fault` on the next received sample
+    pub fn inject_fault(&mut self, fault: Fault) {
+        self.faults.schedule(self.line_samples, fault);
+    }
+
+    /// Drop all scheduled and active faults without logging them. DTR
+    /// goes back to where the host had it if a DTR drop was active.
+    pub fn clear_faults(&mut self) {
+        self.faults.clear();
+        if let Some(dtr) = self.dtr_before_fault.take() {
+            self.set_dtr(dtr);
+        }
+    }
+
+    pub fn events(&self) -> &[ModemEvent] {
+        &self.events
+    }
+
+    /// Drain the event log
+    pub fn take_events(&mut self) -> Vec<ModemEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn log_event(&mut self, kind: ModemEventKind) {
+        let at = self.line_time();
+        log::info!("Modem event at {:?}: {:?}", at, kind);
+        self.events.push(ModemEvent { at, kind });
+    }
+
+    fn service_faults(&mut self) {
+        for transition in self.faults.advance(self.line_samples) {
+            match transition {
+                FaultTransition::Started(fault) => {
+                    self.log_event(ModemEventKind::FaultInjected(fault));
+                    if let Fault::DtrDrop { .. } = fault {
+                        self.dtr_before_fault.get_or_insert(self.dtr);
+                        self.set_dtr(false);
+                    }
+                }
+                FaultTransition::Cleared(fault) => {
+                    self.log_event(ModemEventKind::FaultCleared(fault));
+                    if let Fault::DtrDrop { .. } = fault {
+                        if !self.faults.dtr_dropped() {
+                            if let Some(dtr) = self.dtr_before_fault.take() {
+                                self.set_dtr(dtr);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
 }
@@ -7,12 +7,15 @@
 // - ModemPipeServer::new() -> io::Result<Self>
 // - server.run() -> io::Result<()>
 //
-// It also uses the windows 0.58 safe wrapper signatures correctly,
-// avoids overlapped I/O, and bridges text -> ATCommand -> ATResponse.
+// It also uses the windows 0.58 safe wrapper signatures correctly and
+// avoids overlapped I/O. Reads poll with PeekNamedPipe so the session can
+// keep the line audio moving while the client is quiet; the AT handling
+// itself is `ModemSession`.
 
 use std::ffi::OsStr;
 use std::io;
 use std::os::windows::ffi::OsStrExt;
+use std::time::Duration;
 
 use log::{debug, info};
 
@@ -22,12 +25,40 @@ use windows::Win32::Foundation::{
 };
 use windows::Win32::Storage::FileSystem::{ReadFile, WriteFile, FILE_FLAGS_AND_ATTRIBUTES};
 use windows::Win32::System::Pipes::{
-    ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PIPE_READMODE_MESSAGE, PIPE_TYPE_MESSAGE,
-    PIPE_WAIT,
+    ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe, PeekNamedPipe, PIPE_READMODE_MESSAGE,
+    PIPE_TYPE_MESSAGE, PIPE_WAIT,
 };
 
-use crate::tapi::at_commands::ATCommandParser;
+use crate::audio::ModemAudioConfig;
 use crate::tapi::modem::VirtualModem;
+use crate::tapi::session::{ClientStream, ModemSession};
+
+/// Connected pipe client, read without blocking
+struct PipeClient {
+    handle: HANDLE,
+}
+
+impl ClientStream for PipeClient {
+    fn try_read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        let mut available: u32 = 0;
+        if let Err(e) = unsafe { PeekNamedPipe(self.handle, None, 0, None, Some(&mut available), None) } {
+            return if unsafe { GetLastError() } == ERROR_BROKEN_PIPE {
+                Ok(Some(0))
+            } else {
+                Err(io::Error::other(format!("{e:?}")))
+            };
+        }
+        if available == 0 {
+            return Ok(None);
+        }
+        let n = ModemPipeServer::read_client(self.handle, buf).map_err(|e| io::Error::other(format!("{e:?}")))?;
+        Ok(Some(n as usize))
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        ModemPipeServer::write_client(self.handle, data).map_err(|e| io::Error::other(format!("{e:?}")))
+    }
+}
 
 pub struct ModemPipeServer {
     modem: VirtualModem,
@@ -43,12 +74,12 @@ impl ModemPipeServer {
         // Assume the v0.0.x VirtualModem constructor shape:
         // Result<Self, String>
         let mut modem = VirtualModem::new()
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            .map_err(io::Error::other)?;
 
         // If your v0.0.2 modem still has init_audio(), this keeps behavior aligned
         // with earlier revisions. If it was removed, comment out this block.
         if let Err(e) = modem.init_audio() {
-            return Err(io::Error::new(io::ErrorKind::Other, e));
+            return Err(io::Error::other(e));
         }
 
         Ok(Self {
@@ -149,81 +180,26 @@ impl ModemPipeServer {
 
         loop {
             let handle = self.create_pipe()
-                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{e:?}")))?;
+                .map_err(|e| io::Error::other(format!("{e:?}")))?;
 
             debug!("Waiting for pipe client...");
             if let Err(e) = Self::connect_pipe(handle) {
-                unsafe { CloseHandle(handle); }
-                return Err(io::Error::new(io::ErrorKind::Other, format!("{e:?}")));
+                unsafe { CloseHandle(handle).ok(); }
+                return Err(io::Error::other(format!("{e:?}")));
             }
 
             debug!("Pipe client connected.");
 
-            let mut rx = vec![0u8; self.in_buf_size as usize];
-            let mut line_buf = Vec::<u8>::with_capacity(512);
-
-            // Per-client read loop
-            loop {
-                let n = Self::read_client(handle, &mut rx)
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{e:?}")))? as usize;
-
-                if n == 0 {
-                    debug!("Client disconnected.");
-                    break;
-                }
-
-                line_buf.extend_from_slice(&rx[..n]);
-
-                // AT lines are usually CR or CRLF terminated.
-                while let Some(pos) = line_buf.iter().position(|&b| b == b'\r' || b == b'\n') {
-                    let mut raw = line_buf.drain(..=pos).collect::<Vec<u8>>();
-
-                    while matches!(raw.last(), Some(b'\r' | b'\n')) {
-                        raw.pop();
-                    }
-
-                    if raw.is_empty() {
-                        continue;
-                    }
-
-                    let cmd_line = String::from_utf8_lossy(&raw).to_string();
-                    debug!("RX AT line: {:?}", cmd_line);
-
-                    // Parse -> execute -> collect responses
-                    let mut parser = ATCommandParser::new();
-                    let commands = parser.parse_command_line(&cmd_line);
-
-                    let mut responses = Vec::new();
-                    for c in commands {
-                        responses.extend(self.modem.process_command(c));
-                    }
-
-                    // Serialize responses to wire text
-                    let mut out = String::new();
-                    for r in &responses {
-                        out.push_str(&r.to_string());
-                        out.push_str("\r\n");
-                    }
-
-                    // Ensure at least one line terminator
-                    if out.is_empty() {
-                        out.push_str("OK\r\n");
-                    }
-
-                    Self::write_client(handle, out.as_bytes())
-                        .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{e:?}")))?;
-                }
-
-                // Guard against runaway growth if a client sends no line breaks.
-                if line_buf.len() > 64 * 1024 {
-                    line_buf.clear();
-                }
-            }
+            // Poll once per capture buffer, so the line runs in real time
+            let poll_interval = Duration::from_millis(ModemAudioConfig::default().buffer_duration_ms as u64);
+            let mut session = ModemSession::new(poll_interval);
+            let served = session.run(&mut self.modem, &mut PipeClient { handle });
 
             unsafe {
                 DisconnectNamedPipe(handle).ok();
-                CloseHandle(handle);
+                CloseHandle(handle).ok();
             }
+            served?;
         }
     }
 }
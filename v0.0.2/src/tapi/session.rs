@@ -0,0 +1,121 @@
+// src/tapi/session.rs
+//
+// One client's AT session over a byte stream. Between reads it pumps the
+// line audio through the modem, so DCD, S7 and the result codes they
+// raise (CONNECT, NO CARRIER) move on while the client waits, and those
+// codes go out as soon as they are queued. The named-pipe server runs it
+// on Windows; the tests run it against an in-memory client.
+
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+use log::debug;
+
+use super::at_commands::{ATCommandParser, ATResponse, ModemState};
+use super::modem::VirtualModem;
+
+/// Command line bytes kept while waiting for a terminator; a client that
+/// sends no line breaks loses them past this
+const MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Byte stream to a connected client
+pub trait ClientStream {
+    /// Bytes the client has sent, without waiting for more: `None` if
+    /// nothing is waiting, `Some(0)` once the client has gone
+    fn try_read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>>;
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()>;
+}
+
+pub struct ModemSession {
+    poll_interval: Duration,
+    line_buf: Vec<u8>,
+}
+
+impl ModemSession {
+    /// Session that sleeps `poll_interval` whenever the client is quiet;
+    /// one capture buffer's length keeps the line clock near real time
+    pub fn new(poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            line_buf: Vec::with_capacity(512),
+        }
+    }
+
+    /// Serve `client` until it disconnects
+    pub fn run<C: ClientStream>(&mut self, modem: &mut VirtualModem, client: &mut C) -> io::Result<()> {
+        let mut rx = [0u8; 4096];
+        loop {
+            self.poll_line(modem, client)?;
+            match client.try_read(&mut rx)? {
+                Some(0) => {
+                    debug!("Client disconnected.");
+                    return Ok(());
+                }
+                Some(n) => self.receive(modem, client, &rx[..n])?,
+                None => thread::sleep(self.poll_interval),
+            }
+        }
+    }
+
+    /// Run captured line audio through the modem; pass on the data it
+    /// decoded and any result codes the line raised
+    fn poll_line<C: ClientStream>(&mut self, modem: &mut VirtualModem, client: &mut C) -> io::Result<()> {
+        let data = modem.process_audio();
+        if !data.is_empty() {
+            client.write_all(&data)?;
+        }
+        let unsolicited = modem.take_unsolicited();
+        if !unsolicited.is_empty() {
+            client.write_all(wire_text(&unsolicited).as_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Bytes from the client: run each complete AT line and answer it
+    fn receive<C: ClientStream>(&mut self, modem: &mut VirtualModem, client: &mut C, data: &[u8]) -> io::Result<()> {
+        self.line_buf.extend_from_slice(data);
+
+        // AT lines are usually CR or CRLF terminated.
+        while let Some(pos) = self.line_buf.iter().position(|&b| b == b'\r' || b == b'\n') {
+            let mut raw = self.line_buf.drain(..=pos).collect::<Vec<u8>>();
+            while matches!(raw.last(), Some(b'\r' | b'\n')) {
+                raw.pop();
+            }
+            if raw.is_empty() {
+                continue;
+            }
+
+            let cmd_line = String::from_utf8_lossy(&raw).to_string();
+            debug!("RX AT line: {:?}", cmd_line);
+
+            // Parse -> execute -> collect responses
+            let mut parser = ATCommandParser::new();
+            let mut responses = Vec::new();
+            for c in parser.parse_command_line(&cmd_line) {
+                responses.extend(modem.process_command(c));
+            }
+
+            // A dial or answer still waiting on the line answers later,
+            // from `poll_line`; anything else gets at least OK
+            if responses.is_empty() {
+                if modem.get_state() == ModemState::Connecting {
+                    continue;
+                }
+                responses.push(ATResponse::Ok);
+            }
+            client.write_all(wire_text(&responses).as_bytes())?;
+        }
+
+        if self.line_buf.len() > MAX_LINE_LEN {
+            self.line_buf.clear();
+        }
+        Ok(())
+    }
+}
+
+/// Result codes as sent to the client, each on its own line
+fn wire_text(responses: &[ATResponse]) -> String {
+    responses.iter().map(|r| r.to_string()).collect()
+}
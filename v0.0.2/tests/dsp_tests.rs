@@ -2,9 +2,19 @@
 use hsf_softmodem::dsp::*;
 use hsf_softmodem::dsp::oscillator::*;
 use hsf_softmodem::dsp::filters::*;
+use hsf_softmodem::dsp::fir::{self, FirFilter, Window};
+use hsf_softmodem::dsp::interpolator::{Interpolator, Resampler};
 use hsf_softmodem::dsp::goertzel::*;
+use hsf_softmodem::dsp::hilbert::{AnalyticSignal, FrequencyDiscriminator};
 use hsf_softmodem::dsp::fsk::*;
+use hsf_softmodem::dsp::fsk_detector::FSKDetector;
+use hsf_softmodem::dsp::qam::QAMMode;
+use hsf_softmodem::dsp::qam_modem::QAMModulator;
+use hsf_softmodem::dsp::framing::{AsyncChar, AsyncDeframer, AsyncFramer, CharFormat, Parity};
+use hsf_softmodem::dsp::agc::{AGCConfig, AGC};
+use hsf_softmodem::dsp::carrier::{CarrierDetector, CarrierDetectorConfig, CarrierEvent};
 use approx::assert_relative_eq;
+use num_complex::Complex32;
 use std::f32::consts::PI;
 
 #[test]
@@ -20,6 +30,126 @@ fn test_nco_frequency_generation() {
     assert!(samples[6] < -0.9);       // Trough near -1.0
 }
 
+#[test]
+fn test_nco_sine_table_accuracy() {
+    for (interpolation, tolerance) in [(SineInterpolation::Linear, 5e-7), (SineInterpolation::Nearest, 8e-4)] {
+        let mut nco = NCO::new(1234.567, 8000.0, 1.0);
+        nco.set_interpolation(interpolation);
+        let mut block = vec![0.0; 5000];
+        let mut reference = NCO::new(1234.567, 8000.0, 1.0);
+        reference.set_interpolation(interpolation);
+        nco.generate(&mut block);
+        for &y in &block {
+            // Against the exact sine of the phase word
+            let phase = reference.phase_word() as f64 / 4294967296.0 * std::f64::consts::TAU;
+            let x = reference.next();
+            assert_eq!(x.to_bits(), y.to_bits());
+            assert!((x as f64 - phase.sin()).abs() < tolerance, "{:?} at {}: {}", interpolation, phase, x);
+        }
+    }
+}
+
+#[test]
+fn test_nco_phase_continuous_across_frequency_changes() {
+    let sample_rate = 8000.0;
+    let mut nco = NCO::new(1070.0, sample_rate, 1.0);
+    let mut expected_phase = 0u32;
+    let mut previous = 0.0f32;
+    // Bell 103 mark/space switching at uneven sample counts
+    for (run, &freq) in [1270.0, 1070.0, 1270.0, 1070.0, 2225.0, 2025.0].iter().cycle().take(60).enumerate() {
+        nco.set_frequency(freq);
+        let step = phase_increment(freq, sample_rate);
+        for _ in 0..(17 + run * 7) {
+            assert_eq!(nco.phase_word(), expected_phase);
+            let sample = nco.next();
+            // No jump bigger than the fastest tone's slope allows
+            assert!((sample - previous).abs() <= 2.0 * PI * 2225.0 / sample_rate + 1e-6);
+            previous = sample;
+            expected_phase = expected_phase.wrapping_add(step);
+        }
+    }
+}
+
+#[test]
+fn test_nco_phase_and_frequency_accessors() {
+    let mut nco = NCO::new(1800.0, 8000.0, 1.0);
+    assert!((nco.frequency() - 1800.0).abs() < 1e-5);
+    nco.set_frequency(-300.0);
+    assert!((nco.frequency() + 300.0).abs() < 1e-5);
+
+    nco.set_phase(PI / 2.0);
+    assert!((nco.phase() - PI / 2.0).abs() < 1e-6);
+    assert!((nco.next() - 1.0).abs() < 1e-6);
+    // Wrapped into [0, 2π)
+    nco.set_phase(-PI / 2.0);
+    assert!((nco.phase() - 1.5 * PI).abs() < 1e-6);
+    nco.set_phase(5.0 * PI);
+    assert!((nco.phase() - PI).abs() < 1e-6);
+
+    // Handing phase over exactly, as a coherent receiver would
+    let mut other = NCO::new(1800.0, 8000.0, 1.0);
+    other.set_phase_word(nco.phase_word());
+    other.set_frequency(-300.0);
+    for _ in 0..100 {
+        assert_eq!(nco.next().to_bits(), other.next().to_bits());
+    }
+    nco.reset();
+    assert_eq!(nco.phase_word(), 0);
+}
+
+#[test]
+fn test_complex_nco_quadrature() {
+    let mut nco = ComplexNCO::new(1000.0, 8000.0);
+    let phasors: Vec<_> = (0..8).map(|_| nco.next_phasor()).collect();
+
+    // cos/sin pair on the unit circle, sin lagging cos by a quarter cycle
+    assert!(phasors.iter().all(|p| (p.norm() - 1.0).abs() < 1e-6));
+    assert_relative_eq!(phasors[0].re, 1.0);
+    assert_relative_eq!(phasors[2].im, 1.0, epsilon = 1e-6);
+    assert_relative_eq!(phasors[2].re, 0.0, epsilon = 1e-6);
+
+    // Mixing a carrier at the NCO frequency down leaves DC
+    let mut tone = ComplexNCO::new(1000.0, 8000.0);
+    tone.set_phase(PI / 3.0);
+    let mut lo = ComplexNCO::new(1000.0, 8000.0);
+    let mean = (0..800)
+        .map(|_| lo.mix_down(tone.next_phasor().re))
+        .sum::<num_complex::Complex32>()
+        / 800.0;
+    assert_relative_eq!(mean.arg(), PI / 3.0, epsilon = 1e-3);
+    assert_relative_eq!(mean.norm(), 0.5, epsilon = 1e-3);
+
+    lo.adjust_phase(-3.0 * PI);
+    assert!(lo.phase() >= 0.0 && lo.phase() < 2.0 * PI);
+    lo.set_frequency(1500.0);
+    assert_relative_eq!(lo.frequency(), 1500.0, epsilon = 1e-2);
+}
+
+#[test]
+fn test_complex_nco_keeps_exact_phase() {
+    // An hour of 8 kHz samples: a float phase would have drifted by now
+    let mut nco = ComplexNCO::new(1200.0, 8000.0);
+    let mut sine = NCO::new(1200.0, 8000.0, 1.0);
+    let step = phase_increment(1200.0, 8000.0);
+    let samples = 3600 * 8000u32;
+    for _ in 0..samples {
+        nco.step();
+    }
+    assert_eq!(nco.phase_word(), step.wrapping_mul(samples));
+
+    // The same table as the real NCO, with cos a quarter turn ahead
+    nco.reset();
+    for _ in 0..64 {
+        let phasor = nco.next_phasor();
+        assert_eq!(phasor.im.to_bits(), sine.next().to_bits());
+        assert!((phasor.norm() - 1.0).abs() < 1e-6);
+    }
+    nco.set_phase_word(0);
+    nco.adjust_phase(-PI / 2.0);
+    // f32 π/2 is a few parts in 1e8 off
+    assert!((nco.phase_word() as i64 - (3 << 30)).abs() < 64, "{}", nco.phase_word());
+}
+
 #[test]
 fn test_biquad_lowpass_filter() {
     let mut filter = BiquadFilter::lowpass(1000.0, 0.707, 8000.0);
@@ -52,6 +182,134 @@ fn test_biquad_bandpass_filter() {
     assert!(rms_output.sqrt() > 0.5);
 }
 
+#[test]
+fn test_fir_streaming_matches_convolution() {
+    let taps = [0.5, -0.25, 0.125, 1.0, 0.0625];
+    let input: Vec<f32> = (0..40).map(|n| ((n * 7 % 11) as f32 - 5.0) / 5.0).collect();
+
+    let mut filter = FirFilter::new(&taps);
+    let mut output = vec![0.0; input.len()];
+    filter.process_block(&input, &mut output);
+
+    for n in 0..input.len() {
+        let expected: f32 = (0..taps.len())
+            .filter(|&k| k <= n)
+            .map(|k| taps[k] * input[n - k])
+            .sum();
+        assert_relative_eq!(output[n], expected, epsilon = 1e-6);
+    }
+}
+
+#[test]
+fn test_fir_windowed_sinc_designs() {
+    let lp = fir::lowpass(101, 1000.0, 8000.0, Window::Blackman);
+    assert_relative_eq!(fir::magnitude_response(&lp, 0.0, 8000.0), 1.0, epsilon = 1e-4);
+    assert!(fir::magnitude_response(&lp, 500.0, 8000.0) > 0.99);
+    assert!(fir::magnitude_response(&lp, 1500.0, 8000.0) < 0.01);
+
+    let hp = fir::highpass(101, 1000.0, 8000.0, Window::Hamming);
+    assert!(fir::magnitude_response(&hp, 300.0, 8000.0) < 0.01);
+    assert!(fir::magnitude_response(&hp, 2500.0, 8000.0) > 0.98);
+
+    let bp = fir::bandpass(129, 900.0, 1500.0, 8000.0, Window::Kaiser(Window::kaiser_beta(60.0)));
+    assert_relative_eq!(fir::magnitude_response(&bp, 1200.0, 8000.0), 1.0, epsilon = 1e-4);
+    assert!(fir::magnitude_response(&bp, 2400.0, 8000.0) < 0.01);
+}
+
+#[test]
+fn test_rrc_matched_pair_has_no_isi() {
+    let sps = 13;
+    let symbol_rate = 8000.0 / sps as f32;
+    let rrc = fir::root_raised_cosine(symbol_rate, 8000.0, 0.75, 8);
+    assert_eq!(rrc.len() % 2, 1);
+
+    // RRC into its matched filter is a raised cosine
+    let mut matched = FirFilter::new(&rrc);
+    let mut impulse = vec![0.0; 2 * rrc.len()];
+    impulse[0] = 1.0;
+    let cascade: Vec<f32> = impulse.iter().map(|&x| matched.process(x)).collect();
+    let mut pair = FirFilter::new(&rrc);
+    let pulse: Vec<f32> = cascade.iter().map(|&x| pair.process(x)).collect();
+
+    let peak = rrc.len() - 1;
+    assert_relative_eq!(pulse[peak], 1.0, epsilon = 1e-3);
+    for k in 1..4 {
+        assert!(pulse[peak - k * sps].abs() < 0.01, "ISI at -{} symbols", k);
+        assert!(pulse[peak + k * sps].abs() < 0.01, "ISI at +{} symbols", k);
+    }
+
+    // ...and agrees with the directly designed Nyquist pulse
+    let rc = fir::raised_cosine(symbol_rate, 8000.0, 0.75, 8);
+    assert_relative_eq!(rc[rc.len() / 2], 1.0);
+    assert!(rc[rc.len() / 2 + sps].abs() < 1e-4);
+}
+
+#[test]
+fn test_equiripple_lowpass() {
+    let taps = fir::equiripple_lowpass(63, 1000.0, 1500.0, 8000.0, 10.0);
+    assert_eq!(taps.len(), 63);
+
+    let pass_ripple = (0..=20)
+        .map(|i| (fir::magnitude_response(&taps, i as f32 * 50.0, 8000.0) - 1.0).abs())
+        .fold(0.0f32, f32::max);
+    let stop_peak = (0..=50)
+        .map(|i| fir::magnitude_response(&taps, 1500.0 + i as f32 * 50.0, 8000.0))
+        .fold(0.0f32, f32::max);
+
+    assert!(pass_ripple < 0.05, "passband ripple {}", pass_ripple);
+    assert!(stop_peak < 0.01, "stopband peak {}", stop_peak);
+}
+
+#[test]
+fn test_fractional_delay_interpolators() {
+    let tone = |t: f32| (2.0 * PI * 1200.0 * t / 8000.0).sin();
+    let signal: Vec<f32> = (0..64).map(|n| tone(n as f32)).collect();
+
+    for (interpolator, tolerance) in [
+        (Interpolator::cubic(), 2e-2),
+        (Interpolator::polyphase(32, 16), 2e-3),
+    ] {
+        let len = interpolator.window_len();
+        for mu in [0.0, 0.25, 0.5, 0.8, 1.0] {
+            let window = &signal[20..20 + len];
+            let expected = tone(20.0 + (len / 2 - 1) as f32 + mu);
+            let got = interpolator.interpolate(window, mu);
+            assert!(
+                (got - expected).abs() < tolerance,
+                "{:?} mu={}: {} vs {}",
+                interpolator.window_len(),
+                mu,
+                got,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn test_resampler_preserves_frequency() {
+    // 1 s of 1 kHz at 8 kHz, converted to 9.6 kHz
+    let mut nco = NCO::new(1000.0, 8000.0, 1.0);
+    let mut input = vec![0.0; 8000];
+    nco.generate(&mut input);
+
+    let mut resampler = Resampler::between(8000.0, 9600.0, Interpolator::polyphase(32, 16));
+    let output = resampler.process(&input);
+    assert!((output.len() as i32 - 9600).abs() <= 1);
+
+    // Compare with an ideal 1 kHz tone at 9.6 kHz, allowing for the latency
+    let delay = resampler.delay() as f32 / 8000.0;
+    let worst = output[100..9500]
+        .iter()
+        .enumerate()
+        .map(|(n, &y)| {
+            let t = (n + 100) as f32 / 9600.0 - delay;
+            (y - (2.0 * PI * 1000.0 * t).sin()).abs()
+        })
+        .fold(0.0f32, f32::max);
+    assert!(worst < 5e-3, "worst error {}", worst);
+}
+
 #[test]
 fn test_goertzel_tone_detection() {
     let target_freq = 1200.0;
@@ -112,7 +370,7 @@ fn test_dual_tone_detector() {
     detector.reset();
     
     // Test with space frequency (should return false)
-    nco.set_frequency(space_freq, sample_rate);
+    nco.set_frequency(space_freq);
     for _ in 0..block_size {
         detector.process_sample(nco.next());
     }
@@ -134,6 +392,22 @@ fn test_dtmf_generator() {
     assert!(rms.sqrt() > 0.3);
 }
 
+#[test]
+fn test_dtmf_tones_follow_generator_rate() {
+    let sample_rate = 16000.0;
+    let mut gen = DTMFGenerator::new(sample_rate);
+    let tone = gen.generate_digit('5', 1600);
+    
+    // 770 Hz + 1336 Hz at 16 kHz, not at the default rate
+    let energy = |freq: f32| {
+        let mut goertzel = GoertzelDetector::new(freq, sample_rate, tone.len());
+        tone.iter().for_each(|&x| goertzel.process_sample(x));
+        goertzel.magnitude()
+    };
+    assert!(energy(770.0) > 10.0 * energy(385.0));
+    assert!(energy(1336.0) > 10.0 * energy(668.0));
+}
+
 #[test]
 fn test_fsk_loopback_bell103() {
     let mode = FSKMode::Bell103Originate;
@@ -174,8 +448,10 @@ fn test_fsk_byte_loopback() {
     // Test ASCII string
     let test_data = b"HELLO WORLD!";
     
-    // Modulate
-    let audio = modulator.modulate_bytes(test_data);
+    // Modulate, with mark idle to clear the last character through the
+    // receiver
+    let mut audio = modulator.modulate_bytes(test_data);
+    audio.extend(modulator.idle(4));
     
     // Demodulate
     let received_data = demodulator.demodulate_bytes(&audio);
@@ -187,3 +463,321 @@ fn test_fsk_byte_loopback() {
     assert_eq!(test_data, received_slice, 
         "Expected: {:?}, Got: {:?}", test_data, received_slice);
 }
+
+#[test]
+fn test_agc_levels_and_reports_dbm0() {
+    let config = AGCConfig {
+        freeze_during_data: true,
+        ..AGCConfig::default()
+    };
+    let mut agc = AGC::new(config, 8000.0);
+    let mut nco = NCO::new(1000.0, 8000.0, 1.0);
+
+    // Tone 20 dB below full scale comes out at full scale
+    let output: Vec<f32> = (0..8000).map(|_| agc.process(0.1 * nco.next())).collect();
+    let peak = output[7000..].iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    assert_relative_eq!(agc.level_dbm0(), FULL_SCALE_DBM0 - 20.0, epsilon = 0.5);
+    assert_relative_eq!(agc.gain_db(), 20.0, epsilon = 0.5);
+    assert_relative_eq!(peak, 1.0, epsilon = 0.1);
+
+    // Frozen in data mode: the level still tracks, the gain holds
+    agc.set_data_mode(true);
+    assert!(agc.is_frozen());
+    for _ in 0..8000 {
+        agc.process(0.01 * nco.next());
+    }
+    assert_relative_eq!(agc.level_dbm0(), FULL_SCALE_DBM0 - 40.0, epsilon = 0.5);
+    assert_relative_eq!(agc.gain_db(), 20.0, epsilon = 0.5);
+
+    // Released again, it catches up
+    agc.set_data_mode(false);
+    for _ in 0..8000 {
+        agc.process(0.01 * nco.next());
+    }
+    assert_relative_eq!(agc.gain_db(), 40.0, epsilon = 0.5);
+}
+
+/// Sample index of the first carrier state change, if any
+fn first_change(detector: &mut CarrierDetector, samples: &[f32]) -> Option<usize> {
+    samples.iter().position(|&s| detector.process_sample(s))
+}
+
+#[test]
+fn test_carrier_detector_hysteresis_and_timing() {
+    // S9 = 6 (0.6 s), S10 = 14 (1.4 s)
+    let config = CarrierDetectorConfig::from_s_registers(6, 14);
+    let mut detector = CarrierDetector::new(600.0, 1800.0, 8000.0, config);
+    let tone = |dbm0: f32, freq: f32, len: usize| {
+        let mut nco = NCO::new(freq, 8000.0, 10f32.powf((dbm0 - FULL_SCALE_DBM0) / 20.0));
+        (0..len).map(|_| nco.next()).collect::<Vec<f32>>()
+    };
+
+    // Loud out-of-band energy is not carrier
+    assert_eq!(first_change(&mut detector, &tone(-10.0, 2950.0, 16000)), None);
+
+    // In band: reported once the level has held for S9
+    let at = first_change(&mut detector, &tone(-20.0, 1200.0, 16000)).expect("carrier detected");
+    assert!((4790..4900).contains(&at), "detected after {} samples", at);
+    assert!(detector.is_present());
+    assert_relative_eq!(detector.level_dbm0(), -20.0, epsilon = 0.5);
+
+    // Between the off and on thresholds it stays up
+    assert_eq!(first_change(&mut detector, &tone(-45.0, 1200.0, 24000)), None);
+
+    // Dropouts shorter than S10 are ridden through
+    assert_eq!(first_change(&mut detector, &vec![0.0; 8000]), None);
+    assert_eq!(first_change(&mut detector, &tone(-20.0, 1200.0, 800)), None);
+
+    // Silence for S10 loses it
+    assert_eq!(detector.process_block(&vec![0.0; 12000]), vec![CarrierEvent::Lost]);
+    assert!(!detector.is_present());
+
+    // From below, -45 dBm0 is not enough to come up
+    assert_eq!(first_change(&mut detector, &tone(-45.0, 1200.0, 24000)), None);
+}
+
+#[test]
+fn test_carrier_detector_reports_every_change_in_a_block() {
+    // S9 = S10 = 0.1 s
+    let config = CarrierDetectorConfig::from_s_registers(1, 1);
+    let mut detector = CarrierDetector::new(600.0, 1800.0, 8000.0, config);
+    let mut nco = NCO::new(1200.0, 8000.0, 0.1);
+    let mut line: Vec<f32> = (0..4000).map(|_| nco.next()).collect();
+    line.extend(vec![0.0; 4000]);
+    line.extend((0..4000).map(|_| nco.next()));
+
+    // Up, down and up again, all in one call
+    let events = detector.process_block(&line);
+    assert_eq!(events, vec![CarrierEvent::Detected, CarrierEvent::Lost, CarrierEvent::Detected]);
+    assert!(detector.is_present());
+    assert_eq!(detector.state(), CarrierEvent::Detected);
+}
+
+#[test]
+fn test_analytic_signal_mixes_down_without_image() {
+    let sample_rate = 8000.0;
+    let mut tone = NCO::new(1200.0, sample_rate, 1.0);
+    let input: Vec<f32> = (0..4000).map(|_| tone.next()).collect();
+
+    let mut analytic = AnalyticSignal::default();
+    let mut output = vec![Complex32::new(0.0, 0.0); input.len()];
+    analytic.process_block(&input, &mut output);
+    let settled = &output[2 * analytic.delay()..];
+
+    // One-sided: constant envelope, spinning at +1200 Hz
+    for z in settled {
+        assert_relative_eq!(z.norm(), 1.0, epsilon = 1e-3);
+    }
+    let mut discriminator = FrequencyDiscriminator::new(sample_rate);
+    let freqs: Vec<f32> = settled.iter().map(|&z| discriminator.process(z)).collect();
+    for f in &freqs[1..] {
+        assert_relative_eq!(*f, 1200.0, epsilon = 1.0);
+    }
+
+    // A complex mix then leaves DC with no 2400 Hz product; a real mix
+    // of the same tone swings by the full image amplitude
+    let mut nco = ComplexNCO::new(1200.0, sample_rate);
+    let analytic_mix: Vec<Complex32> = output.iter().map(|&z| nco.mix_down_complex(z)).collect();
+    let mut nco = ComplexNCO::new(1200.0, sample_rate);
+    let real_mix: Vec<Complex32> = input.iter().map(|&x| nco.mix_down(x)).collect();
+
+    let swing = |v: &[Complex32]| {
+        let mean = v.iter().sum::<Complex32>() / v.len() as f32;
+        v.iter().map(|z| (z - mean).norm()).fold(0.0f32, f32::max)
+    };
+    assert!(swing(&analytic_mix[100..]) < 2e-3, "analytic swing {}", swing(&analytic_mix[100..]));
+    assert!(swing(&real_mix[100..]) > 0.45);
+}
+
+#[test]
+fn test_fsk_bit_sync_locks_to_far_end_timing() {
+    let mode = FSKMode::V21Originate;
+    let bits: Vec<bool> = (0..1000u32).map(|i| (i.wrapping_mul(2654435761) >> 13) & 1 == 1).collect();
+
+    // Half a bit of idle mark ahead of the data, so the data starts in the
+    // middle of the receiver's nominal bit, and a far end 1% fast
+    let mut audio = FSKModulator::new(mode, 300.0, 8000.0).modulate(&[true; 3]);
+    audio.truncate(13);
+    audio.extend(FSKModulator::new(mode, 300.0, 8000.0).modulate(&bits));
+    let audio = Resampler::new(1.01, Interpolator::polyphase(32, 16)).process(&audio);
+
+    let mut demodulator = FSKDemodulator::new(mode, 300.0, 8000.0);
+    let received = demodulator.demodulate(&audio);
+
+    assert!(demodulator.is_bit_locked());
+    assert_relative_eq!(demodulator.clock_offset(), 0.01, epsilon = 0.002);
+
+    // Every bit comes through once, after at most a leading idle bit
+    let start = (0..=1)
+        .find(|&s| received[s..].iter().zip(&bits).all(|(a, b)| a == b))
+        .expect("bit stream should match the transmitted data");
+    assert!(received.len() - start >= bits.len() - 1);
+}
+
+#[test]
+fn test_async_framing_formats_and_parity() {
+    assert_eq!("7E1".parse::<CharFormat>().unwrap(), CharFormat::ASYNC_7E1);
+    assert_eq!("8n2".parse::<CharFormat>().unwrap(), CharFormat::ASYNC_8N2);
+    assert_eq!(CharFormat::ASYNC_7O1.to_string(), "7O1");
+    assert!("9N1".parse::<CharFormat>().is_err());
+    assert!("8X1".parse::<CharFormat>().is_err());
+    assert!("8N3".parse::<CharFormat>().is_err());
+
+    let data = b"Hello, 0x7F\x00\xFF";
+    for format in [CharFormat::ASYNC_8N1, CharFormat::ASYNC_8N2, CharFormat::ASYNC_7E1, CharFormat::ASYNC_7O1] {
+        let mut framer = AsyncFramer::new(format);
+        let bits = framer.frame(data);
+        let lead_in = 2 * format.frame_bits();
+        assert_eq!(bits.len(), lead_in + data.len() * format.frame_bits(), "{}", format);
+
+        let mask = (0xFFu16 >> (8 - format.data_bits)) as u8;
+        let expected: Vec<u8> = data.iter().map(|b| b & mask).collect();
+        assert_eq!(AsyncDeframer::new(format).deframe_bytes(&bits), expected, "{}", format);
+    }
+
+    // 'A' (0x41, two ones) as 7E1: start, 1000001, parity 0, stop
+    let mut bits = AsyncFramer::new(CharFormat::ASYNC_7E1).frame(b"A");
+    let parity = bits.len() - 2;
+    assert!(!bits[parity]);
+    bits[parity] = true;
+    let mut deframer = AsyncDeframer::new(CharFormat::ASYNC_7E1);
+    assert_eq!(deframer.deframe(&bits), vec![AsyncChar::ParityError(0x41)]);
+    assert_eq!(deframer.stats().parity_errors, 1);
+
+    // Missing stop bit
+    let mut bits = AsyncFramer::new(CharFormat::ASYNC_8N1).frame(b"AB");
+    let stop = bits.len() - 11;
+    bits[stop] = false;
+    let received = AsyncDeframer::new(CharFormat::ASYNC_8N1).deframe(&bits);
+    assert_eq!(received[0], AsyncChar::FramingError(b'A'));
+}
+
+#[test]
+fn test_v14_stop_bit_deletion_and_break() {
+    let format = CharFormat::new(8, Parity::None, 1).unwrap();
+    let data: Vec<u8> = (0..60u8).map(|i| if i % 5 == 0 { 0 } else { i.wrapping_mul(37) }).collect();
+
+    // Overspeed: every 8th character goes out without its stop bit
+    let mut framer = AsyncFramer::v14(format);
+    framer.set_stop_bit_deletion(true);
+    let mut bits = framer.frame(&data);
+    let lead_in = 2 * format.frame_bits();
+    assert_eq!(bits.len(), lead_in + data.len() * 10 - data.len() / 8);
+
+    // A V.14 receiver puts the stop bits back; start/stop framing can't
+    let mut deframer = AsyncDeframer::v14(format);
+    bits.extend([true; 10]);
+    assert_eq!(deframer.deframe_bytes(&bits), data);
+    assert_eq!(deframer.stats().stop_bits_inserted, data.len() / 8);
+    assert_eq!(deframer.stats().framing_errors, 0);
+    let mut plain = AsyncDeframer::new(format);
+    plain.deframe(&bits);
+    assert!(plain.stats().framing_errors > 0);
+
+    // Break: 2M+3 spaces then 2M marks, M = 10; a character after it
+    // still comes through
+    let mut framer = AsyncFramer::v14(format);
+    let mut bits = framer.frame(b"x");
+    let break_bits = framer.break_bits();
+    assert_eq!(break_bits.iter().filter(|&&b| !b).count(), 23);
+    bits.extend(break_bits);
+    framer.frame_byte(b'y', &mut bits);
+    let mut deframer = AsyncDeframer::v14(format);
+    assert_eq!(
+        deframer.deframe(&bits),
+        vec![AsyncChar::Data(b'x'), AsyncChar::Break, AsyncChar::Data(b'y')]
+    );
+    assert_eq!(deframer.stats().breaks, 1);
+}
+
+#[test]
+fn test_fsk_carries_7e1_characters() {
+    let mode = FSKMode::V21Answer;
+    let text = b"ATDT5551234\r";
+
+    let mut modulator = FSKModulator::new(mode, 300.0, 8000.0);
+    let mut demodulator = FSKDemodulator::new(mode, 300.0, 8000.0);
+    modulator.set_char_format(CharFormat::ASYNC_7E1);
+    demodulator.set_char_format(CharFormat::ASYNC_7E1);
+
+    let mut audio = modulator.modulate_bytes(text);
+    audio.extend(modulator.modulate_break());
+    audio.extend(modulator.modulate_bytes(b"OK"));
+    audio.extend(modulator.idle(20));
+
+    let mut expected = text.to_vec();
+    expected.extend(b"OK");
+    assert_eq!(demodulator.demodulate_bytes(&audio), expected);
+    let stats = demodulator.framing_stats();
+    assert_eq!((stats.parity_errors, stats.framing_errors, stats.breaks), (0, 0, 1));
+}
+
+#[test]
+fn test_fsk_detectors_decode_clean_signal() {
+    assert_eq!("PLL".parse::<FSKDetector>().unwrap(), FSKDetector::PLL);
+    assert!("coherent".parse::<FSKDetector>().is_err());
+
+    // Starts with a space bit, so the data is found after the idle marks
+    let bits: Vec<bool> = (0..400u32).map(|i| i > 0 && (i.wrapping_mul(2654435761) >> 13) & 1 == 1).collect();
+    for mode in [FSKMode::Bell103Originate, FSKMode::V21Answer] {
+        let mut audio = FSKModulator::new(mode, 300.0, 8000.0).modulate(&[true; 20]);
+        audio.extend(FSKModulator::new(mode, 300.0, 8000.0).modulate(&bits));
+
+        for detector in FSKDetector::ALL {
+            let mut demodulator = FSKDemodulator::with_detector(mode, 300.0, 8000.0, detector);
+            assert_eq!(demodulator.detector(), detector);
+            let received = demodulator.demodulate(&audio);
+
+            // Idle, then the data bit for bit (the last two may still be
+            // in the detector); the first bits are detector warm-up
+            let start = 10 + received[10..].iter().position(|&b| !b).expect("no data seen");
+            let data = &received[start..];
+            assert!(data.len() >= bits.len() - 2, "{:?} {}: {} bits", mode, detector, data.len());
+            assert!(data.iter().zip(&bits).all(|(a, b)| a == b), "{:?} {}: bit errors", mode, detector);
+        }
+    }
+}
+
+/// Level in dBm0 of `samples`, skipping the first `skip` (filter fill)
+fn measured_dbm0(samples: &[f32], skip: usize) -> f32 {
+    let tail = &samples[skip..];
+    power_to_dbm0(tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32)
+}
+
+#[test]
+fn test_tx_level_applies_to_every_source() {
+    // A full-scale reference 3 dB below the u-law one makes the same
+    // level 3 dB hotter in samples
+    assert_relative_eq!(TxLevel::new(FULL_SCALE_DBM0).sine_amplitude(), 1.0, epsilon = 1e-6);
+    assert_relative_eq!(
+        TxLevel::with_reference(-10.0, FULL_SCALE_DBM0 - 3.0).sine_amplitude(),
+        TxLevel::new(-7.0).sine_amplitude(),
+        epsilon = 1e-6
+    );
+
+    let data: Vec<u8> = (0..200u32).map(|i| (i * 73 + 5) as u8).collect();
+    for dbm0 in [DEFAULT_TX_LEVEL_DBM0, -10.0, -43.0] {
+        let level = TxLevel::new(dbm0);
+
+        let mut fsk = FSKModulator::new(FSKMode::V21Originate, 300.0, 8000.0);
+        fsk.set_tx_level(level);
+        assert_relative_eq!(measured_dbm0(&fsk.modulate_bytes(&data), 0), dbm0, epsilon = 0.1);
+
+        for mode in [QAMMode::V22, QAMMode::V22bis] {
+            let mut qam = QAMModulator::new(mode, 1200.0, 8000.0);
+            qam.set_tx_level(level);
+            let audio = qam.modulate_bytes(&data);
+            assert_relative_eq!(measured_dbm0(&audio, 200), dbm0, epsilon = 0.5);
+        }
+
+        let mut dtmf = DTMFGenerator::new(8000.0);
+        dtmf.set_tx_level(level);
+        assert_relative_eq!(measured_dbm0(&dtmf.generate_digit('5', 4000), 0), dbm0, epsilon = 0.1);
+    }
+
+    // The defaults are the calibrated level too
+    let audio = FSKModulator::new(FSKMode::Bell103Originate, 300.0, 8000.0).modulate(&[true; 100]);
+    assert_relative_eq!(measured_dbm0(&audio, 0), DEFAULT_TX_LEVEL_DBM0, epsilon = 0.1);
+    let audio = DTMFGenerator::new(8000.0).generate_digit('#', 4000);
+    assert_relative_eq!(measured_dbm0(&audio, 0), DEFAULT_TX_LEVEL_DBM0, epsilon = 0.1);
+}
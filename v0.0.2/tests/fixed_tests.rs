@@ -0,0 +1,184 @@
+// tests/fixed_tests.rs
+//
+// Fixed-point chains against the float implementation on the same test
+// vectors. Run with `cargo test --features fixed-point`.
+
+use hsf_softmodem::dsp::filters::BiquadFilter;
+use hsf_softmodem::dsp::fixed::{self, q15_to_f32, samples_from_q15, samples_to_q15};
+use hsf_softmodem::dsp::fsk::{FSKDemodulator, FSKModulator, FSKMode};
+use hsf_softmodem::dsp::fsk_detector::QuadratureCorrelator;
+use hsf_softmodem::dsp::goertzel::GoertzelDetector;
+use hsf_softmodem::dsp::oscillator::NCO;
+use hsf_softmodem::dsp::qam::QAMMode;
+use hsf_softmodem::dsp::qam_modem::{QAMDemodulator, QAMModulator};
+use hsf_softmodem::sim::channel::{ChannelSimulator, Impairments};
+
+const FSK_MODES: [FSKMode; 4] = [FSKMode::Bell103Originate, FSKMode::Bell103Answer, FSKMode::V21Originate, FSKMode::V21Answer];
+
+fn max_error(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len());
+    a.iter().zip(b).map(|(x, y)| (x - y).abs()).fold(0.0, f32::max)
+}
+
+/// Two tones and noise at -6 dBFS peak, as test input for the filters
+fn test_signal(sample_rate: f32, len: usize) -> Vec<f32> {
+    let line = Impairments { snr_db: Some(10.0), seed: 46, ..Impairments::clean() };
+    let tones: Vec<f32> = (0..len)
+        .map(|n| {
+            let t = n as f32 / sample_rate;
+            0.25 * (2.0 * std::f32::consts::PI * 1170.0 * t).sin() + 0.2 * (2.0 * std::f32::consts::PI * 2100.0 * t).sin()
+        })
+        .collect();
+    ChannelSimulator::new(line, sample_rate).process(&tones)
+}
+
+#[test]
+fn test_fixed_nco_tracks_float() {
+    for (freq, rate) in [(1070.0, 8000.0), (2225.0, 9600.0), (980.0, 7200.0), (1850.0, 16000.0)] {
+        let mut float = NCO::new(freq, rate, 0.9);
+        let mut fixed = fixed::oscillator::NCO::new(freq, rate, 0.9);
+        let a: Vec<f32> = (0..4000).map(|_| float.next()).collect();
+        let b: Vec<f32> = (0..4000).map(|_| q15_to_f32(fixed.next_sample())).collect();
+        // Same phase words; Q15 rounding and the coarser table only
+        assert!(max_error(&a, &b) < 1e-4, "{} Hz at {}: {}", freq, rate, max_error(&a, &b));
+    }
+}
+
+#[test]
+fn test_fixed_biquad_tracks_float() {
+    let input = test_signal(8000.0, 8000);
+    let q15_input = samples_to_q15(&input);
+    let designs = [
+        BiquadFilter::bandpass(1170.0, 400.0, 8000.0),
+        BiquadFilter::bandpass(2125.0, 400.0, 8000.0),
+        BiquadFilter::lowpass(900.0, 0.707, 8000.0),
+    ];
+    for design in designs {
+        let mut fixed = fixed::filters::BiquadFilter::from_float(&design);
+        let mut float = design.clone();
+        let a: Vec<f32> = q15_input.iter().map(|&x| float.process(q15_to_f32(x))).collect();
+        let b: Vec<f32> = q15_input.iter().map(|&x| q15_to_f32(fixed.process(x))).collect();
+        assert!(max_error(&a, &b) < 2e-4, "{}", max_error(&a, &b));
+    }
+}
+
+#[test]
+fn test_fixed_goertzel_tracks_float() {
+    let input = samples_to_q15(&test_signal(8000.0, 205));
+    for freq in [697.0, 1170.0, 1336.0, 2100.0] {
+        let mut float = GoertzelDetector::new(freq, 8000.0, 205);
+        let mut fixed = fixed::goertzel::GoertzelDetector::new(freq, 8000.0, 205);
+        for &x in &input {
+            float.process_sample(q15_to_f32(x));
+            fixed.process_sample(x);
+        }
+        assert!(fixed.is_complete());
+        let a = float.magnitude();
+        let b = fixed.magnitude() as f32 / 32768.0;
+        assert!((a - b).abs() < 1e-3 * a.max(1.0), "{} Hz: {} vs {}", freq, a, b);
+    }
+}
+
+#[test]
+fn test_fixed_fsk_modulator_tracks_float() {
+    let bits: Vec<bool> = (0..600).map(|i| (i * 7 % 11) < 5).collect();
+    for sample_rate in [8000.0, 9600.0] {
+        for mode in FSK_MODES {
+            let a = FSKModulator::new(mode, 300.0, sample_rate).modulate(&bits);
+            let b = samples_from_q15(&fixed::fsk::FSKModulator::new(mode, 300.0, sample_rate).modulate(&bits));
+            assert!(max_error(&a, &b) < 1e-3, "{:?} at {}: {}", mode, sample_rate, max_error(&a, &b));
+        }
+    }
+}
+
+#[test]
+fn test_fixed_fsk_correlator_tracks_float() {
+    let input = samples_to_q15(&FSKModulator::new(FSKMode::Bell103Answer, 300.0, 8000.0).modulate(&[true, false, false, true, true, false, true]));
+    let mut float = QuadratureCorrelator::new(FSKMode::Bell103Answer, 27, 8000.0);
+    let mut fixed = fixed::fsk::QuadratureCorrelator::new(FSKMode::Bell103Answer, 27, 8000.0);
+    for &x in &input {
+        let a = float.process(q15_to_f32(x));
+        let b = fixed.process(x) as f32 / 32768.0;
+        assert!((a - b).abs() < 2e-3, "{} vs {}", a, b);
+    }
+}
+
+#[test]
+fn test_fixed_fsk_decodes_as_float() {
+    let text = b"Fixed point, same bits as the float receiver.";
+    for mode in FSK_MODES {
+        let mut modulator = FSKModulator::new(mode, 300.0, 8000.0);
+        let mut audio = modulator.modulate_bytes(text);
+        audio.extend(modulator.idle(10));
+        let line = Impairments { gain_db: -6.0, snr_db: Some(21.0), seed: 46, ..Impairments::clean() };
+        let received = ChannelSimulator::new(line, 8000.0).process(&audio);
+
+        let float_bits = FSKDemodulator::new(mode, 300.0, 8000.0).demodulate(&received);
+        let mut demodulator = fixed::fsk::FSKDemodulator::new(mode, 300.0, 8000.0);
+        let fixed_bits = demodulator.demodulate(&samples_to_q15(&received));
+        let differing = float_bits.iter().zip(&fixed_bits).filter(|(a, b)| a != b).count();
+        assert!(differing <= 2, "{:?}: {} of {} bits differ", mode, differing, float_bits.len());
+        assert!(demodulator.is_bit_locked(), "{:?}", mode);
+
+        let mut demodulator = fixed::fsk::FSKDemodulator::new(mode, 300.0, 8000.0);
+        assert!(demodulator.demodulate_bytes(&samples_to_q15(&received)).ends_with(text), "{:?}", mode);
+    }
+}
+
+#[test]
+fn test_fixed_fsk_loopback() {
+    for sample_rate in [7200.0, 9600.0] {
+        let mut modulator = fixed::fsk::FSKModulator::new(FSKMode::V21Originate, 300.0, sample_rate);
+        let mut demodulator = fixed::fsk::FSKDemodulator::new(FSKMode::V21Originate, 300.0, sample_rate);
+        let mut audio = modulator.modulate_bytes(b"HELLO WORLD!");
+        audio.extend(modulator.idle(4));
+        assert!(demodulator.demodulate_bytes(&audio).ends_with(b"HELLO WORLD!"), "{} Hz", sample_rate);
+    }
+}
+
+#[test]
+fn test_fixed_qam_modulator_tracks_float() {
+    let bits: Vec<bool> = (0..2400).map(|i| (i * 13 % 17) < 8).collect();
+    for mode in [QAMMode::V22, QAMMode::Bell212A, QAMMode::V22bis] {
+        for sample_rate in [8000.0, 9600.0] {
+            let mut float = QAMModulator::new(mode, 1200.0, sample_rate);
+            let mut fixed = fixed::qam_modem::QAMModulator::new(mode, 1200.0, sample_rate);
+            let mut a = float.modulate(&bits);
+            a.extend(float.flush());
+            // 0 dBm0 V.22 peaks just over full scale, where Q15 clips
+            let a: Vec<f32> = a.iter().map(|x| x.clamp(-1.0, 1.0)).collect();
+            let mut b = samples_from_q15(&fixed.modulate(&bits));
+            b.extend(samples_from_q15(&fixed.flush()));
+            assert!(max_error(&a, &b) < 2e-3, "{:?} at {}: {}", mode, sample_rate, max_error(&a, &b));
+        }
+    }
+}
+
+#[test]
+fn test_fixed_dpsk_decodes_as_float() {
+    let text: Vec<u8> = (0..150u32).map(|i| b' ' + (i * 11 % 95) as u8).collect();
+    for sample_rate in [8000.0, 9600.0] {
+        let mut modulator = QAMModulator::new(QAMMode::V22, 1200.0, sample_rate);
+        let mut audio = modulator.modulate_bytes(&text);
+        audio.extend(modulator.flush());
+        let line = Impairments { gain_db: -6.0, snr_db: Some(20.0), seed: 46, ..Impairments::clean() };
+        let received = ChannelSimulator::new(line, sample_rate).process(&audio);
+
+        let float = QAMDemodulator::new(QAMMode::V22, 1200.0, sample_rate).demodulate_bytes(&received);
+        let mut demodulator = fixed::qam_modem::QAMDemodulator::new(QAMMode::V22, 1200.0, sample_rate).unwrap();
+        let fixed = demodulator.demodulate_bytes(&samples_to_q15(&received));
+        assert_eq!(fixed, float, "{} Hz", sample_rate);
+        assert_eq!(fixed, text, "{} Hz", sample_rate);
+    }
+}
+
+#[test]
+fn test_fixed_qam_loopback_and_v22bis_receiver_refused() {
+    let mut modulator = fixed::qam_modem::QAMModulator::new(QAMMode::Bell212A, 2400.0, 8000.0);
+    let mut demodulator = fixed::qam_modem::QAMDemodulator::new(QAMMode::Bell212A, 2400.0, 8000.0).unwrap();
+    let mut audio = modulator.modulate_bytes(b"HELLO WORLD!");
+    audio.extend(modulator.flush());
+    assert_eq!(demodulator.demodulate_bytes(&audio), b"HELLO WORLD!");
+
+    assert!(fixed::qam_modem::QAMDemodulator::new(QAMMode::V22bis, 1200.0, 8000.0).is_err());
+}
@@ -0,0 +1,194 @@
+// tests/golden_tests.rs
+//
+// Bit-exact regression tests against the reference audio in
+// tests/fixtures/golden, and a check that every mode gets the payload back
+// out of it. If a DSP change is *meant* to alter modem output,
+// regenerate the fixtures (see tests/fixtures/README.md):
+//
+//   WOFLMODEM_REGEN_GOLDEN=1 cargo test --test golden_tests
+//
+// and commit the updated files together with the change.
+
+use hsf_softmodem::dsp::fsk::{FSKDemodulator, FSKMode, FSKModulator};
+use hsf_softmodem::dsp::qam::QAMMode;
+use hsf_softmodem::dsp::qam_modem::{QAMDemodulator, QAMModulator};
+use hsf_softmodem::sim::channel::{ChannelSimulator, Impairments};
+use hsf_softmodem::dsp::DEFAULT_TX_LEVEL_DBM0;
+use hsf_softmodem::sim::wav::{read_wav, write_wav_f32};
+use hsf_softmodem::SAMPLE_RATE;
+use std::path::PathBuf;
+
+const REGEN_VAR: &str = "WOFLMODEM_REGEN_GOLDEN";
+const PAYLOAD: &[u8] = b"woflmodem golden";
+const TRAILING_IDLE_BITS: usize = 20;
+/// Mark idle ahead of the QAM payload for the receiver to train on
+const QAM_LEAD_IN_BITS: usize = 1200;
+/// V.22bis holds up to two symbols in the receive pipeline
+const QAM_TRAILING_IDLE_BITS: usize = 64;
+
+#[derive(Clone, Copy)]
+enum Modem {
+    Fsk(FSKMode),
+    Qam(QAMMode),
+}
+
+struct Fixture {
+    name: &'static str,
+    modem: Modem,
+    impaired: bool,
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture { name: "bell103_originate_clean", modem: Modem::Fsk(FSKMode::Bell103Originate), impaired: false },
+    Fixture { name: "bell103_originate_impaired", modem: Modem::Fsk(FSKMode::Bell103Originate), impaired: true },
+    Fixture { name: "bell103_answer_clean", modem: Modem::Fsk(FSKMode::Bell103Answer), impaired: false },
+    Fixture { name: "bell103_answer_impaired", modem: Modem::Fsk(FSKMode::Bell103Answer), impaired: true },
+    Fixture { name: "v21_originate_clean", modem: Modem::Fsk(FSKMode::V21Originate), impaired: false },
+    Fixture { name: "v21_originate_impaired", modem: Modem::Fsk(FSKMode::V21Originate), impaired: true },
+    Fixture { name: "v21_answer_clean", modem: Modem::Fsk(FSKMode::V21Answer), impaired: false },
+    Fixture { name: "v21_answer_impaired", modem: Modem::Fsk(FSKMode::V21Answer), impaired: true },
+    Fixture { name: "v22_clean", modem: Modem::Qam(QAMMode::V22), impaired: false },
+    Fixture { name: "v22_impaired", modem: Modem::Qam(QAMMode::V22), impaired: true },
+    Fixture { name: "v22bis_clean", modem: Modem::Qam(QAMMode::V22bis), impaired: false },
+    Fixture { name: "v22bis_impaired", modem: Modem::Qam(QAMMode::V22bis), impaired: true },
+    Fixture { name: "bell212a_clean", modem: Modem::Qam(QAMMode::Bell212A), impaired: false },
+    Fixture { name: "bell212a_impaired", modem: Modem::Qam(QAMMode::Bell212A), impaired: true },
+];
+
+/// The "impaired" line: some loss, band limiting, delay and 20 dB SNR
+/// against the received signal
+fn impaired_line() -> Impairments {
+    Impairments {
+        gain_db: -6.0,
+        snr_db: Some(20.0),
+        signal_dbm0: DEFAULT_TX_LEVEL_DBM0,
+        delay_samples: 37,
+        bandwidth_hz: Some(3300.0),
+        line: None,
+        seed: 0x5EED_0026,
+    }
+}
+
+fn regenerating() -> bool {
+    std::env::var_os(REGEN_VAR).is_some()
+}
+
+fn fixture_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("golden")
+}
+
+fn audio_path(fixture: &Fixture) -> PathBuf {
+    fixture_dir().join(format!("{}.wav", fixture.name))
+}
+
+/// Run the transmitter (and line, for impaired fixtures) from a fresh state
+fn render(fixture: &Fixture) -> Vec<f32> {
+    // Trailing mark idle carries the last character through the receiver
+    let clean = match fixture.modem {
+        Modem::Fsk(mode) => {
+            let mut modulator = FSKModulator::new(mode, 300.0, SAMPLE_RATE);
+            let mut audio = modulator.modulate_bytes(PAYLOAD);
+            audio.extend(modulator.idle(TRAILING_IDLE_BITS));
+            audio
+        }
+        Modem::Qam(mode) => {
+            let mut modulator = QAMModulator::new(mode, mode.carrier_freq_originate(), SAMPLE_RATE);
+            let mut audio = modulator.idle(QAM_LEAD_IN_BITS);
+            audio.extend(modulator.modulate_bytes(PAYLOAD));
+            audio.extend(modulator.idle(QAM_TRAILING_IDLE_BITS));
+            audio.extend(modulator.flush());
+            audio
+        }
+    };
+
+    if fixture.impaired {
+        ChannelSimulator::new(impaired_line(), SAMPLE_RATE).process(&clean)
+    } else {
+        clean
+    }
+}
+
+fn decode(fixture: &Fixture, audio: &[f32]) -> Vec<u8> {
+    match fixture.modem {
+        Modem::Fsk(mode) => FSKDemodulator::new(mode, 300.0, SAMPLE_RATE).demodulate_bytes(audio),
+        Modem::Qam(mode) => {
+            QAMDemodulator::new(mode, mode.carrier_freq_originate(), SAMPLE_RATE).demodulate_bytes(audio)
+        }
+    }
+}
+
+fn load_audio(fixture: &Fixture) -> Vec<f32> {
+    let path = audio_path(fixture);
+    let (rate, samples) = read_wav(&path).unwrap_or_else(|e| {
+        panic!("{}: {} (regenerate with {}=1)", path.display(), e, REGEN_VAR)
+    });
+    assert_eq!(rate, SAMPLE_RATE as u32, "{}: unexpected sample rate", fixture.name);
+    samples
+}
+
+#[test]
+fn test_golden_audio_is_bit_exact() {
+    if regenerating() {
+        std::fs::create_dir_all(fixture_dir()).unwrap();
+    }
+
+    let mut failures = Vec::new();
+
+    for fixture in FIXTURES {
+        let rendered = render(fixture);
+
+        if regenerating() {
+            write_wav_f32(audio_path(fixture), SAMPLE_RATE as u32, &rendered).unwrap();
+            continue;
+        }
+
+        let reference = load_audio(fixture);
+        if rendered.len() != reference.len() {
+            failures.push(format!(
+                "{}: length {} != reference {}",
+                fixture.name,
+                rendered.len(),
+                reference.len()
+            ));
+            continue;
+        }
+
+        if let Some(idx) = rendered
+            .iter()
+            .zip(reference.iter())
+            .position(|(a, b)| a.to_bits() != b.to_bits())
+        {
+            failures.push(format!(
+                "{}: first mismatch at sample {} ({} != {})",
+                fixture.name, idx, rendered[idx], reference[idx]
+            ));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "modem output changed; if intended, rerun with {}=1:\n{}",
+        REGEN_VAR,
+        failures.join("\n")
+    );
+}
+
+/// Why the decoded bytes of `fixture` are not the payload, if they aren't
+fn decode_failure(fixture: &Fixture) -> Option<String> {
+    let decoded = decode(fixture, &load_audio(fixture));
+    (decoded != PAYLOAD).then(|| format!("{}: decoded {:?}", fixture.name, String::from_utf8_lossy(&decoded)))
+}
+
+#[test]
+fn test_golden_decodes_payload() {
+    if regenerating() {
+        return;
+    }
+
+    let failures: Vec<String> = FIXTURES.iter().filter_map(decode_failure).collect();
+
+    assert!(failures.is_empty(), "payload not recovered:\n{}", failures.join("\n"));
+}
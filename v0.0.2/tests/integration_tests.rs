@@ -2,8 +2,14 @@
 //
 // Lightweight integration tests for the VirtualModem public API.
 
+use hsf_softmodem::dsp::fsk::{FSKMode, FSKModulator};
+use hsf_softmodem::dsp::power_to_dbm0;
 use hsf_softmodem::tapi::at_commands::{ATCommand, ATResponse, ModemState};
-use hsf_softmodem::tapi::modem::VirtualModem;
+use hsf_softmodem::tapi::faults::Fault;
+use hsf_softmodem::tapi::modem::{ModemEventKind, VirtualModem};
+use hsf_softmodem::tapi::session::{ClientStream, ModemSession};
+use std::io;
+use std::time::{Duration, Instant};
 
 #[test]
 fn test_modem_initialization() {
@@ -31,10 +37,137 @@ fn test_dial_and_connect_flow() {
 
     assert_eq!(modem.get_state(), ModemState::Command);
 
-    let responses = modem.process_command(ATCommand::Dial("5551234".to_string()));
-    assert!(responses.iter().any(|r| matches!(r, ATResponse::Connect(_))));
+    // Nothing until the far end's carrier is up
+    assert!(modem.process_command(ATCommand::Dial("5551234".to_string())).is_empty());
+    assert_eq!(modem.get_state(), ModemState::Connecting);
 
+    let audio = bell103_audio(&[0x55; 40]);
+    let mut connect = Vec::new();
+    for block in audio.chunks(160) {
+        modem.receive_samples(block);
+        connect.extend(modem.take_unsolicited());
+        if !connect.is_empty() {
+            assert!(modem.dcd());
+            break;
+        }
+    }
+    assert_eq!(connect, vec![ATResponse::Connect(300)]);
     assert_eq!(modem.get_state(), ModemState::Connected);
+    assert!(modem.line_time() >= Duration::from_millis(600), "connected at {:?}", modem.line_time());
+}
+
+#[test]
+fn test_no_carrier_when_s7_runs_out() {
+    let mut modem = VirtualModem::new().unwrap();
+    assert_eq!(modem.process_command(ATCommand::SetRegister(7, 2)), vec![ATResponse::Ok]);
+    assert!(modem.process_command(ATCommand::Dial("5551234".to_string())).is_empty());
+
+    for _ in 0..190 {
+        modem.receive_samples(&[0.0; 80]);
+    }
+    assert_eq!(modem.get_state(), ModemState::Connecting);
+    assert!(modem.take_unsolicited().is_empty());
+
+    for _ in 0..20 {
+        modem.receive_samples(&[0.0; 80]);
+    }
+    assert_eq!(modem.get_state(), ModemState::Command);
+    assert_eq!(modem.take_unsolicited(), vec![ATResponse::NoCarrier]);
+    assert!(!modem.events().iter().any(|e| matches!(e.kind, ModemEventKind::Connected(_))));
+}
+
+#[test]
+fn test_answer_connects_at_once_on_carrier() {
+    let mut modem = VirtualModem::new().unwrap();
+    for block in bell103_audio(&[0x55; 40]).chunks(160) {
+        modem.receive_samples(block);
+    }
+    assert!(modem.dcd());
+
+    assert_eq!(modem.process_command(ATCommand::Answer), vec![ATResponse::Connect(300)]);
+    assert_eq!(modem.get_state(), ModemState::Connected);
+}
+
+#[test]
+fn test_carrier_drop_and_return_in_one_block() {
+    let mut modem = VirtualModem::new().unwrap();
+    assert_eq!(modem.process_command(ATCommand::SetRegister(10, 5)), vec![ATResponse::Ok]);
+    modem.process_command(ATCommand::Dial("5551234".to_string()));
+    let mut modulator = FSKModulator::new(FSKMode::Bell103Originate, 300.0, 8000.0);
+    modem.receive_samples(&modulator.modulate_bytes(&[0x55; 40]));
+    assert_eq!(modem.take_unsolicited(), vec![ATResponse::Connect(300)]);
+
+    // One second of silence, then the carrier back, in a single call
+    let mut line = vec![0.0; 8000];
+    line.extend(modulator.modulate_bytes(&[0x55; 40]));
+    let drop_at = modem.line_time();
+    modem.receive_samples(&line);
+
+    assert_eq!(modem.take_unsolicited(), vec![ATResponse::NoCarrier]);
+    assert_eq!(modem.get_state(), ModemState::Command);
+    assert!(modem.dcd());
+
+    // Both changes logged, each at its own line time
+    let changes: Vec<_> = modem
+        .events()
+        .iter()
+        .filter(|e| matches!(e.kind, ModemEventKind::CarrierDetected | ModemEventKind::CarrierLost))
+        .collect();
+    assert_eq!(changes.len(), 3, "{:?}", changes);
+    assert_eq!(changes[1].kind, ModemEventKind::CarrierLost);
+    assert_eq!(changes[2].kind, ModemEventKind::CarrierDetected);
+    let lost_after = changes[1].at - drop_at;
+    assert!(lost_after >= Duration::from_millis(500) && lost_after <= Duration::from_millis(530), "lost after {:?}", lost_after);
+    assert!(changes[2].at - drop_at >= Duration::from_millis(1600), "back at {:?}", changes[2].at - drop_at);
+}
+
+/// Client that sends its script at once, then waits for `until` in what
+/// comes back (or gives up) and disconnects
+struct ScriptedClient {
+    script: Vec<u8>,
+    until: &'static str,
+    deadline: Instant,
+    received: String,
+}
+
+impl ClientStream for ScriptedClient {
+    fn try_read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        if !self.script.is_empty() {
+            let n = self.script.len().min(buf.len());
+            buf[..n].copy_from_slice(&self.script[..n]);
+            self.script.drain(..n);
+            return Ok(Some(n));
+        }
+        if self.received.contains(self.until) || Instant::now() > self.deadline {
+            return Ok(Some(0));
+        }
+        Ok(None)
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.received.push_str(&String::from_utf8_lossy(data));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_session_reports_no_carrier_without_further_commands() {
+    let mut modem = VirtualModem::new().unwrap();
+    modem.init_audio().unwrap();
+    let mut client = ScriptedClient {
+        script: b"AT\rATS7=1\rATD5551234\r".to_vec(),
+        until: "NO CARRIER",
+        deadline: Instant::now() + Duration::from_secs(20),
+        received: String::new(),
+    };
+
+    // Nothing more comes from the client after ATD: the session has to run
+    // the line itself for S7 to run out
+    ModemSession::new(Duration::from_millis(1)).run(&mut modem, &mut client).unwrap();
+
+    assert_eq!(client.received, "OK\r\nOK\r\nNO CARRIER\r\n");
+    assert_eq!(modem.get_state(), ModemState::Command);
+    assert!(modem.line_time() >= Duration::from_secs(1), "gave up at {:?}", modem.line_time());
 }
 
 #[test]
@@ -65,3 +198,246 @@ fn test_s_register_access_round_trip() {
         other => panic!("Expected Text response, got {:?}", other),
     }
 }
+
+/// Level in dBm0 of what the modem sends for `data`
+fn transmit_level(modem: &mut VirtualModem, data: &[u8]) -> f32 {
+    for &b in data {
+        modem.process_data_char(b);
+    }
+    let audio = modem.process_tx_queue();
+    // Past the pulse-shaping filter fill
+    let tail = &audio[audio.len() / 4..];
+    power_to_dbm0(tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32)
+}
+
+#[test]
+fn test_transmit_level_register() {
+    let mut modem = VirtualModem::new().unwrap();
+    let data = [0x55u8; 100];
+
+    // S91 holds the level in -dBm0, -10 dBm0 out of the box
+    assert_eq!(modem.process_command(ATCommand::QueryRegister(91)), vec![ATResponse::Text("010".into())]);
+    assert!((transmit_level(&mut modem, &data) + 10.0).abs() < 0.2);
+
+    assert_eq!(modem.process_command(ATCommand::SetRegister(91, 43)), vec![ATResponse::Ok]);
+    assert!((transmit_level(&mut modem, &data) + 43.0).abs() < 0.2);
+
+    // Outside -10..-43 dBm0 is refused and leaves the level alone
+    assert_eq!(modem.process_command(ATCommand::SetRegister(91, 9)), vec![ATResponse::Error]);
+    assert_eq!(modem.process_command(ATCommand::SetRegister(91, 44)), vec![ATResponse::Error]);
+    assert_eq!(modem.tx_level().dbm0, -43.0);
+
+    // The setting follows the modem into QAM modes and back to defaults
+    modem.process_command(ATCommand::SetRegister(91, 20));
+    modem.process_command(ATCommand::SelectSpeed(1200));
+    assert!((transmit_level(&mut modem, &data) + 20.0).abs() < 0.5);
+    modem.process_command(ATCommand::Reset);
+    assert_eq!(modem.tx_level().dbm0, -10.0);
+
+    // Against a hotter audio path the same level is fewer sample units
+    modem.set_full_scale_reference(0.0);
+    modem.process_command(ATCommand::SelectSpeed(300));
+    let level = transmit_level(&mut modem, &data);
+    assert!((level - (-10.0 + hsf_softmodem::dsp::FULL_SCALE_DBM0)).abs() < 0.2, "{}", level);
+}
+
+/// 300 baud Bell 103 audio carrying `data`, as the modem's default receiver expects
+fn bell103_audio(data: &[u8]) -> Vec<f32> {
+    FSKModulator::new(FSKMode::Bell103Originate, 300.0, 8000.0).modulate_bytes(data)
+}
+
+#[test]
+fn test_bit_error_burst_fault() {
+    let audio = bell103_audio(b"fault injection");
+
+    let mut reference = VirtualModem::new().unwrap();
+    let clean = reference.receive_samples(&audio);
+    assert!(clean.starts_with(b"fault injectio"), "{:?}", clean);
+    assert_eq!(reference.framing_stats().framing_errors, 0);
+
+    // Lead-in is 20 mark bits; the burst lands on the start bit of the
+    // sixth character and runs into the seventh
+    let mut modem = VirtualModem::new().unwrap();
+    modem.schedule_fault(Duration::from_secs_f32(70.0 / 300.0), Fault::BitErrorBurst { bits: 12 });
+    let corrupted = modem.receive_samples(&audio);
+
+    // The line hit costs framing, not just bit flips in whole characters
+    assert!(corrupted.starts_with(b"fault"));
+    assert_ne!(corrupted[5..], clean[5..]);
+    assert!(modem.framing_stats().framing_errors > 0, "{:?}", modem.framing_stats());
+
+    // Idle mark after the hit lets the receiver find the start bits again
+    let recovered = modem.receive_samples(&bell103_audio(b"recovered"));
+    assert!(recovered.ends_with(b"recovere"), "{:?}", recovered);
+
+    assert!(modem
+        .events()
+        .iter()
+        .any(|e| e.kind == ModemEventKind::FaultInjected(Fault::BitErrorBurst { bits: 12 })));
+}
+
+#[test]
+fn test_carrier_drop_fault_is_logged_with_line_time() {
+    let mut modem = VirtualModem::new().unwrap();
+    let fault = Fault::CarrierDrop {
+        duration: Duration::from_millis(100),
+    };
+    modem.schedule_fault(Duration::from_millis(50), fault);
+
+    // 250 ms of line audio in 20 ms blocks
+    let audio = bell103_audio(&[0x55; 10]);
+    for block in audio[..2000].chunks(160) {
+        modem.receive_samples(block);
+    }
+
+    let events = modem.events();
+    let started = events
+        .iter()
+        .find(|e| e.kind == ModemEventKind::FaultInjected(fault))
+        .expect("carrier drop should be logged");
+    let cleared = events
+        .iter()
+        .find(|e| e.kind == ModemEventKind::FaultCleared(fault))
+        .expect("carrier return should be logged");
+
+    assert_eq!(started.at, Duration::from_millis(50));
+    assert_eq!(cleared.at, Duration::from_millis(150));
+    assert_eq!(modem.line_time(), Duration::from_millis(250));
+}
+
+#[test]
+fn test_line_time_runs_at_the_modem_sample_rate() {
+    let mut modem = VirtualModem::with_sample_rate(16000.0).unwrap();
+    assert_eq!(modem.sample_rate(), 16000.0);
+    let fault = Fault::CarrierDrop {
+        duration: Duration::from_millis(100),
+    };
+    modem.schedule_fault(Duration::from_millis(50), fault);
+
+    // 250 ms of line audio at 16 kHz
+    for block in vec![0.0; 4000].chunks(320) {
+        modem.receive_samples(block);
+    }
+
+    let started = modem
+        .events()
+        .iter()
+        .find(|e| e.kind == ModemEventKind::FaultInjected(fault))
+        .expect("carrier drop should be logged");
+    assert_eq!(started.at, Duration::from_millis(50));
+    assert_eq!(modem.line_time(), Duration::from_millis(250));
+
+    assert!(VirtualModem::with_sample_rate(0.0).is_err());
+}
+
+#[test]
+fn test_dtr_drop_fault_hangs_up() {
+    let mut modem = VirtualModem::new().unwrap();
+    modem.process_command(ATCommand::Answer);
+    for block in bell103_audio(&[0x55; 30]).chunks(160) {
+        modem.receive_samples(block);
+    }
+    assert_eq!(modem.get_state(), ModemState::Connected);
+
+    modem.inject_fault(Fault::DtrDrop {
+        duration: Duration::from_millis(20),
+    });
+    modem.receive_samples(&[0.0; 400]);
+
+    assert_eq!(modem.get_state(), ModemState::Command);
+    assert!(modem.dtr(), "DTR should be restored after the fault");
+
+    let kinds: Vec<ModemEventKind> = modem.take_events().into_iter().map(|e| e.kind).collect();
+    let dtr_low = kinds.iter().position(|k| *k == ModemEventKind::DtrChanged(false)).unwrap();
+    let hung_up = kinds.iter().position(|k| *k == ModemEventKind::HungUp).unwrap();
+    let dtr_high = kinds.iter().position(|k| *k == ModemEventKind::DtrChanged(true)).unwrap();
+    assert!(dtr_low < hung_up && hung_up < dtr_high);
+    assert!(modem.events().is_empty());
+}
+
+#[test]
+fn test_clear_faults_restores_dtr() {
+    let mut modem = VirtualModem::new().unwrap();
+    modem.inject_fault(Fault::DtrDrop {
+        duration: Duration::from_secs(10),
+    });
+    modem.receive_samples(&[0.0; 160]);
+    assert!(!modem.dtr());
+
+    modem.clear_faults();
+    assert!(modem.dtr(), "DTR should be back where the host had it");
+    modem.receive_samples(&[0.0; 160]);
+    assert!(modem.dtr());
+
+    // A host that had DTR down keeps it down
+    modem.set_dtr(false);
+    modem.inject_fault(Fault::DtrDrop {
+        duration: Duration::from_secs(10),
+    });
+    modem.receive_samples(&[0.0; 160]);
+    modem.clear_faults();
+    assert!(!modem.dtr());
+}
+
+#[test]
+fn test_audio_stall_holds_transmit_data() {
+    let mut modem = VirtualModem::new().unwrap();
+    modem.process_command(ATCommand::Dial("5551234".to_string()));
+    for &b in b"hold" {
+        modem.process_data_char(b);
+    }
+
+    modem.inject_fault(Fault::AudioStall {
+        duration: Duration::from_millis(40),
+    });
+    modem.receive_samples(&[0.0; 160]);
+    assert!(modem.process_tx_queue().is_empty(), "stalled engine must not play");
+
+    modem.receive_samples(&[0.0; 320]);
+    assert!(!modem.process_tx_queue().is_empty(), "queued data should go out after the stall");
+}
+
+#[test]
+fn test_lost_carrier_hangs_up_with_no_carrier() {
+    let mut modem = VirtualModem::new().unwrap();
+    modem.process_command(ATCommand::Dial("5551234".to_string()));
+    assert!(!modem.dcd());
+
+    // S10 = 5: hang up 0.5 s after the carrier goes
+    assert_eq!(modem.process_command(ATCommand::SetRegister(10, 5)), vec![ATResponse::Ok]);
+
+    let audio = bell103_audio(&[0x55; 60]);
+    for block in audio.chunks(160) {
+        modem.receive_samples(block);
+    }
+    assert!(modem.dcd(), "carrier should be up after {:?}", modem.line_time());
+    assert_eq!(modem.get_state(), ModemState::Connected);
+    assert_eq!(modem.take_unsolicited(), vec![ATResponse::Connect(300)]);
+
+    let carrier_up = modem.line_time();
+    modem.inject_fault(Fault::CarrierDrop {
+        duration: Duration::from_secs(2),
+    });
+    for _ in 0..100 {
+        modem.receive_samples(&[0.0; 80]);
+    }
+
+    assert!(!modem.dcd());
+    assert_eq!(modem.get_state(), ModemState::Command);
+    assert_eq!(modem.take_unsolicited(), vec![ATResponse::NoCarrier]);
+
+    let events = modem.events();
+    let detected = events.iter().find(|e| e.kind == ModemEventKind::CarrierDetected).unwrap();
+    let lost = events.iter().find(|e| e.kind == ModemEventKind::CarrierLost).unwrap();
+    assert!(detected.at >= Duration::from_millis(600) && detected.at <= carrier_up);
+
+    // S10 after the drop, to within a couple of 10 ms blocks
+    let delay = lost.at - carrier_up;
+    assert!(
+        delay >= Duration::from_millis(500) && delay <= Duration::from_millis(530),
+        "lost after {:?}",
+        delay
+    );
+    let hung_up = events.iter().position(|e| e.kind == ModemEventKind::HungUp).unwrap();
+    assert!(events.iter().position(|e| e.kind == ModemEventKind::CarrierLost).unwrap() < hung_up);
+}
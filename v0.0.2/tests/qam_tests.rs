@@ -2,6 +2,12 @@
 use hsf_softmodem::dsp::fsk::*;
 use hsf_softmodem::dsp::qam_modem::*;
 use hsf_softmodem::dsp::qam::*;
+use hsf_softmodem::dsp::interpolator::{Interpolator, Resampler};
+use hsf_softmodem::dsp::framing::CharFormat;
+use hsf_softmodem::sim::channel::{ChannelSimulator, Impairments};
+use hsf_softmodem::sim::line::LinePreset;
+use hsf_softmodem::dsp::equalizer::{Adaptation, EqualizerConfig, FSEqualizer, LMSEqualizer, Slicer, TapSpacing};
+use num_complex::Complex32;
 use rand::Rng;
 
 /// Add AWGN noise to signal [web:106][web:109]
@@ -135,3 +141,537 @@ fn test_qam_ber_performance() {
         }
     }
 }
+
+#[test]
+fn test_qam16_constellation_is_two_dimensional() {
+    let mut modulator = QAMModulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+    let mut demodulator = QAMDemodulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+
+    // Train on marks, then the data has to come back intact
+    demodulator.demodulate_bytes(&modulator.idle(1200));
+    let test_data: Vec<u8> = (0..200u32).map(|i| (i * 37 + 11) as u8).collect();
+    let mut audio = modulator.modulate_bytes(&test_data);
+    audio.extend(modulator.flush());
+
+    demodulator.enable_tap(400);
+    assert_eq!(demodulator.demodulate_bytes(&audio), test_data);
+    let tap = demodulator.take_tap().unwrap();
+
+    // With a real quadrature carrier every I and Q level shows up
+    for level in [-1.0f32, -1.0 / 3.0, 1.0 / 3.0, 1.0] {
+        assert!(tap.symbols().skip(20).any(|s| (s.decision.re - level).abs() < 1e-4));
+        assert!(tap.symbols().skip(20).any(|s| (s.decision.im - level).abs() < 1e-4));
+    }
+
+    let evm = tap.evm(20).unwrap();
+    assert!(evm < 0.3, "back-to-back EVM {:.3}", evm);
+}
+
+/// Play `signal` through a far-end clock running `ppm` fast
+fn clock_drift(signal: &[f32], ppm: f64) -> Vec<f32> {
+    Resampler::new(1.0 + ppm * 1e-6, Interpolator::polyphase(32, 16)).process(signal)
+}
+
+#[test]
+fn test_qam_timing_recovery_tracks_clock_drift() {
+    for ppm in [-250.0, 250.0] {
+        let mut modulator = QAMModulator::new(QAMMode::V22, 1200.0, 8000.0);
+        let mut demodulator = QAMDemodulator::new(QAMMode::V22, 1200.0, 8000.0);
+
+        // 10 s of data: the clocks slip 1.5 symbols against each other
+        let test_data: Vec<u8> = (0..1500u32).map(|i| (i * 37 + 11) as u8).collect();
+        let mut audio = modulator.modulate_bytes(&test_data);
+        audio.extend(modulator.flush());
+        let received = clock_drift(&audio, ppm);
+
+        demodulator.enable_tap(1000);
+        demodulator.demodulate(&received);
+        let tap = demodulator.take_tap().unwrap();
+
+        assert!(demodulator.is_timing_locked(), "{} ppm: timing not locked", ppm);
+
+        // A fast far end sends shorter symbols, seen as a negative offset
+        let offset = demodulator.clock_offset() as f64 * 1e6;
+        assert!(
+            (offset + ppm).abs() < 0.3 * ppm.abs(),
+            "{} ppm: estimated {:.0} ppm",
+            ppm,
+            offset
+        );
+
+        // DPSK has a constant envelope, so the amplitude at the strobes
+        // (ahead of the equalizer) shows whether we are still sampling on
+        // the pulse peaks
+        let amplitude_error = tap
+            .symbols()
+            .map(|s| (s.pre_eq.norm() - 1.0).abs())
+            .sum::<f32>()
+            / tap.len() as f32;
+        assert!(amplitude_error < 0.05, "{} ppm: amplitude error {:.3}", ppm, amplitude_error);
+    }
+}
+
+#[test]
+fn test_qam_agc_recovers_line_loss() {
+    for loss_db in [-20.0f32, 6.0] {
+        let mut modulator = QAMModulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+        let mut demodulator = QAMDemodulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+
+        let test_data: Vec<u8> = (0..600u32).map(|i| (i * 37 + 11) as u8).collect();
+        let scale = 10f32.powf(loss_db / 20.0);
+        let received: Vec<f32> = modulator
+            .modulate_bytes(&test_data)
+            .iter()
+            .map(|s| s * scale)
+            .collect();
+
+        demodulator.enable_tap(1000);
+        demodulator.demodulate(&received);
+        let tap = demodulator.take_tap().unwrap();
+
+        let level = demodulator.receive_level_dbm0();
+        let expected = modulator.tx_level().dbm0 + loss_db;
+        assert!((level - expected).abs() < 1.0, "{} dB: level {:.1} dBm0, expected {:.1}", loss_db, level, expected);
+
+        // The constellation comes out at its nominal size
+        let rms = (tap.symbols().skip(200).map(|s| s.post_eq.norm_sqr()).sum::<f32>()
+            / (tap.len() - 200) as f32)
+            .sqrt();
+        assert!((rms - (10.0f32 / 9.0).sqrt()).abs() < 0.15, "{} dB: symbol rms {:.3}", loss_db, rms);
+    }
+}
+
+#[test]
+fn test_mode_slicers_match_constellations() {
+    let dpsk = QAMMode::V22.slicer();
+    assert!((dpsk.slice(Complex32::new(0.1, 0.8)) - Complex32::new(0.0, 1.0)).norm() < 1e-6);
+    assert!((dpsk.slice(Complex32::new(-1.3, 0.2)) - Complex32::new(-1.0, 0.0)).norm() < 1e-6);
+
+    // Every V.22bis point slices to itself, and the grid is /3-scaled
+    let qam = QAMMode::V22bis.slicer();
+    for bits in 0..16u8 {
+        let point = map_to_qam16(bits);
+        assert_eq!(qam.slice(point * 1.1), point);
+    }
+    assert_eq!(qam.slice(Complex32::new(0.45, -0.9)), Complex32::new(1.0 / 3.0, -1.0));
+}
+
+#[test]
+fn test_equalizer_trains_then_goes_decision_directed() {
+    let mut rng = rand::thread_rng();
+    let mut equalizer = LMSEqualizer::new(11, 0.01, QAMMode::V22bis.slicer());
+
+    // Symbol-spaced channel with a strong post-cursor echo
+    let channel = [Complex32::new(1.0, 0.0), Complex32::new(0.3, -0.2)];
+    let symbols: Vec<Complex32> = (0..4000).map(|_| map_to_qam16(rng.gen::<u8>())).collect();
+    let received: Vec<Complex32> = (0..symbols.len())
+        .map(|n| channel[0] * symbols[n] + if n > 0 { channel[1] * symbols[n - 1] } else { Complex32::new(0.0, 0.0) })
+        .collect();
+
+    // The centre tap puts the output 5 symbols behind the input; training
+    // symbols are offered for the first 1000 symbols only
+    let delay = 5;
+    let mut errors_after_training = 0;
+    for n in 0..received.len() {
+        let training = (n < 1000 && n >= delay).then(|| symbols[n - delay]);
+        let output = equalizer.equalize(received[n], training);
+        if n >= 2000 && equalizer.slice(output) != symbols[n - delay] {
+            errors_after_training += 1;
+        }
+    }
+
+    assert!(!equalizer.is_training(), "equalizer never left training (mse {:.4})", equalizer.mse());
+    assert!(equalizer.mse() < 0.01, "mse {:.4}", equalizer.mse());
+    assert_eq!(errors_after_training, 0);
+}
+
+#[test]
+fn test_demodulator_goes_decision_directed_with_data_intact() {
+    let text: Vec<u8> = (0..300u32).map(|i| b' ' + (i * 7 % 95) as u8).collect();
+
+    for mode in [QAMMode::V22, QAMMode::Bell212A, QAMMode::V22bis] {
+        let mut modulator = QAMModulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+        let mut demodulator = QAMDemodulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+        let line = Impairments { snr_db: Some(40.0), line: Some(LinePreset::LongLoop), seed: 36, ..Impairments::clean() };
+        let mut channel = ChannelSimulator::new(line, 8000.0);
+
+        // The mode's slicer takes the equalizer off training on the marks
+        demodulator.demodulate_bytes(&channel.process(&modulator.idle(2400)));
+        assert!(demodulator.is_decision_directed(), "{:?}: still training (mse {:.4})", mode, demodulator.equalizer_mse());
+
+        let mut audio = modulator.modulate_bytes(&text);
+        audio.extend(modulator.idle(128));
+        audio.extend(modulator.flush());
+        let received = demodulator.demodulate_bytes(&channel.process(&audio));
+        assert_eq!(String::from_utf8_lossy(&received), String::from_utf8_lossy(&text), "{:?}", mode);
+        assert!(demodulator.is_decision_directed(), "{:?}: fell back to training", mode);
+    }
+}
+
+/// Raised-cosine pulse (roll-off 0.75) at `t` symbols
+fn raised_cosine(t: f32) -> f32 {
+    let sinc = if t.abs() < 1e-6 { 1.0 } else { (std::f32::consts::PI * t).sin() / (std::f32::consts::PI * t) };
+    let d = 1.0 - (1.5 * t).powi(2);
+    if d.abs() < 1e-4 {
+        std::f32::consts::FRAC_PI_4 * sinc
+    } else {
+        sinc * (0.75 * std::f32::consts::PI * t).cos() / d
+    }
+}
+
+/// Run `config` over V.22bis symbols sampled half a symbol off the pulse
+/// peaks through a line with an echo one symbol later. Training symbols are
+/// offered for the first 1000 symbols. Returns the first symbol at which
+/// the MSE dropped below 0.005, and the decision errors after symbol 1500.
+fn run_equalizer(config: EqualizerConfig) -> (Option<usize>, usize, FSEqualizer) {
+    let symbols: Vec<Complex32> = (0..3000usize).map(|i| map_to_qam16(((i * 7 + i * i * 3 + 5) % 16) as u8)).collect();
+    let echo = Complex32::new(0.3, 0.2);
+    let line = |t: f32| -> Complex32 {
+        let first = (t.floor() as i64 - 10).max(0) as usize;
+        let last = (t.floor() as i64 + 10).clamp(0, symbols.len() as i64 - 1) as usize;
+        (first..=last)
+            .map(|k| symbols[k] * (raised_cosine(t - k as f32 - 0.5) + echo * raised_cosine(t - k as f32 - 1.5)))
+            .sum()
+    };
+
+    let mut equalizer = FSEqualizer::new(config, QAMMode::V22bis.slicer());
+    let delay = equalizer.delay();
+    let mut converged_at = None;
+    let mut errors = 0;
+
+    for k in 0..symbols.len() {
+        let inputs = match config.spacing {
+            TapSpacing::Symbol => vec![line(k as f32)],
+            TapSpacing::HalfSymbol => vec![line(k as f32 - 0.5), line(k as f32)],
+        };
+        let training = (k >= delay && k < 1000).then(|| symbols[k - delay]);
+        let output = equalizer.equalize(&inputs, training);
+
+        if converged_at.is_none() && k > delay && equalizer.mse() < 0.005 {
+            converged_at = Some(k);
+        }
+        if k >= 1500 && equalizer.slice(output) != symbols[k - delay] {
+            errors += 1;
+        }
+    }
+
+    (converged_at, errors, equalizer)
+}
+
+#[test]
+fn test_fractionally_spaced_equalizer_tolerates_timing_phase() {
+    let nlms = Adaptation::Nlms { step_size: 0.25 };
+    let symbol_spaced = EqualizerConfig { spacing: TapSpacing::Symbol, span_symbols: 8, adaptation: nlms };
+    let half_spaced = EqualizerConfig { spacing: TapSpacing::HalfSymbol, span_symbols: 8, adaptation: nlms };
+
+    // Sampling between the peaks aliases the band edges together for a
+    // T-spaced equalizer; the T/2 one sees the whole band
+    let (t_converged, _, t_eq) = run_equalizer(symbol_spaced);
+    let (fse_converged, fse_errors, fse) = run_equalizer(half_spaced);
+
+    assert!(t_converged.is_none(), "T-spaced reached mse {:.4}", t_eq.mse());
+    assert!(fse_converged.is_some(), "T/2 stuck at mse {:.4}", fse.mse());
+    assert!(!fse.is_training());
+    assert_eq!(fse_errors, 0);
+    assert!(fse.tap_energy() > 0.5 && fse.tap_energy() < 4.0, "tap energy {:.2}", fse.tap_energy());
+}
+
+#[test]
+fn test_rls_equalizer_converges_faster_than_nlms() {
+    let config = |adaptation| EqualizerConfig { spacing: TapSpacing::HalfSymbol, span_symbols: 8, adaptation };
+    let (nlms_at, nlms_errors, _) = run_equalizer(config(Adaptation::Nlms { step_size: 0.25 }));
+    let (rls_at, rls_errors, rls) = run_equalizer(config(Adaptation::Rls { forgetting: 0.995 }));
+
+    let (nlms_at, rls_at) = (nlms_at.expect("NLMS converged"), rls_at.expect("RLS converged"));
+    assert!(rls_at < nlms_at, "RLS at symbol {}, NLMS at {}", rls_at, nlms_at);
+    assert_eq!(nlms_errors + rls_errors, 0);
+    assert!(rls.mse() < 1e-3, "RLS mse {:.5}", rls.mse());
+}
+
+#[test]
+fn test_fractionally_spaced_equalizers_carry_data_over_loaded_loop() {
+    let text: Vec<u8> = (0..300u32).map(|i| b' ' + (i * 11 % 95) as u8).collect();
+    let config = |adaptation| EqualizerConfig { spacing: TapSpacing::HalfSymbol, span_symbols: 8, adaptation };
+
+    for mode in [QAMMode::V22, QAMMode::V22bis] {
+        for adaptation in [Adaptation::Nlms { step_size: 0.25 }, Adaptation::Rls { forgetting: 0.995 }] {
+            let mut modulator = QAMModulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+            let mut demodulator = QAMDemodulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+            demodulator.set_equalizer(config(adaptation));
+            let line = Impairments { snr_db: Some(40.0), line: Some(LinePreset::LoadedLoop), seed: 37, ..Impairments::clean() };
+            let mut channel = ChannelSimulator::new(line, 8000.0);
+
+            demodulator.demodulate_bytes(&channel.process(&modulator.idle(2400)));
+
+            let mut audio = modulator.modulate_bytes(&text);
+            audio.extend(modulator.idle(128));
+            audio.extend(modulator.flush());
+            let received = demodulator.demodulate_bytes(&channel.process(&audio));
+            assert_eq!(String::from_utf8_lossy(&received), String::from_utf8_lossy(&text), "{:?} {:?}", mode, adaptation);
+        }
+    }
+}
+
+#[test]
+fn test_equalizers_train_in_the_receiver_on_long_and_loaded_loops() {
+    let text: Vec<u8> = (0..300u32).map(|i| b' ' + (i * 11 % 95) as u8).collect();
+    let config = |adaptation| EqualizerConfig { spacing: TapSpacing::HalfSymbol, span_symbols: 8, adaptation };
+
+    for mode in [QAMMode::V22, QAMMode::V22bis] {
+        for preset in [LinePreset::LongLoop, LinePreset::LoadedLoop] {
+            for adaptation in [Adaptation::Nlms { step_size: 0.25 }, Adaptation::Rls { forgetting: 0.995 }] {
+                let mut modulator = QAMModulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+                let mut demodulator = QAMDemodulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+                demodulator.set_equalizer(config(adaptation));
+                let mut channel = ChannelSimulator::new(Impairments::preset(preset), 8000.0);
+
+                // Two seconds of the scrambled-ones training at the preset's noise
+                demodulator.demodulate_bytes(&channel.process(&modulator.idle(1200 * mode.bits_per_symbol())));
+                let label = format!("{:?} {} {:?}", mode, preset.profile().name, adaptation);
+                assert!(demodulator.is_decision_directed(), "{}: still training (mse {:.4})", label, demodulator.equalizer_mse());
+                assert!(demodulator.equalizer_mse() < 0.005, "{}: mse {:.4}", label, demodulator.equalizer_mse());
+
+                let mut audio = modulator.modulate_bytes(&text);
+                audio.extend(modulator.idle(128));
+                audio.extend(modulator.flush());
+                let received = demodulator.demodulate_bytes(&channel.process(&audio));
+                assert_eq!(String::from_utf8_lossy(&received), String::from_utf8_lossy(&text), "{}", label);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_carrier_recovery_pulls_in_frequency_offset() {
+    for (mode, offset_hz) in [(QAMMode::V22, -60.0f32), (QAMMode::V22, 25.0), (QAMMode::V22bis, 25.0)] {
+        // Far end's carrier is off by `offset_hz`
+        let mut modulator = QAMModulator::new(mode, 1200.0 + offset_hz, 8000.0);
+        let mut demodulator = QAMDemodulator::new(mode, 1200.0, 8000.0);
+
+        // Raw data bits: no async framing or mark lead-in
+        let test_bits: Vec<bool> = (0..1500u32)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .flat_map(|byte| (0..8).map(move |bit| (byte >> bit) & 1 == 1))
+            .collect();
+        let mut audio = modulator.modulate(&test_bits);
+        audio.extend(modulator.flush());
+
+        demodulator.enable_tap(10000);
+        demodulator.demodulate(&audio);
+        let tap = demodulator.take_tap().unwrap();
+
+        assert!(demodulator.is_locked(), "{:?} {} Hz: carrier not locked", mode, offset_hz);
+        let estimate = demodulator.carrier_offset();
+        assert!((estimate - offset_hz).abs() < 1.0, "{:?} {} Hz: estimated {:.2} Hz", mode, offset_hz, estimate);
+
+        // Settled constellation: EVM over the last 1000 symbols
+        let tail: Vec<_> = tap.symbols().skip(tap.len() - 1000).collect();
+        let error: f32 = tail.iter().map(|s| s.error().norm_sqr()).sum();
+        let reference: f32 = tail.iter().map(|s| s.decision.norm_sqr()).sum();
+        let evm = (error / reference).sqrt();
+        assert!(evm < 0.05, "{:?} {} Hz: EVM {:.3}", mode, offset_hz, evm);
+    }
+}
+
+#[test]
+fn test_carrier_recovery_does_not_lock_on_noise() {
+    let mut rng = rand::thread_rng();
+    let noise: Vec<f32> = (0..16000).map(|_| rng.gen_range(-0.05..0.05)).collect();
+
+    for mode in [QAMMode::V22, QAMMode::V22bis] {
+        let mut demodulator = QAMDemodulator::new(mode, 1200.0, 8000.0);
+        for chunk in noise.chunks(1000) {
+            demodulator.demodulate(chunk);
+            assert!(!demodulator.is_locked(), "{:?}: locked on noise", mode);
+        }
+        assert!(demodulator.phase_error_variance() > 0.1, "{:?}: variance {:.3}", mode, demodulator.phase_error_variance());
+    }
+}
+
+#[test]
+fn test_carrier_recovery_restarts_when_carrier_arrives() {
+    let text: Vec<u8> = (0..300u32).map(|i| b' ' + (i * 13 % 95) as u8).collect();
+
+    for mode in [QAMMode::V22, QAMMode::V22bis] {
+        for (seed, noise_samples) in [(1, 2400), (2, 2400), (3, 8000), (4, 8000)] {
+            let mut modulator = QAMModulator::new(mode, 1200.0, 8000.0);
+            let mut demodulator = QAMDemodulator::new(mode, 1200.0, 8000.0);
+            let line = Impairments { gain_db: -6.0, snr_db: Some(35.0), seed, ..Impairments::clean() };
+            let mut channel = ChannelSimulator::new(line, 8000.0);
+
+            // Line noise before the far end starts, as an answering modem
+            // hears while the caller is still silent
+            demodulator.demodulate_bytes(&channel.process(&vec![0.0; noise_samples]));
+            demodulator.demodulate_bytes(&channel.process(&modulator.idle(1800)));
+            assert!(demodulator.is_locked(), "{:?} seed {}: not locked after training", mode, seed);
+
+            let mut audio = modulator.modulate_bytes(&text);
+            audio.extend(modulator.flush());
+            let received = demodulator.demodulate_bytes(&channel.process(&audio));
+            assert_eq!(String::from_utf8_lossy(&received), String::from_utf8_lossy(&text), "{:?} seed {}", mode, seed);
+        }
+    }
+}
+
+#[test]
+fn test_qam16_quadrants_are_differential() {
+    let bits: Vec<u8> = (0..64u32).map(|i| (i * 11 + 3) as u8 & 0x0F).collect();
+    let mut previous = 0;
+    let points: Vec<Complex32> = bits
+        .iter()
+        .map(|&b| {
+            previous = qam16_differential_encode(b, previous);
+            map_to_qam16(previous)
+        })
+        .collect();
+
+    // A receiver locked any number of quarter turns out decodes the same
+    // bits once it has seen one symbol
+    for turns in 0..4 {
+        let rotation = Complex32::new(0.0, 1.0).powi(turns);
+        let mut previous = 0;
+        let decoded: Vec<u8> = points
+            .iter()
+            .map(|&p| {
+                let point = slice_qam16(p * rotation);
+                let b = qam16_differential_decode(point, previous);
+                previous = point;
+                b
+            })
+            .collect();
+        assert_eq!(decoded[1..], bits[1..], "{} quarter turns", turns);
+    }
+}
+
+#[test]
+fn test_v22bis_bytes_round_trip() {
+    let text: Vec<u8> = (0..300u32).map(|i| b' ' + (i * 7 % 95) as u8).collect();
+
+    for (offset_hz, snr_db) in [(0.0f32, None), (7.0, Some(30.0)), (-12.0, Some(30.0))] {
+        let mut modulator = QAMModulator::new(QAMMode::V22bis, 1200.0 + offset_hz, 8000.0);
+        let mut demodulator = QAMDemodulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+        let line = Impairments { gain_db: -6.0, snr_db, seed: 38, ..Impairments::clean() };
+        let mut channel = ChannelSimulator::new(line, 8000.0);
+
+        // Train on scrambled marks first, as the V.22bis handshake does; the
+        // equalizer needs about 400 symbols before lock counts
+        demodulator.demodulate_bytes(&channel.process(&modulator.idle(2400)));
+        assert!(demodulator.is_locked(), "{} Hz: not locked after training", offset_hz);
+
+        let mut audio = modulator.modulate_bytes(&text);
+        audio.extend(modulator.flush());
+        let received = demodulator.demodulate_bytes(&channel.process(&audio));
+        assert_eq!(String::from_utf8_lossy(&received), String::from_utf8_lossy(&text), "{} Hz", offset_hz);
+        assert!(demodulator.is_locked(), "{} Hz: lost lock", offset_hz);
+    }
+}
+
+#[test]
+fn test_v14_async_characters_over_v22() {
+    for format in [CharFormat::ASYNC_8N1, CharFormat::ASYNC_7E1] {
+        let mut modulator = QAMModulator::new(QAMMode::V22, 1200.0, 8000.0);
+        let mut demodulator = QAMDemodulator::new(QAMMode::V22, 1200.0, 8000.0);
+        modulator.set_char_format(format);
+        demodulator.set_char_format(format);
+
+        // DTE slightly faster than the line: stop bits get deleted
+        modulator.set_stop_bit_deletion(true);
+        let text: Vec<u8> = (0..199u32).map(|i| b' ' + (i * 7 % 95) as u8).collect();
+        let mut audio = modulator.modulate_bytes(&text);
+        audio.extend(modulator.modulate_break());
+        audio.extend(modulator.flush());
+
+        let line = Impairments { gain_db: -6.0, snr_db: Some(20.0), seed: 14, ..Impairments::clean() };
+        let received = ChannelSimulator::new(line, 8000.0).process(&audio);
+
+        assert_eq!(demodulator.demodulate_bytes(&received), text, "{}", format);
+        let stats = demodulator.framing_stats();
+        assert_eq!(stats.stop_bits_inserted, text.len() / 8, "{}", format);
+        assert_eq!((stats.parity_errors, stats.breaks), (0, 1), "{}", format);
+    }
+}
+
+#[test]
+fn test_flush_delivers_every_character() {
+    let text = b"No idle after this, flush has to carry it all.";
+    for mode in [QAMMode::V22, QAMMode::Bell212A, QAMMode::V22bis] {
+        for sample_rate in [8000.0, 9600.0, 16000.0] {
+            for streamed in [false, true] {
+                let mut modulator = QAMModulator::new(mode, 1200.0, sample_rate);
+                let mut demodulator = QAMDemodulator::new(mode, 1200.0, sample_rate);
+                demodulator.demodulate_bytes(&modulator.idle(2400));
+
+                let mut audio = Vec::new();
+                if streamed {
+                    // Stopped mid-character: flush sends the rest of it first
+                    let mut block = [0.0; 50];
+                    let mut data = &text[..];
+                    while !data.is_empty() {
+                        let (taken, n) = modulator.modulate_bytes_into(data, &mut block);
+                        data = &data[taken..];
+                        audio.extend_from_slice(&block[..n]);
+                    }
+                    loop {
+                        let n = modulator.flush_into(&mut block);
+                        if n == 0 {
+                            break;
+                        }
+                        audio.extend_from_slice(&block[..n]);
+                    }
+                } else {
+                    audio.extend(modulator.modulate_bytes(text));
+                    audio.extend(modulator.flush());
+                }
+                let received = demodulator.demodulate_bytes(&audio);
+                assert_eq!(received, text, "{:?} at {} Hz, streamed {}", mode, sample_rate, streamed);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_fsk_honors_fractional_samples_per_bit() {
+    let modes = [FSKMode::Bell103Originate, FSKMode::Bell103Answer, FSKMode::V21Originate, FSKMode::V21Answer];
+    for sample_rate in [7200.0, 8000.0, 9600.0, 16000.0] {
+        for mode in modes {
+            let mut modulator = FSKModulator::new(mode, 300.0, sample_rate);
+            let mut demodulator = FSKDemodulator::new(mode, 300.0, sample_rate);
+
+            // Bit boundaries don't drift: 1000 bits take 1000 bit periods
+            let bits: Vec<bool> = (0..1000).map(|i| (i * 7 % 11) < 5).collect();
+            let samples_per_bit = sample_rate / 300.0;
+            assert_eq!(modulator.modulate(&bits).len(), (1000.0 * samples_per_bit).round() as usize);
+
+            let text = b"The quick brown fox jumps over the lazy dog";
+            let mut audio = modulator.modulate_bytes(text);
+            audio.extend(modulator.idle(10));
+            let line = Impairments { gain_db: -10.0, snr_db: Some(20.0), seed: 45, ..Impairments::clean() };
+            let received = ChannelSimulator::new(line, sample_rate).process(&audio);
+
+            let decoded = demodulator.demodulate_bytes(&received);
+            assert!(decoded.ends_with(text), "{:?} at {} Hz: {:?}", mode, sample_rate, String::from_utf8_lossy(&decoded));
+        }
+    }
+}
+
+#[test]
+fn test_v22_honors_fractional_samples_per_symbol() {
+    for sample_rate in [7200.0, 8000.0, 9600.0, 16000.0] {
+        let mut modulator = QAMModulator::new(QAMMode::V22, 1200.0, sample_rate);
+        let mut demodulator = QAMDemodulator::new(QAMMode::V22, 1200.0, sample_rate);
+
+        // 600 baud exactly, not the nearest whole number of samples
+        let symbols = 3000;
+        let audio = modulator.modulate(&vec![false; symbols * 2]);
+        let expected = symbols as f32 * sample_rate / 600.0;
+        assert!((audio.len() as f32 - expected).abs() <= 1.0, "{} Hz: {} samples for {}", sample_rate, audio.len(), expected);
+
+        let text: Vec<u8> = (0..120u32).map(|i| b' ' + (i * 13 % 95) as u8).collect();
+        let mut audio = modulator.modulate_bytes(&text);
+        audio.extend(modulator.flush());
+        let line = Impairments { gain_db: -6.0, snr_db: Some(20.0), seed: 45, ..Impairments::clean() };
+        let received = ChannelSimulator::new(line, sample_rate).process(&audio);
+
+        assert_eq!(demodulator.demodulate_bytes(&received), text, "{} Hz", sample_rate);
+    }
+}
@@ -0,0 +1,362 @@
+// tests/sim_tests.rs
+//
+// Channel simulator, WAV I/O and scenario-driven link simulation.
+
+use hsf_softmodem::dsp::fsk::FSKMode;
+use hsf_softmodem::dsp::fsk_detector::{default_detector, FSKDetector};
+use hsf_softmodem::dsp::oscillator::NCO;
+use hsf_softmodem::dsp::{power_to_dbm0, TxLevel};
+use hsf_softmodem::dsp::qam::QAMMode;
+use hsf_softmodem::dsp::qam_modem::{QAMDemodulator, QAMModulator};
+use hsf_softmodem::sim::channel::{ChannelSimulator, Impairments};
+use hsf_softmodem::sim::line::LinePreset;
+use hsf_softmodem::sim::plot::{constellation_svg, eye_svg, symbols_csv, timing_csv, Rail};
+use hsf_softmodem::sim::link::{run_scenario, LineMode, LinkEventKind, Role};
+use hsf_softmodem::sim::scenario::Scenario;
+use hsf_softmodem::sim::wav::{decode_wav, encode_wav_f32};
+
+const V21_SCENARIO: &str = r#"
+name = "v21 test"
+duration_s = 3.0
+
+[payload]
+text = "modemsim"
+
+[originate]
+mode = "v21"
+
+[answer]
+mode = "v21"
+
+[channel]
+gain_db = -6.0
+snr_db = 30.0
+seed = 3
+"#;
+
+#[test]
+fn test_channel_is_deterministic() {
+    let input: Vec<f32> = (0..800).map(|n| (n as f32 * 0.3).sin()).collect();
+    let impairments = Impairments {
+        gain_db: -3.0,
+        snr_db: Some(15.0),
+        signal_dbm0: -10.0,
+        delay_samples: 11,
+        bandwidth_hz: Some(3000.0),
+        line: None,
+        seed: 42,
+    };
+
+    let a = ChannelSimulator::new(impairments, 8000.0).process(&input);
+    let b = ChannelSimulator::new(impairments, 8000.0).process(&input);
+
+    assert_eq!(a, b);
+    assert!(a[..11].iter().all(|&s| s == 0.0), "bulk delay not applied");
+}
+
+#[test]
+fn test_wav_round_trip_is_bit_exact() {
+    let samples = vec![0.0, 1.0, -1.0, 0.123_456_79, f32::MIN_POSITIVE];
+    let (rate, decoded) = decode_wav(&encode_wav_f32(8000, &samples)).unwrap();
+
+    assert_eq!(rate, 8000);
+    assert_eq!(decoded.len(), samples.len());
+    assert!(samples
+        .iter()
+        .zip(decoded.iter())
+        .all(|(a, b)| a.to_bits() == b.to_bits()));
+}
+
+#[test]
+fn test_scenario_parsing() {
+    let scenario = Scenario::from_toml_str(V21_SCENARIO).unwrap();
+    assert_eq!(scenario.originate.mode, LineMode::V21);
+    assert_eq!(scenario.payload.bytes().unwrap(), b"modemsim");
+    assert_eq!(scenario.sample_rate, 8000.0);
+    assert_eq!(scenario.originate.fsk_detector, None);
+
+    // The FSK back end is chosen by name
+    let pll = V21_SCENARIO.replace("[answer]\nmode = \"v21\"", "[answer]\nmode = \"v21\"\nfsk_detector = \"pll\"");
+    assert_eq!(Scenario::from_toml_str(&pll).unwrap().answer.fsk_detector, Some(FSKDetector::PLL));
+    assert!(Scenario::from_toml_str(&pll.replace("\"pll\"", "\"costas\"")).is_err());
+
+    // Unknown keys and ambiguous payloads are rejected
+    assert!(Scenario::from_toml_str(&V21_SCENARIO.replace("gain_db", "gain")).is_err());
+    assert!(Scenario::from_toml_str(
+        &V21_SCENARIO.replace("text = \"modemsim\"", "text = \"x\"\nhex = \"00\"")
+    )
+    .is_err());
+}
+
+#[test]
+fn test_v21_link_simulation() {
+    let scenario = Scenario::from_toml_str(V21_SCENARIO).unwrap();
+    let report = run_scenario(&scenario).unwrap();
+
+    // Both sides hear each other and go to data mode
+    assert!(report.originate.handshake_s.is_some());
+    assert!(report.answer.handshake_s.is_some());
+    assert!(report
+        .events
+        .iter()
+        .any(|e| e.side == Role::Originate && e.kind == LinkEventKind::DataStarted));
+
+    // V.21 channel 1 (originate -> answer) decodes cleanly
+    assert!(report.answer.bits_checked > 300);
+    assert_eq!(report.answer.ber(), Some(0.0));
+}
+
+#[test]
+fn test_shipped_scenarios_run_clean() {
+    for text in [
+        include_str!("../scenarios/v21_clean.toml"),
+        include_str!("../scenarios/v22_long_loop.toml"),
+        include_str!("../scenarios/v22bis_noisy_loop.toml"),
+    ] {
+        let report = run_scenario(&Scenario::from_toml_str(text).unwrap()).unwrap();
+
+        for side in [&report.originate, &report.answer] {
+            assert!(side.bits_checked > 500, "{}: {} only checked {} bits", report.name, side.role, side.bits_checked);
+            assert_eq!(side.ber(), Some(0.0), "{}: {}", report.name, side.role);
+
+            // A receiver starts unlocked, so the first lock change is an acquisition
+            let first = report
+                .events
+                .iter()
+                .find(|e| e.side == side.role && matches!(e.kind, LinkEventKind::LockAcquired | LinkEventKind::LockLost));
+            assert!(
+                first.is_none_or(|e| e.kind == LinkEventKind::LockAcquired),
+                "{}: {} logged {:?} first",
+                report.name,
+                side.role,
+                first
+            );
+        }
+    }
+}
+
+#[test]
+fn test_throughput_counts_only_error_free_data() {
+    // At 1 dB SNR V.22 gets through the handshake but never syncs: a tenth
+    // or more of the bits are wrong, spread through the data, so hardly a
+    // block of it counts
+    let scenario = Scenario::from_toml_str(
+        &V21_SCENARIO.replace("\"v21\"", "\"v22\"").replace("snr_db = 30.0", "snr_db = 1.0"),
+    )
+    .unwrap();
+    let report = run_scenario(&scenario).unwrap();
+    for side in [&report.originate, &report.answer] {
+        assert!(side.ber().is_some_and(|ber| ber > 0.05), "{}: BER {:?}", side.role, side.ber());
+        assert!(side.throughput_bps < 120.0, "{}: {} bps", side.role, side.throughput_bps);
+    }
+
+    // On a clean line every data bit counts
+    let report = run_scenario(&Scenario::from_toml_str(V21_SCENARIO).unwrap()).unwrap();
+    assert!(report.answer.throughput_bps > 250.0, "{} bps", report.answer.throughput_bps);
+}
+
+#[test]
+fn test_snr_is_against_the_received_signal() {
+    // A -10 dBm0 tone behind 12 dB of loss arrives at -22 dBm0
+    let level = TxLevel::new(-10.0);
+    let mut input = vec![0.0; 16000];
+    NCO::new(1004.0, 8000.0, level.sine_amplitude()).generate(&mut input);
+
+    let quiet = Impairments { gain_db: -12.0, signal_dbm0: -10.0, ..Impairments::clean() };
+    let noisy = Impairments { snr_db: Some(20.0), ..quiet };
+    let signal = ChannelSimulator::new(quiet, 8000.0).process(&input);
+    let received = ChannelSimulator::new(noisy, 8000.0).process(&input);
+
+    let mean_square = |x: &mut dyn Iterator<Item = f32>| x.map(|v| v * v).sum::<f32>() / input.len() as f32;
+    let signal_dbm0 = power_to_dbm0(mean_square(&mut signal.iter().copied()));
+    let noise_dbm0 = power_to_dbm0(mean_square(&mut received.iter().zip(&signal).map(|(r, s)| r - s)));
+
+    assert!((signal_dbm0 + 22.0).abs() < 0.1, "{}", signal_dbm0);
+    assert!((signal_dbm0 - noise_dbm0 - 20.0).abs() < 0.2, "{} {}", signal_dbm0, noise_dbm0);
+}
+
+/// Steady-state level in dB of a tone after the (noise-free) line shape
+fn tone_level_db(preset: LinePreset, freq: f32) -> f32 {
+    let impairments = Impairments {
+        line: Some(preset),
+        ..Impairments::default()
+    };
+    let mut line = ChannelSimulator::new(impairments, 8000.0);
+    let mut nco = NCO::new(freq, 8000.0, 1.0);
+
+    let mut input = vec![0.0; 8000];
+    nco.generate(&mut input);
+    let output = line.process(&input);
+
+    let tail = &output[4000..];
+    let power = tail.iter().map(|x| x * x).sum::<f32>() / tail.len() as f32;
+    10.0 * (power / 0.5).log10()
+}
+
+#[test]
+fn test_line_preset_attenuation_profiles() {
+    // In-band the flat line is transparent
+    assert!(tone_level_db(LinePreset::Flat, 1004.0).abs() < 0.5);
+
+    // Slope between 1004 and 2804 Hz follows each preset's table
+    for preset in LinePreset::ALL {
+        let profile = preset.profile();
+        let expected = profile
+            .attenuation
+            .iter()
+            .find(|&&(f, _)| f == 2804.0)
+            .map(|&(_, db)| db)
+            .unwrap_or(0.0);
+        let slope = tone_level_db(preset, 1004.0) - tone_level_db(preset, 2804.0);
+        assert!(
+            (slope - expected).abs() < 0.75,
+            "{}: 1004-2804 Hz slope {:.2} dB, expected {:.2} dB",
+            profile.name,
+            slope,
+            expected
+        );
+    }
+
+    // Out of band is stopped by the channel-bank skirts
+    assert!(tone_level_db(LinePreset::ShortLoop, 3800.0) < -20.0);
+}
+
+/// Send 200 bytes over a preset after 1.5 s of training; returns the BER
+/// at the best alignment and how many bytes came out
+fn preset_performance(mode: QAMMode, preset: LinePreset) -> (f32, usize) {
+    let mut modulator = QAMModulator::new(mode, 1200.0, 8000.0);
+    let mut demodulator = QAMDemodulator::new(mode, 1200.0, 8000.0);
+    let mut line = ChannelSimulator::new(Impairments::preset(preset), 8000.0);
+
+    // Train on marks first; the satellite hop's 270 ms passes in the lead-in
+    demodulator.demodulate_bytes(&line.process(&modulator.idle(1800 * mode.bits_per_symbol())));
+
+    let test_data: Vec<u8> = (0..200u32).map(|i| (i * 37 + 11) as u8).collect();
+    let mut audio = modulator.modulate_bytes(&test_data);
+    audio.extend(modulator.flush());
+    audio.extend(std::iter::repeat_n(0.0, 4000));
+    let received = demodulator.demodulate_bytes(&line.process(&audio));
+    if received.len() < test_data.len() {
+        return (1.0, received.len());
+    }
+
+    // Score at the alignment with the fewest errors (line delay varies)
+    let n = test_data.len();
+    let bit_errors: u32 = (0..=received.len() - n)
+        .map(|offset| {
+            test_data
+                .iter()
+                .zip(&received[offset..offset + n])
+                .map(|(a, b)| (a ^ b).count_ones())
+                .sum()
+        })
+        .min()
+        .unwrap();
+    (bit_errors as f32 / (n * 8) as f32, received.len())
+}
+
+#[test]
+fn test_qam_performance_per_line_preset() {
+    // Worst BER allowed on each preset, at the preset's noise seed
+    const MAX_BER: f32 = 1e-3;
+    let presets = [
+        LinePreset::Flat,
+        LinePreset::ShortLoop,
+        LinePreset::LongLoop,
+        LinePreset::LoadedLoop,
+        LinePreset::SatelliteHop,
+    ];
+
+    for mode in [QAMMode::V22, QAMMode::V22bis] {
+        for preset in presets {
+            let (ber, bytes) = preset_performance(mode, preset);
+            println!("{:?} {:<24} BER = {:.4} ({} bytes received)", mode, preset.profile().name, ber, bytes);
+            assert!(bytes >= 200, "{:?} {}: only {} bytes received", mode, preset.profile().name, bytes);
+            assert!(ber <= MAX_BER, "{:?} {}: BER {:.4} over {}", mode, preset.profile().name, ber, MAX_BER);
+        }
+    }
+}
+
+#[test]
+fn test_qam_tap_capture_and_export() {
+    let mut modulator = QAMModulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+    let mut demodulator = QAMDemodulator::new(QAMMode::V22bis, 1200.0, 8000.0);
+
+    let data: Vec<u8> = (0..100u32).map(|i| (i * 37 + 11) as u8).collect();
+    let audio = modulator.modulate_bytes(&data);
+
+    // Off by default; when on it keeps only the newest symbols
+    assert!(demodulator.tap().is_none());
+    demodulator.enable_tap(64);
+    demodulator.demodulate(&audio);
+    let tap = demodulator.take_tap().unwrap();
+    assert!(demodulator.tap().is_none());
+
+    assert_eq!(tap.len(), 64);
+    let first = tap.symbols().next().unwrap().index;
+    assert_eq!(tap.symbols().last().unwrap().index - first, 63);
+    assert!(first > 0, "older symbols should have been dropped");
+    assert!(tap.timing_samples().all(|t| t.symbol >= first && t.phase > 0.0 && t.phase <= 1.0));
+
+    // Every decision is a 16-QAM point
+    assert!(tap.symbols().all(|s| {
+        let (i, q) = (s.decision.re * 3.0, s.decision.im * 3.0);
+        [i, q].iter().all(|v| [-3.0, -1.0, 1.0, 3.0].iter().any(|p| (v - p).abs() < 1e-4))
+    }));
+
+    let csv = symbols_csv(&tap);
+    assert!(csv.starts_with("index,pre_eq_i,"));
+    assert_eq!(csv.lines().count(), 65);
+    assert_eq!(timing_csv(&tap).lines().count(), tap.timing_samples().count() + 1);
+
+    let constellation = constellation_svg(&tap, 0);
+    assert!(constellation.starts_with("<svg") && constellation.trim_end().ends_with("</svg>"));
+    assert_eq!(constellation.matches("<circle").count(), 128);
+
+    let eye = eye_svg(&tap, Rail::Quadrature, 0);
+    assert!(eye.contains("<polyline"));
+}
+
+/// Link BER for one FSK detector, over a few noise seeds
+fn fsk_detector_ber(mode: &str, detector: FSKDetector, snr_db: f32) -> f64 {
+    let (mut errors, mut checked) = (0, 0);
+    for seed in 1..=3 {
+        let toml = format!(
+            "duration_s = 6.0\n\
+             [payload]\nrandom_bytes = 64\n\
+             [originate]\nmode = \"{mode}\"\nfsk_detector = \"{detector}\"\n\
+             [answer]\nmode = \"{mode}\"\nfsk_detector = \"{detector}\"\n\
+             [channel]\nsnr_db = {snr_db}\nseed = {seed}\n"
+        );
+        let report = run_scenario(&Scenario::from_toml_str(&toml).unwrap()).unwrap();
+        errors += report.originate.bit_errors + report.answer.bit_errors;
+        checked += report.originate.bits_checked + report.answer.bits_checked;
+    }
+    assert!(checked > 0, "{} {}: no data compared", mode, detector);
+    errors as f64 / checked as f64
+}
+
+#[test]
+fn test_fsk_detector_ber_comparison() {
+    // Near the knee, where the back ends differ: about -2 dB against the
+    // 0 dBm0 signal, as the channel's SNR is relative to full scale
+    let snr_db = 1.0;
+
+    for (mode, fsk_mode) in [("bell103", FSKMode::Bell103Originate), ("v21", FSKMode::V21Originate)] {
+        let results: Vec<(FSKDetector, f64)> = FSKDetector::ALL
+            .into_iter()
+            .map(|detector| (detector, fsk_detector_ber(mode, detector, snr_db)))
+            .collect();
+
+        println!("{} at {} dB SNR:", mode, snr_db);
+        for (detector, ber) in &results {
+            println!("  {:<14} BER {:.2e}", detector.to_string(), ber);
+        }
+
+        // Every back end works, and the default is (close to) the best
+        let best = results.iter().map(|&(_, ber)| ber).fold(f64::MAX, f64::min);
+        let default = results.iter().find(|(d, _)| *d == default_detector(fsk_mode)).unwrap().1;
+        assert!(results.iter().all(|&(_, ber)| ber < 0.05), "{}: {:?}", mode, results);
+        assert!(default <= best * 1.25 + 1e-4, "{}: default BER {:.2e}, best {:.2e}", mode, default, best);
+    }
+}
@@ -0,0 +1,146 @@
+// tests/simd_tests.rs
+//
+// Every SIMD level this CPU runs, against the scalar path on the same
+// input. The bank kernels must match bit for bit; the dot products to
+// rounding.
+use hsf_softmodem::dsp::equalizer::{Adaptation, EqualizerConfig, FSEqualizer, Slicer, TapSpacing};
+use hsf_softmodem::dsp::filters::{BiquadBank, BiquadFilter};
+use hsf_softmodem::dsp::goertzel::{GoertzelBank, GoertzelDetector};
+use hsf_softmodem::dsp::qam::QAMMode;
+use hsf_softmodem::dsp::simd::SimdLevel;
+use num_complex::Complex32;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn complex_noise(rng: &mut StdRng, len: usize) -> Vec<Complex32> {
+    (0..len).map(|_| Complex32::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))).collect()
+}
+
+#[test]
+fn test_simd_level_detection() {
+    let detected = SimdLevel::detect();
+    assert!(detected.is_supported());
+    assert_eq!(SimdLevel::detect(), detected);
+    assert_eq!(SimdLevel::supported()[0], SimdLevel::Scalar);
+    assert_eq!(SimdLevel::supported().last(), Some(&detected));
+    for level in SimdLevel::ALL {
+        assert_eq!(level.name().parse::<SimdLevel>(), Ok(level));
+        assert_eq!(BiquadBank::new(&[]).set_simd_level(level).is_ok(), level.is_supported(), "{}", level);
+    }
+    assert!("mmx".parse::<SimdLevel>().is_err());
+}
+
+#[test]
+fn test_simd_complex_kernels_match_scalar() {
+    let mut rng = StdRng::seed_from_u64(48);
+    for len in [0, 1, 3, 4, 7, 16, 33, 64] {
+        let a = complex_noise(&mut rng, len);
+        let b = complex_noise(&mut rng, len);
+        let error = Complex32::new(0.3, -0.2);
+
+        let dot = SimdLevel::Scalar.complex_dot(&a, &b);
+        let energy = SimdLevel::Scalar.complex_energy(&a);
+        let mut taps = b.clone();
+        SimdLevel::Scalar.complex_axpy_conj(&mut taps, &a, error, 0.05);
+
+        for level in SimdLevel::supported() {
+            assert!((level.complex_dot(&a, &b) - dot).norm() < 1e-5, "{} dot, {} taps", level, len);
+            assert!((level.complex_energy(&a) - energy).abs() < 1e-5, "{} energy, {} taps", level, len);
+            let mut simd_taps = b.clone();
+            level.complex_axpy_conj(&mut simd_taps, &a, error, 0.05);
+            for (x, y) in simd_taps.iter().zip(&taps) {
+                assert!((x - y).norm() < 1e-6, "{} update, {} taps", level, len);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_biquad_bank_is_bit_exact() {
+    // 11 lanes: a full AVX2 vector, then SSE2/NEON and scalar tails
+    let designs: Vec<BiquadFilter> = (0..11)
+        .map(|i| match i % 3 {
+            0 => BiquadFilter::bandpass(600.0 + 150.0 * i as f32, 300.0, 8000.0),
+            1 => BiquadFilter::lowpass(400.0 + 200.0 * i as f32, 0.707, 8000.0),
+            _ => BiquadFilter::bandpass(1200.0, 50.0 * i as f32, 9600.0),
+        })
+        .collect();
+    let mut rng = StdRng::seed_from_u64(48);
+    let frames = 500;
+    let input: Vec<f32> = (0..frames * designs.len()).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+    let mut expected = vec![0.0; input.len()];
+    let mut filters = designs.clone();
+    for (lane, filter) in filters.iter_mut().enumerate() {
+        for frame in 0..frames {
+            let i = frame * designs.len() + lane;
+            expected[i] = filter.process(input[i]);
+        }
+    }
+
+    for level in SimdLevel::supported() {
+        let mut bank = BiquadBank::new(&designs);
+        bank.set_simd_level(level).unwrap();
+        assert_eq!(bank.len(), 11);
+        // Two calls, to carry state across
+        let mut output = vec![0.0; input.len()];
+        let split = 123 * designs.len();
+        bank.process_frames(&input[..split], &mut output[..split]);
+        bank.process_frames(&input[split..], &mut output[split..]);
+        for (i, (a, b)) in output.iter().zip(&expected).enumerate() {
+            assert_eq!(a.to_bits(), b.to_bits(), "{}: lane {} frame {}", level, i % 11, i / 11);
+        }
+    }
+}
+
+#[test]
+fn test_goertzel_bank_is_bit_exact() {
+    let freqs = [697.0, 770.0, 852.0, 941.0, 1209.0, 1336.0, 1477.0, 1633.0, 2100.0];
+    let mut rng = StdRng::seed_from_u64(48);
+    let input: Vec<f32> = (0..205).map(|_| rng.gen_range(-1.0..1.0)).collect();
+
+    for level in SimdLevel::supported() {
+        let mut bank = GoertzelBank::new(&freqs, 8000.0, 205);
+        bank.set_simd_level(level).unwrap();
+        bank.process_block(&input[..100]);
+        assert!(!bank.is_complete());
+        bank.process_block(&input[100..]);
+        assert!(bank.is_complete());
+
+        for (index, &freq) in freqs.iter().enumerate() {
+            let mut detector = GoertzelDetector::new(freq, 8000.0, 205);
+            input.iter().for_each(|&x| detector.process_sample(x));
+            assert_eq!(bank.magnitude_squared(index).to_bits(), detector.magnitude_squared().to_bits(), "{} at {} Hz", level, freq);
+        }
+    }
+}
+
+#[test]
+fn test_equalizer_simd_matches_scalar() {
+    let mode = QAMMode::V22bis;
+    let mut rng = StdRng::seed_from_u64(48);
+    let slicer = mode.slicer();
+    // Symbols through a mild two-path channel, T/2 samples
+    let symbols: Vec<Complex32> = (0..600).map(|_| slicer.slice(Complex32::new(rng.gen_range(-3.0..3.0), rng.gen_range(-3.0..3.0)))).collect();
+    let channel = |i: usize| {
+        let current = symbols[i];
+        let previous = if i > 0 { symbols[i - 1] } else { Complex32::new(0.0, 0.0) };
+        [(current + previous) * 0.5, current + previous * Complex32::new(0.2, -0.1)]
+    };
+
+    for adaptation in [Adaptation::Lms { step_size: 0.01 }, Adaptation::Nlms { step_size: 0.25 }, Adaptation::Rls { forgetting: 0.99 }] {
+        let config = EqualizerConfig { spacing: TapSpacing::HalfSymbol, span_symbols: 8, adaptation };
+        let mut scalar = FSEqualizer::new(config, mode.slicer());
+        scalar.set_simd_level(SimdLevel::Scalar).unwrap();
+        let mut simd = FSEqualizer::new(config, mode.slicer());
+        assert_eq!(simd.simd_level(), SimdLevel::detect());
+
+        for i in 0..symbols.len() {
+            let training = (i < 300).then(|| symbols[i.saturating_sub(scalar.delay())]);
+            let a = scalar.equalize(&channel(i), training);
+            let b = simd.equalize(&channel(i), training);
+            assert!((a - b).norm() < 1e-3, "{:?}, symbol {}: {} vs {}", adaptation, i, a, b);
+        }
+        assert!((scalar.mse() - simd.mse()).abs() < 1e-4, "{:?}", adaptation);
+    }
+}
@@ -0,0 +1,223 @@
+// tests/streaming_tests.rs
+//
+// The `*_into` calls over caller buffers, against the allocating calls
+// on the same input. Modulator audio must match bit for bit however the
+// output is chunked.
+use hsf_softmodem::dsp::bits::{BitReader, BitWriter};
+use hsf_softmodem::dsp::fsk::*;
+use hsf_softmodem::dsp::qam::QAMMode;
+use hsf_softmodem::dsp::qam_modem::{QAMDemodulator, QAMModulator};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const TEXT: &[u8] = b"The quick brown fox jumps over the lazy dog 0123456789";
+
+fn assert_bit_identical(a: &[f32], b: &[f32]) {
+    assert_eq!(a.len(), b.len());
+    for (i, (x, y)) in a.iter().zip(b).enumerate() {
+        assert_eq!(x.to_bits(), y.to_bits(), "sample {}", i);
+    }
+}
+
+#[test]
+fn test_bit_writer_packs_lsb_first() {
+    let mut storage = [0xff; 2];
+    let mut writer = BitWriter::new(&mut storage);
+    assert_eq!(writer.capacity(), 16);
+    for &bit in &[true, false, true, true, false, false, false, false, true, true] {
+        assert!(writer.push(bit));
+    }
+    assert_eq!(writer.len(), 10);
+    assert_eq!(writer.remaining(), 6);
+    assert_eq!(writer.get(3), Some(true));
+    assert_eq!(writer.get(10), None);
+    // Stale bits in the caller's storage are cleared as bytes start
+    assert_eq!(writer.as_bytes(), &[0x0d, 0x03]);
+    assert_eq!(writer.reader().collect::<Vec<_>>().len(), 10);
+
+    while writer.push(false) {}
+    assert!(writer.is_full());
+    writer.clear();
+    assert!(writer.is_empty());
+
+    let reader = BitReader::new(&[0xa5]);
+    assert_eq!(reader.len(), 8);
+    let bits: Vec<bool> = reader.collect();
+    assert_eq!(bits, [true, false, true, false, false, true, false, true]);
+    assert_eq!(BitReader::with_len(&[0xff], 3).count(), 3);
+}
+
+#[test]
+fn test_fsk_modulate_into_matches_modulate() {
+    let mut rng = StdRng::seed_from_u64(49);
+    let bits: Vec<bool> = (0..300).map(|_| rng.gen()).collect();
+    let packed = pack(&bits);
+    // 24 samples a bit, then a fractional 36.75
+    for sample_rate in [7200.0, 11025.0] {
+        let mut reference = FSKModulator::new(FSKMode::Bell103Originate, 300.0, sample_rate);
+        let expected = reference.modulate(&bits);
+
+        let mut modulator = FSKModulator::new(FSKMode::Bell103Originate, 300.0, sample_rate);
+        assert_eq!(modulator.samples_for_bits(bits.len()), expected.len());
+        // Blocks that end mid-bit
+        let mut audio = Vec::new();
+        let mut block = [0.0; 13];
+        let mut source = BitReader::with_len(&packed, bits.len());
+        loop {
+            let n = modulator.modulate_into(&mut source, &mut block);
+            if n == 0 {
+                break;
+            }
+            audio.extend_from_slice(&block[..n]);
+        }
+        assert_bit_identical(&audio, &expected);
+    }
+}
+
+#[test]
+fn test_fsk_bytes_into_round_trip() {
+    let mut reference = FSKModulator::new(FSKMode::Bell103Originate, 300.0, 9600.0);
+    let expected = reference.modulate_bytes(TEXT);
+
+    let mut modulator = FSKModulator::new(FSKMode::Bell103Originate, 300.0, 9600.0);
+    let mut audio = Vec::new();
+    let mut block = [0.0; 100];
+    let mut data = TEXT;
+    loop {
+        let (taken, n) = modulator.modulate_bytes_into(data, &mut block);
+        data = &data[taken..];
+        if n == 0 {
+            break;
+        }
+        audio.extend_from_slice(&block[..n]);
+    }
+    assert_bit_identical(&audio, &expected);
+    audio.extend(modulator.idle(4));
+
+    let mut demodulator = FSKDemodulator::new(FSKMode::Bell103Originate, 300.0, 9600.0);
+    let mut received = Vec::new();
+    let mut out = [0u8; 3];
+    for chunk in audio.chunks(97) {
+        let mut rest = chunk;
+        while !rest.is_empty() {
+            let (consumed, written) = demodulator.demodulate_bytes_into(rest, &mut out);
+            received.extend_from_slice(&out[..written]);
+            rest = &rest[consumed..];
+        }
+    }
+    assert!(received.ends_with(TEXT), "{:?}", String::from_utf8_lossy(&received));
+}
+
+#[test]
+fn test_fsk_demodulate_into_matches_demodulate() {
+    let mut modulator = FSKModulator::new(FSKMode::Bell103Answer, 300.0, 8000.0);
+    let audio = modulator.modulate_bytes(TEXT);
+    let expected = FSKDemodulator::new(FSKMode::Bell103Answer, 300.0, 8000.0).demodulate(&audio);
+
+    let mut demodulator = FSKDemodulator::new(FSKMode::Bell103Answer, 300.0, 8000.0);
+    // Too small for the whole run: drain it as it fills
+    let mut storage = [0u8; 8];
+    let mut received = Vec::new();
+    let mut rest = &audio[..];
+    while !rest.is_empty() {
+        let mut bits = BitWriter::new(&mut storage);
+        let consumed = demodulator.demodulate_into(rest, &mut bits);
+        received.extend(bits.reader());
+        rest = &rest[consumed..];
+    }
+    assert_eq!(received, expected);
+}
+
+#[test]
+fn test_qam_modulate_into_matches_modulate() {
+    let mut rng = StdRng::seed_from_u64(49);
+    let bits: Vec<bool> = (0..800).map(|_| rng.gen()).collect();
+    for mode in [QAMMode::Bell212A, QAMMode::V22bis] {
+        let mut reference = QAMModulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+        let mut expected = reference.modulate(&bits);
+        expected.extend(reference.flush());
+
+        let mut modulator = QAMModulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+        let mut audio = Vec::new();
+        let mut block = [0.0; 17];
+        // Bits fed a few at a time, so symbols straddle calls
+        for chunk in bits.chunks(3) {
+            let mut source = chunk.iter().copied();
+            loop {
+                let n = modulator.modulate_into(&mut source, &mut block);
+                audio.extend_from_slice(&block[..n]);
+                if n < block.len() {
+                    break;
+                }
+            }
+        }
+        loop {
+            let n = modulator.flush_into(&mut block);
+            if n == 0 {
+                break;
+            }
+            audio.extend_from_slice(&block[..n]);
+        }
+        assert_bit_identical(&audio, &expected);
+    }
+}
+
+#[test]
+fn test_qam_bytes_into_round_trip() {
+    let mode = QAMMode::V22;
+    let mut modulator = QAMModulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+    let mut audio = Vec::new();
+    let mut block = [0.0; 64];
+    let mut data = TEXT;
+    loop {
+        let (taken, n) = modulator.modulate_bytes_into(data, &mut block);
+        data = &data[taken..];
+        if n == 0 {
+            break;
+        }
+        audio.extend_from_slice(&block[..n]);
+    }
+    // Flush finishes the last symbol and clears the receiver
+    loop {
+        let n = modulator.flush_into(&mut block);
+        if n == 0 {
+            break;
+        }
+        audio.extend_from_slice(&block[..n]);
+    }
+
+    let mut reference = QAMDemodulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+    let expected_bits = reference.demodulate(&audio);
+
+    let mut demodulator = QAMDemodulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+    let mut storage = [0u8; 16];
+    let mut bits = Vec::new();
+    let mut rest = &audio[..];
+    while !rest.is_empty() {
+        let mut writer = BitWriter::new(&mut storage);
+        let consumed = demodulator.demodulate_into(rest, &mut writer);
+        bits.extend(writer.reader());
+        rest = &rest[consumed..];
+    }
+    assert_eq!(bits, expected_bits);
+
+    let mut demodulator = QAMDemodulator::new(mode, mode.carrier_freq_originate(), 8000.0);
+    let mut received = Vec::new();
+    let mut out = [0u8; 4];
+    let mut rest = &audio[..];
+    while !rest.is_empty() {
+        let (consumed, written) = demodulator.demodulate_bytes_into(rest, &mut out);
+        received.extend_from_slice(&out[..written]);
+        rest = &rest[consumed..];
+    }
+    assert!(received.ends_with(TEXT), "{:?}", String::from_utf8_lossy(&received));
+}
+
+fn pack(bits: &[bool]) -> Vec<u8> {
+    let mut bytes = vec![0u8; bits.len().div_ceil(8)];
+    let mut writer = BitWriter::new(&mut bytes);
+    bits.iter().for_each(|&bit| {
+        writer.push(bit);
+    });
+    bytes
+}